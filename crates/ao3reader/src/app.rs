@@ -7,9 +7,6 @@ use std::sync::mpsc::{self, Receiver, Sender};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 use ao3reader_core::view::works::HistoryView;
-use reqwest::blocking::Client;
-use reqwest::redirect::Policy;
-use reqwest::StatusCode;
 
 use ao3reader_core::anyhow::{Error, Context as ResultExt};
 use ao3reader_core::chrono::Local;
@@ -17,20 +14,22 @@ use ao3reader_core::framebuffer::{Framebuffer, KoboFramebuffer1, KoboFramebuffer
 use ao3reader_core::view::{View, Event, EntryId, EntryKind, ViewId, AppCmd, RenderData, RenderQueue, UpdateData};
 use ao3reader_core::view::{handle_event, process_render_queue, wait_for_all};
 use ao3reader_core::view::common::{locate, locate_by_id, transfer_notifications, overlapping_rectangle};
+use ao3reader_core::view::common::{show_loading, hide_loading};
 use ao3reader_core::view::common::{toggle_input_history_menu, toggle_keyboard_layout_menu};
 use ao3reader_core::view::frontlight::FrontlightWindow;
 use ao3reader_core::view::menu::{Menu, MenuKind};
 use ao3reader_core::view::dictionary::Dictionary as DictionaryApp;
 use ao3reader_core::view::touch_events::TouchEvents;
 use ao3reader_core::view::rotation_values::RotationValues;
-use ao3reader_core::document::sys_info_as_html;
+use ao3reader_core::document::{sys_info_as_html, outbox_as_html};
 use ao3reader_core::input::{DeviceEvent, PowerSource, ButtonCode, ButtonStatus, VAL_RELEASE, VAL_PRESS};
 use ao3reader_core::input::{raw_events, device_events, usb_events, display_rotate_event, button_scheme_event};
 use ao3reader_core::gesture::{GestureEvent, gesture_events};
-use ao3reader_core::helpers::{load_toml, save_toml, get_url};
+use ao3reader_core::helpers::{load_toml, save_toml};
 use ao3reader_core::settings::{ButtonScheme, Settings, SETTINGS_PATH, RotationLock, IntermKind};
 use ao3reader_core::geom::{Rectangle, DiagDir, Region};
 use ao3reader_core::view::works::{Works, IndexType};
+use ao3reader_core::view::works::workindex::FetchedPages;
 use ao3reader_core::view::reader::Reader;
 use ao3reader_core::view::dialog::Dialog;
 use ao3reader_core::view::home::Home;
@@ -38,8 +37,10 @@ use ao3reader_core::view::overlay::about::About;
 use ao3reader_core::view::intermission::Intermission;
 use ao3reader_core::view::notification::Notification;
 use ao3reader_core::device::{CURRENT_DEVICE, Orientation};
-use ao3reader_core::http::update_session;
+use ao3reader_core::http::{update_session, clear_session, Ao3Error};
+use ao3reader_core::http::outbox::Ao3Action;
 use ao3reader_core::context::Context;
+use ao3reader_core::http::pool::FetchPool;
 
 pub const APP_NAME: &str = "AO3 Reader";
 const FB_DEVICE: &str = "/dev/fb0";
@@ -122,6 +123,60 @@ fn resume(id: TaskId, tasks: &mut Vec<Task>, view: &mut dyn View, hub: &Sender<E
     }
 }
 
+// Marks a work or updates a subscription right away, or once AO3 can be reached.
+fn send_action(action: Ao3Action, title: String, fetch_pool: &mut FetchPool, hub: &Sender<Event>, context: &Context) {
+    if context.online {
+        fetch_pool.send_action(&context.client, action, title);
+    } else {
+        let res = Err(Ao3Error::Network("offline".to_string()));
+        hub.send(Event::ActionSent(Box::new(action), title, res)).ok();
+    }
+}
+
+// Shows what AO3 answered, and has the lists that changed fetched again.
+#[allow(clippy::too_many_arguments)]
+fn action_sent(action: Ao3Action, title: String, res: Result<Vec<String>, Ao3Error>, view: &mut dyn View, history: &mut [HistoryItem], hub: &Sender<Event>, rq: &mut RenderQueue, context: &mut Context) {
+    let (evt, queued, failed) = match action {
+        Ao3Action::Subscribe { .. } | Ao3Action::Unsubscribe { .. } =>
+            (Event::SubscriptionsChanged, "The subscription will be updated once AO3 can be reached.",
+             "Sorry, we were unable to update the subscription."),
+        _ => (Event::MarkedForLaterChanged, "The work will be marked once AO3 can be reached.",
+              "Sorry, we were unable to mark the work."),
+    };
+    let messages = match res {
+        Ok(messages) => {
+            if view.is::<Works>() {
+                view.handle_event(&evt, hub, &mut VecDeque::new(), rq, context);
+            }
+            for item in history.iter_mut().filter(|item| item.view.is::<Works>()) {
+                item.view.handle_event(&evt, hub, &mut VecDeque::new(), &mut RenderQueue::new(), context);
+            }
+            messages
+        },
+        Err(e) if e.is_transient() => {
+            context.outbox.push(action, title);
+            vec![queued.to_string()]
+        },
+        Err(e) => vec![format!("{} {}", failed, e)],
+    };
+    for msg in messages {
+        let notif = Notification::new(msg, hub, rq, context);
+        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+    }
+}
+
+// Lists fetched again after an action changed them on AO3.
+fn reload_index(fetched: &FetchedPages, view: &mut dyn View, history: &mut [HistoryItem], hub: &Sender<Event>, rq: &mut RenderQueue) {
+    if let Some(works) = view.downcast_mut::<Works>() {
+        works.take_pages(fetched, hub, rq);
+    }
+    for item in history.iter_mut() {
+        if let Some(works) = item.view.downcast_mut::<Works>() {
+            works.take_pages(fetched, hub, &mut RenderQueue::new());
+        }
+    }
+}
+
 fn power_off(view: &mut dyn View, history: &mut Vec<HistoryItem>, updating: &mut Vec<UpdateData>, context: &mut Context) {
     let (tx, _rx) = mpsc::channel();
     view.handle_event(&Event::Back, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), context);
@@ -189,7 +244,9 @@ pub fn run() -> Result<(), Error> {
     // Similarly, can we open the AO3 Reader intantly with a loading
     // page that indicates setup status, instead of freezing the Kobo screen?
     // Ideally AO3 Reader UI would be much snappier
-    context.client.renew_login();
+    if let Err(e) = context.client.renew_login() {
+        println!("Can't renew login: {}", e);
+    }
 
     // TODO - these do not seem to be used in AO3 Reader since it does not
     // actually import libraries.  Leaving for now, but skipping testing
@@ -198,6 +255,7 @@ pub fn run() -> Result<(), Error> {
     }
     context.load_dictionaries();
     context.load_keyboard_layouts();
+    context.load_selectors();
 
     // Kobo inputs that are not mimocked in the emuator
     // Skipping teting for now
@@ -287,9 +345,14 @@ pub fn run() -> Result<(), Error> {
 
     let mut tasks: Vec<Task> = Vec::new();
     let mut history: Vec<HistoryItem> = Vec::new();
+    let mut fetch_pool = FetchPool::new(&tx);
+    // Whether to edit the bookmark of the work being opened, when asked from an index.
+    let mut pending_bookmark = false;
+    // The indexes being fetched again, after an action changed them.
     let mut rq = RenderQueue::new();
     let mut view: Box<dyn View> = Box::new(Home::new(context.fb.rect(), &mut rq,
-            context.settings.time_format.clone(), &mut context.fonts, &mut context.battery, context.settings.frontlight, context.client.logged_in, &context.settings.ao3.faves));
+            context.settings.time_format.clone(), &mut context.fonts, &mut context.battery, context.settings.frontlight, context.client.logged_in(),
+            context.settings.ao3.username.as_deref(), &context.settings.ao3.faves));
 
     let mut updating = Vec::new();
     let current_dir = env::current_dir()?;
@@ -304,6 +367,9 @@ pub fn run() -> Result<(), Error> {
     schedule_task(TaskId::CheckBattery, Event::CheckBattery,
                   BATTERY_REFRESH_INTERVAL, &tx, &mut tasks);
     tx.send(Event::WakeUp).ok();
+    if context.settings.ao3.secrets_unreadable {
+        tx.send(Event::Notify("Your saved AO3 login couldn't be read. Please log in again.".to_string())).ok();
+    }
 
     while let Ok(evt) = rx.recv() {
         match evt {
@@ -392,6 +458,9 @@ pub fn run() -> Result<(), Error> {
                                                       &tx, &mut rq, &mut context);
                         context.online = true;
                         view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                        if let Some(actions) = context.outbox.start_replay() {
+                            fetch_pool.replay_outbox(&context.client, actions);
+                        }
                         if view.is::<Works>() {
                             view.handle_event(&evt, &tx, &mut bus, &mut rq, &mut context);
                         } else if let Some(entry) = history.get_mut(0).filter(|entry| entry.view.is::<Works>()) {
@@ -457,6 +526,14 @@ pub fn run() -> Result<(), Error> {
                             if let Ok(settings) = load_toml::<Settings, _>(path)
                                                             .map_err(|e| eprintln!("Can't load settings: {:#}.", e)) {
                                 context.settings = settings;
+                                if context.settings.ao3.load_secrets() {
+                                    save_toml(&context.settings, path)
+                                        .map_err(|e| eprintln!("Can't save settings: {:#}.", e))
+                                        .ok();
+                                }
+                                if context.settings.ao3.secrets_unreadable {
+                                    tx.send(Event::Notify("Your saved AO3 login couldn't be read. Please log in again.".to_string())).ok();
+                                }
                             }
                             if context.settings.wifi {
                                 Command::new("scripts/wifi-enable.sh")
@@ -739,21 +816,117 @@ pub fn run() -> Result<(), Error> {
                 }
             },
             Event::OpenWork(id) => {
-                let uri = format!("https://archiveofourown.org/works/{}?view_full_work=true&view_adult=true", id);
-                let html = context.client.get_html(&uri);
+                pending_bookmark = false;
+                fetch_pool.cancel_all();
+                fetch_pool.open_work(&context.client, id, context.settings.ao3.lazy_load_words);
+                show_loading(view.as_mut(), "Loading work…".to_string(), &mut rq, &mut context);
+            },
+            Event::Select(EntryId::EditBookmark(work_id)) => {
+                fetch_pool.cancel_all();
+                fetch_pool.open_work(&context.client, work_id, context.settings.ao3.lazy_load_words);
+                pending_bookmark = true;
+                show_loading(view.as_mut(), "Loading work…".to_string(), &mut rq, &mut context);
+            },
+            Event::Select(EntryId::MarkForLater(work_id, title)) => {
+                send_action(Ao3Action::MarkForLater { work_id }, title, &mut fetch_pool, &tx, &context);
+            },
+            Event::Select(EntryId::MarkAsRead(work_id, title)) => {
+                send_action(Ao3Action::MarkAsRead { work_id }, title, &mut fetch_pool, &tx, &context);
+            },
+            Event::Select(EntryId::Subscribe(path, name)) => {
+                send_action(Ao3Action::Subscribe { path }, name, &mut fetch_pool, &tx, &context);
+            },
+            Event::Select(EntryId::Unsubscribe(path, name)) => {
+                send_action(Ao3Action::Unsubscribe { path }, name, &mut fetch_pool, &tx, &context);
+            },
+            Event::ActionSent(action, title, res) => {
+                action_sent(*action, title, res, view.as_mut(), &mut history, &tx, &mut rq, &mut context);
+            },
+            Event::ReloadIndex(request) => {
+                fetch_pool.reload_index(&context.client, *request);
+            },
+            Event::IndexReloaded(fetched) => {
+                reload_index(&fetched, view.as_mut(), &mut history, &tx, &mut rq);
+            },
+            Event::FetchLatestUpdates(items) => {
+                fetch_pool.load_latest_updates(&context.client, items);
+            },
+            Event::LatestUpdateFetched(path, updated) => {
+                let evt = Event::LatestUpdateFetched(path, updated);
+                if view.is::<Works>() {
+                    view.handle_event(&evt, &tx, &mut VecDeque::new(), &mut rq, &mut context);
+                }
+                for item in history.iter_mut().filter(|item| item.view.is::<Works>()) {
+                    item.view.handle_event(&evt, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), &mut context);
+                }
+            },
+            Event::FetchChapter(path) => {
+                fetch_pool.load_chapter(&context.client, path);
+            },
+            Event::Select(EntryId::LoadComments(path)) => {
+                view.children_mut().retain(|child| !child.is::<Menu>());
+                fetch_pool.load_comments(&context.client, path);
+                show_loading(view.as_mut(), "Loading comments…".to_string(), &mut rq, &mut context);
+            },
+            Event::Select(EntryId::Download(work_id, format)) => {
+                fetch_pool.download(&context.client, work_id, format, context.settings.ao3.download_path.clone());
+                show_loading(view.as_mut(), format!("Downloading {}…", format), &mut rq, &mut context);
+            },
+            Event::WorkDownloaded(fetch_id, path) => {
+                if !fetch_pool.finish(fetch_id) {
+                    continue;
+                }
+                hide_loading(view.as_mut(), &mut rq);
+                let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                let notif = Notification::new(format!("Saved {}.", name), &tx, &mut rq, &mut context);
+                view.children_mut().push(Box::new(notif) as Box<dyn View>);
+            },
+            Event::WorkFetched(fetch_id, ref uri, ref html) => {
+                if !fetch_pool.finish(fetch_id) {
+                    continue;
+                }
+                hide_loading(view.as_mut(), &mut rq);
                 let rotation = context.display.rotation;
                 let dithered = context.fb.dithered();
-                let r = Reader::from_ao3(context.fb.rect(), &html, Some(&uri), &tx, &mut context);
+                match Reader::from_ao3(context.fb.rect(), html, Some(uri), &tx, &mut context) {
+                    Ok(r) => {
+                        let mut next_view = Box::new(r) as Box<dyn View>;
+                        transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
+                        history.push(HistoryItem {
+                            view,
+                            rotation,
+                            monochrome: context.fb.monochrome(),
+                            dithered,
+                        });
+                        view = next_view;
+                        if pending_bookmark {
+                            pending_bookmark = false;
+                            tx.send(Event::Show(ViewId::BookmarkEditor)).ok();
+                        }
+                    },
+                    Err(e) => {
+                        println!("Error opening {} - {}", uri, e);
+                        let notif = Notification::new(e.to_string(), &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    }
+                }
+            },
+            Event::CommentsFetched(fetch_id, ref html, ref fields) => {
+                if !fetch_pool.finish(fetch_id) {
+                    continue;
+                }
+                hide_loading(view.as_mut(), &mut rq);
+                let mut r = Reader::from_html(context.fb.rect(), html, None, &tx, &mut context);
+                r.set_comment_fields(fields.clone());
                 let mut next_view = Box::new(r) as Box<dyn View>;
                 transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
                 history.push(HistoryItem {
                     view,
-                    rotation,
+                    rotation: context.display.rotation,
                     monochrome: context.fb.monochrome(),
-                    dithered,
+                    dithered: context.fb.dithered(),
                 });
                 view = next_view;
-
             },
             Event::Select(EntryId::About) => {
                 let dialog = Dialog::new(ViewId::AboutDialog,
@@ -763,9 +936,13 @@ pub fn run() -> Result<(), Error> {
                 rq.add(RenderData::new(dialog.id(), *dialog.rect(), UpdateMode::Gui));
                 view.children_mut().push(Box::new(dialog) as Box<dyn View>);
             },
-            Event::Select(EntryId::SystemInfo) => {
+            Event::Select(EntryId::SystemInfo) | Event::Select(EntryId::ShowOutbox) => {
                 view.children_mut().retain(|child| !child.is::<Menu>());
-                let html = sys_info_as_html();
+                let html = if let Event::Select(EntryId::ShowOutbox) = evt {
+                    outbox_as_html(&context.outbox)
+                } else {
+                    sys_info_as_html()
+                };
                 let r = Reader::from_html(context.fb.rect(), &html, None, &tx, &mut context);
                 let mut next_view = Box::new(r) as Box<dyn View>;
                 transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
@@ -779,199 +956,160 @@ pub fn run() -> Result<(), Error> {
             },
             Event::OpenHtml(ref html, ref link_uri) => {
                 view.children_mut().retain(|child| !child.is::<Menu>());
-                let r = Reader::from_ao3(context.fb.rect(), html, link_uri.as_deref(), &tx, &mut context);
-                let mut next_view = Box::new(r) as Box<dyn View>;
-                transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                history.push(HistoryItem {
-                    view,
-                    rotation: context.display.rotation,
-                    monochrome: context.fb.monochrome(),
-                    dithered: context.fb.dithered(),
-                });
-                view = next_view;
-            },
-            Event::LoadIndex( link_uri) => {
-                println!("loading tag {}", link_uri);
-
-                let url = get_url(&link_uri);
-                let client = Client::builder().redirect(Policy::none()).build().unwrap();
-                let res = client.get(url.as_str()).send();
-                match res {
+                match Reader::from_ao3(context.fb.rect(), html, link_uri.as_deref(), &tx, &mut context) {
                     Ok(r) => {
-                        match r.status() {
-                            StatusCode::OK => {
-                                view.children_mut().retain(|child| !child.is::<Menu>());
-                                let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), link_uri, &tx,
-                                                                     &mut rq, &mut context, IndexType::TagWorks)?);
-                                transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                history.push(HistoryItem {
-                                    view,
-                                    rotation: context.display.rotation,
-                                    monochrome: context.fb.monochrome(),
-                                    dithered: context.fb.dithered(),
-                                });
-                                view = next_view;
-                            },
-                            StatusCode::FOUND |
-                                StatusCode::MOVED_PERMANENTLY => {
-                                    // Check if we're being redirected to a different works index
-                                    // because of tag synning
-                                    if let Some(loc) = r.headers().get(reqwest::header::LOCATION) {
-                                        if let Ok(loc) = loc.to_str() {
-                                            let loc_str = loc.to_string();
-                                            if loc_str.ends_with("/works") {
-                                                view.children_mut().retain(|child| !child.is::<Menu>());
-                                                let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), loc_str, &tx,
-                                                                                     &mut rq, &mut context, ao3reader_core::view::works::IndexType::TagWorks)?);
-                                                transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                                history.push(HistoryItem {
-                                                    view,
-                                                    rotation: context.display.rotation,
-                                                    monochrome: context.fb.monochrome(),
-                                                    dithered: context.fb.dithered(),
-                                                });
-                                                view = next_view;
-                                            }
-                                        }
-                                    } else {
-                                        // TODO: change this when we can look at tag pages
-                                        let msg = format!("Unwrangled tag! No works available.");
-                                        let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                                    }
-                            },
-                            _ => {
-                                println!("Got {} for {}", r.status(), link_uri);
-                                let msg = format!("Error: {}", r.status());
-                                let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                            }
-                        }
+                        let mut next_view = Box::new(r) as Box<dyn View>;
+                        transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
+                        history.push(HistoryItem {
+                            view,
+                            rotation: context.display.rotation,
+                            monochrome: context.fb.monochrome(),
+                            dithered: context.fb.dithered(),
+                        });
+                        view = next_view;
                     },
                     Err(e) => {
-                        println!("Error fetching {} - {}", link_uri, e);
-                        let msg = format!("Error: {}", e);
-                        let notif = Notification::new(msg, &tx, &mut rq, &mut context);
+                        let notif = Notification::new(e.to_string(), &tx, &mut rq, &mut context);
                         view.children_mut().push(Box::new(notif) as Box<dyn View>);
                     }
-                };
-
+                }
             },
-            Event::LoadSearch( query) => {
+            Event::LoadIndex(link_uri) => {
+                println!("loading tag {}", link_uri);
+                fetch_pool.cancel_all();
+                fetch_pool.load_index(&context.client, link_uri, IndexType::TagWorks);
+                show_loading(view.as_mut(), "Loading works…".to_string(), &mut rq, &mut context);
+            },
+            Event::LoadSearch(query) => {
                 println!("loading search {}", query);
-
-                let link_uri = format!("https://archiveofourown.org/works/search?work_search%5Bquery%5D={}", query);
-                let url = get_url(&link_uri);
-                let client = Client::builder().redirect(Policy::none()).build().unwrap();
-                let res = client.get(url.as_str()).send();
-                match res {
-                    Ok(r) => {
-                        match r.status() {
-                            StatusCode::OK => {
-                                view.children_mut().retain(|child| !child.is::<Menu>());
-                                let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), link_uri, &tx,
-                                                                     &mut rq, &mut context, IndexType::Search(query))?);
-                                transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                history.push(HistoryItem {
-                                    view,
-                                    rotation: context.display.rotation,
-                                    monochrome: context.fb.monochrome(),
-                                    dithered: context.fb.dithered(),
-                                });
-                                view = next_view;
-                            },
-                            StatusCode::FOUND |
-                                StatusCode::MOVED_PERMANENTLY => {
-                                    // Check if we're being redirected to a different works index
-                                    // because of tag synning
-                                    if let Some(loc) = r.headers().get(reqwest::header::LOCATION) {
-                                        if let Ok(loc) = loc.to_str() {
-                                            let loc_str = loc.to_string();
-                                            if loc_str.ends_with("/works") {
-                                                view.children_mut().retain(|child| !child.is::<Menu>());
-                                                let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), loc_str, &tx,
-                                                                                     &mut rq, &mut context, ao3reader_core::view::works::IndexType::TagWorks)?);
-                                                transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                                history.push(HistoryItem {
-                                                    view,
-                                                    rotation: context.display.rotation,
-                                                    monochrome: context.fb.monochrome(),
-                                                    dithered: context.fb.dithered(),
-                                                });
-                                                view = next_view;
-                                            }
-                                        }
-                                    } else {
-                                        // TODO: change this when we can look at tag pages
-                                        let msg = format!("Unwrangled tag! No works available.");
-                                        let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                                    }
-                            },
-                            _ => {
-                                println!("Got {} for {}", r.status(), link_uri);
-                                let msg = format!("Error: {}", r.status());
-                                let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        println!("Error fetching {} - {}", link_uri, e);
-                        let msg = format!("Error: {}", e);
-                        let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                    }
-                };
-
+                let link_uri = context.settings.ao3.url(&format!("/works/search?work_search%5Bquery%5D={}", query)).to_string();
+                fetch_pool.cancel_all();
+                fetch_pool.load_index(&context.client, link_uri, IndexType::Search(query));
+                show_loading(view.as_mut(), "Searching…".to_string(), &mut rq, &mut context);
             },
             Event::LoadHistory(history_view) => {
                 if let Some(ref username) = context.settings.ao3.username {
 
-                    let mut link_uri = format!("https://archiveofourown.org/users/{}/readings", username);
+                    let mut link_uri = context.settings.ao3.url(&format!("/users/{}/readings", username)).to_string();
                     if let HistoryView::MarkedForLater = history_view {
                          link_uri = link_uri + "?show=to-read";
                     }
 
-                    let res = context.client.get(&link_uri).send();
-                    match res {
-                        Ok(r) => {
-                            match r.status() {
-                                StatusCode::OK => {
-                                    view.children_mut().retain(|child| !child.is::<Menu>());
-                                    let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), link_uri, &tx,
-                                                                         &mut rq, &mut context, IndexType::History(history_view))?);
-                                    transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                    history.push(HistoryItem {
-                                        view,
-                                        rotation: context.display.rotation,
-                                        monochrome: context.fb.monochrome(),
-                                        dithered: context.fb.dithered(),
-                                    });
-                                    view = next_view;
-                                },
-                                _ => {
-                                    println!("Got {} for {}", r.status(), link_uri);
-                                    let msg = format!("Error: {}", r.status());
-                                    let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                                }
-                            }
-                        },
-                        Err(e) => {
-                            println!("Error fetching {} - {}", link_uri, e);
-                            let msg = format!("Error: {}", e);
-                            let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                            view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                        }
-                    };
+                    fetch_pool.cancel_all();
+                    fetch_pool.load_index(&context.client, link_uri, IndexType::History(history_view));
+                    show_loading(view.as_mut(), "Loading history…".to_string(), &mut rq, &mut context);
                 } else {
                     println!("Can't load history without a username!");
-                    let msg = format!("Can't load history without a username!");
+                    let msg = "Can't load history without a username!".to_string();
                     let notif = Notification::new(msg, &tx, &mut rq, &mut context);
                     view.children_mut().push(Box::new(notif) as Box<dyn View>);
                 }
             },
+            Event::LoadSubscriptions => {
+                if let Some(ref username) = context.settings.ao3.username {
+                    let link_uri = context.settings.ao3.url(&format!("/users/{}/subscriptions", username)).to_string();
+                    fetch_pool.cancel_all();
+                    fetch_pool.load_index(&context.client, link_uri, IndexType::Subscriptions);
+                    show_loading(view.as_mut(), "Loading subscriptions…".to_string(), &mut rq, &mut context);
+                } else {
+                    let notif = Notification::new("Can't load subscriptions without a username!".to_string(), &tx, &mut rq, &mut context);
+                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                }
+            },
+            Event::IndexFetched(fetch_id, data) => {
+                if !fetch_pool.finish(fetch_id) {
+                    continue;
+                }
+                hide_loading(view.as_mut(), &mut rq);
+                let works = Works::new(context.fb.rect(), *data, &tx, &mut rq, &mut context);
+                view.children_mut().retain(|child| !child.is::<Menu>());
+                let mut next_view: Box<dyn View> = Box::new(works);
+                transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
+                history.push(HistoryItem {
+                    view,
+                    rotation: context.display.rotation,
+                    monochrome: context.fb.monochrome(),
+                    dithered: context.fb.dithered(),
+                });
+                view = next_view;
+            },
+            Event::LoadIndexPages(request) => {
+                fetch_pool.cancel_all();
+                fetch_pool.load_index_pages(&context.client, *request);
+                show_loading(view.as_mut(), "Loading works…".to_string(), &mut rq, &mut context);
+            },
+            Event::IndexPagesFetched(fetch_id, fetched) => {
+                if !fetch_pool.finish(fetch_id) {
+                    continue;
+                }
+                hide_loading(view.as_mut(), &mut rq);
+                if let Some(works) = view.downcast_mut::<Works>() {
+                    works.take_pages(&fetched, &tx, &mut rq);
+                }
+            },
+            Event::FetchProgress(fetch_id, text) => {
+                if fetch_pool.is_pending(fetch_id) {
+                    show_loading(view.as_mut(), text, &mut rq, &mut context);
+                }
+            },
+            Event::Login => {
+                if context.settings.ao3.username.is_some() && context.settings.ao3.secrets.password.is_some() {
+                    fetch_pool.cancel_all();
+                    fetch_pool.login(&context.client);
+                    show_loading(view.as_mut(), "Logging in…".to_string(), &mut rq, &mut context);
+                } else {
+                    let msg = "Add your AO3 user name and password to Settings.toml to log in.".to_string();
+                    let notif = Notification::new(msg, &tx, &mut rq, &mut context);
+                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                }
+            },
+            Event::Logout => {
+                fetch_pool.cancel_all();
+                fetch_pool.logout(&context.client);
+                show_loading(view.as_mut(), "Logging out…".to_string(), &mut rq, &mut context);
+            },
+            Event::SessionUpdated(fetch_id) => {
+                if fetch_pool.finish(fetch_id) {
+                    hide_loading(view.as_mut(), &mut rq);
+                    if context.client.logged_in() {
+                        update_session(&mut context);
+                    } else {
+                        clear_session(&mut context);
+                    }
+                    if view.is::<Home>() {
+                        view = Box::new(Home::new(context.fb.rect(), &mut rq,
+                                context.settings.time_format.clone(), &mut context.fonts, &mut context.battery, context.settings.frontlight, context.client.logged_in(),
+                                context.settings.ao3.username.as_deref(), &context.settings.ao3.faves));
+                    }
+                }
+            },
+            Event::OutboxReplayed(results) => {
+                if context.client.logged_in() {
+                    update_session(&mut context);
+                }
+                for msg in context.outbox.finish_replay(results) {
+                    let notif = Notification::new(msg, &tx, &mut rq, &mut context);
+                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                }
+            },
+            Event::Select(EntryId::RetryOutbox) => {
+                context.outbox.retry_failed();
+                if context.online {
+                    if let Some(actions) = context.outbox.start_replay() {
+                        fetch_pool.replay_outbox(&context.client, actions);
+                    }
+                }
+            },
+            Event::Select(EntryId::DiscardOutbox) => {
+                context.outbox.discard_failed();
+            },
+            Event::FetchFailed(fetch_id, e) => {
+                if fetch_pool.finish(fetch_id) {
+                    hide_loading(view.as_mut(), &mut rq);
+                    let notif = Notification::new(e.to_string(), &tx, &mut rq, &mut context);
+                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                }
+            },
             Event::Select(EntryId::Launch(app_cmd)) => {
                 view.children_mut().retain(|child| !child.is::<Menu>());
                 let monochrome = context.fb.monochrome();
@@ -995,7 +1133,10 @@ pub fn run() -> Result<(), Error> {
                 view = next_view;
             },
             Event::Back => {
-                if let Some(item) = history.pop() {
+                // Back first cancels whatever is still loading.
+                if fetch_pool.cancel_all() {
+                    hide_loading(view.as_mut(), &mut rq);
+                } else if let Some(item) = history.pop() {
                     view = item.view;
                     if item.monochrome != context.fb.monochrome() {
                         context.fb.set_monochrome(item.monochrome);
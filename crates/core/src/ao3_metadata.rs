@@ -1,13 +1,18 @@
+use std::collections::BTreeSet;
 use regex::Regex;
-use scraper::Html;
-use crate::html::{scrape, scrape_inner_text, scrape_inner_text_to_html, scrape_link_list, scrape_outer, Link};
+use scraper::{Html, Selector};
+use crate::html::{scrape, scrape_inner_text, scrape_html_text, scrape_link, scrape_link_list, scrape_many_outer, scrape_outer, HtmlText, Link, PageErrors};
 use chrono::NaiveDate;
 use serde::{Serialize, Deserialize};
 use crate::helpers::date_format;
 use crate::view::icon::DisabledIcon;
 use crate::geom::Rectangle;
+use crate::selectors::{selectors, TagSelectors};
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+const BLURB_DATE_FORMAT: &str = "%d %b %Y";
+const WORK_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Rating{
     NotRated,
     Explicit,
@@ -16,7 +21,32 @@ pub enum Rating{
     General
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+impl Rating {
+    // AO3's symbols are named after the classes of their spans, e.g. `rating-teen`.
+    fn from_symbol(symbol: &str) -> Option<Rating> {
+        match symbol {
+            "explicit" => Some(Rating::Explicit),
+            "mature" => Some(Rating::Mature),
+            "teen" => Some(Rating::Teen),
+            "general-audience" => Some(Rating::General),
+            "notrated" => Some(Rating::NotRated),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Rating> {
+        match name.trim() {
+            "Explicit" => Some(Rating::Explicit),
+            "Mature" => Some(Rating::Mature),
+            "Teen And Up Audiences" => Some(Rating::Teen),
+            "General Audiences" => Some(Rating::General),
+            "Not Rated" => Some(Rating::NotRated),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Category{
     Slash,
     Femslash,
@@ -26,7 +56,33 @@ pub enum Category{
     Other
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+impl Category {
+    fn from_symbol(symbol: &str) -> Option<Category> {
+        match symbol {
+            "slash" => Some(Category::Slash),
+            "femslash" => Some(Category::Femslash),
+            "het" => Some(Category::Het),
+            "gen" => Some(Category::Gen),
+            "multi" => Some(Category::Multi),
+            "other" => Some(Category::Other),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Category> {
+        match name.trim() {
+            "M/M" => Some(Category::Slash),
+            "F/F" => Some(Category::Femslash),
+            "F/M" => Some(Category::Het),
+            "Gen" => Some(Category::Gen),
+            "Multi" => Some(Category::Multi),
+            "Other" => Some(Category::Other),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Warning{
     Yes,
     No,
@@ -34,12 +90,23 @@ pub enum Warning{
     External
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Warning {
+    fn from_name(name: &str) -> Warning {
+        match name.trim() {
+            "No Archive Warnings Apply" => Warning::No,
+            "Creator Chose Not To Use Archive Warnings" => Warning::ChoseNotTo,
+            _ => Warning::Yes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default, rename_all = "camelCase")]
 pub struct RequiredTags {
     pub rating: Rating,
     pub warnings: Warning,
-    pub category: Category,
+    // Empty when the creator didn't pick any.
+    pub categories: BTreeSet<Category>,
     pub complete: bool
 }
 
@@ -48,13 +115,82 @@ impl Default for RequiredTags {
         RequiredTags {
             rating: Rating::NotRated,
             warnings: Warning::ChoseNotTo,
-            category: Category::Multi,
+            categories: BTreeSet::new(),
             complete: false
         }
     }
 }
 
-// TODO: add chapters
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TagKind {
+    Warning,
+    Relationship,
+    Character,
+    Freeform,
+}
+
+impl TagKind {
+    // In the order AO3 lists them.
+    pub const ALL: [TagKind; 4] = [TagKind::Warning, TagKind::Relationship, TagKind::Character, TagKind::Freeform];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TagKind::Warning => "Warnings",
+            TagKind::Relationship => "Relationships",
+            TagKind::Character => "Characters",
+            TagKind::Freeform => "Additional Tags",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WorkTags {
+    pub warnings: Vec<Link>,
+    pub relationships: Vec<Link>,
+    pub characters: Vec<Link>,
+    pub freeforms: Vec<Link>,
+}
+
+impl WorkTags {
+    fn scrape(html: &Html, selectors: &TagSelectors) -> WorkTags {
+        let mut tags = WorkTags::default();
+        for kind in TagKind::ALL {
+            *tags.of_kind_mut(kind) = scrape_link_list(html, selectors.of_kind(kind));
+        }
+        tags
+    }
+
+    pub fn of_kind(&self, kind: TagKind) -> &[Link] {
+        match kind {
+            TagKind::Warning => &self.warnings,
+            TagKind::Relationship => &self.relationships,
+            TagKind::Character => &self.characters,
+            TagKind::Freeform => &self.freeforms,
+        }
+    }
+
+    fn of_kind_mut(&mut self, kind: TagKind) -> &mut Vec<Link> {
+        match kind {
+            TagKind::Warning => &mut self.warnings,
+            TagKind::Relationship => &mut self.relationships,
+            TagKind::Character => &mut self.characters,
+            TagKind::Freeform => &mut self.freeforms,
+        }
+    }
+
+    // The tags shown in blurbs: the warnings have their own icon.
+    pub fn descriptive(&self) -> impl Iterator<Item=&Link> {
+        self.relationships.iter().chain(&self.characters).chain(&self.freeforms)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeriesPart {
+    pub part: usize,
+    pub series: Link,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename_all = "camelCase")]
 pub struct Ao3Info {
@@ -63,14 +199,26 @@ pub struct Ao3Info {
     pub authors: Vec<Link>,
     pub fandoms: Vec<Link>,
     pub req_tags: RequiredTags,
-    pub tags: Vec<Link>,
-    pub summary: String,
+    pub tags: WorkTags,
+    pub summary: Vec<HtmlText>,
     pub kudos: usize,
     pub hits: usize,
     pub bookmarks: usize,
     pub comments: usize,
     pub words: usize,
     pub chapters: String,
+    pub language: String,
+    pub series: Vec<SeriesPart>,
+    pub collections: Vec<Link>,
+    // Gift recipients.
+    pub gifts: Vec<Link>,
+    // The works this one was inspired by.
+    pub inspirations: Vec<Link>,
+    // Only visible to logged in users.
+    pub restricted: bool,
+    // Blurbs only show when works were last updated.
+    #[serde(with = "date_format")]
+    pub published: NaiveDate,
     #[serde(with = "date_format")]
     pub updated: NaiveDate,
 }
@@ -83,14 +231,21 @@ impl Default for Ao3Info {
             authors: vec![],
             fandoms: vec![],
             req_tags: RequiredTags::default(),
-            tags: vec![],
-            summary: "No summary".to_string(),
+            tags: WorkTags::default(),
+            summary: vec![HtmlText::Text("No summary".to_string())],
             chapters: "0/0".to_string(),
             kudos: 0,
             hits: 0,
             comments: 0,
             words: 0,
             bookmarks: 0,
+            language: String::new(),
+            series: vec![],
+            collections: vec![],
+            gifts: vec![],
+            inspirations: vec![],
+            restricted: false,
+            published: NaiveDate::MIN,
             updated: NaiveDate::MIN
         }
     }
@@ -100,31 +255,69 @@ pub fn str_to_usize(str: String) -> usize {
     str.replace(",", "").parse::<usize>().unwrap_or(0)
 }
 
+// Series positions read "Part 2 of <a>Series</a>", with the number
+// emphasized in blurbs.
+fn scrape_series(frag: &Html, select: &str) -> Vec<SeriesPart> {
+    let part_re = Regex::new(r"Part\s+(?:<strong>)?(\d+)").unwrap();
+    scrape_many_outer(frag, select).into_iter().filter_map(|position| {
+        let part = part_re.captures(&position).map_or(0, |caps| str_to_usize(caps[1].to_string()));
+        let series = scrape_link(&Html::parse_fragment(&position), "a").ok()?;
+        Some(SeriesPart { part, series })
+    }).collect()
+}
+
+// Inspirations are listed in the work's associations, along with gifts and translations.
+fn scrape_inspirations(frag: &Html, select: &str) -> Vec<Link> {
+    scrape_many_outer(frag, select).into_iter().filter(|association| {
+        association.contains("Inspired by")
+    }).filter_map(|association| {
+        scrape_link(&Html::parse_fragment(&association), "a").ok()
+    }).collect()
+}
+
+fn scrape_date(frag: &Html, select: &str, format: &str) -> Option<NaiveDate> {
+    scrape(frag, select).ok().and_then(|date| NaiveDate::parse_from_str(&date, format).ok())
+}
+
+// Counts are left out of blurbs and work pages while they're zero.
+fn scrape_count(frag: &Html, select: &str) -> usize {
+    scrape(frag, select).map_or(0, str_to_usize)
+}
+
 impl Ao3Info {
-    pub fn new(data: String) -> Ao3Info {
+    // Parses a work blurb from an index. The errors list the required fields
+    // that couldn't be found.
+    pub fn new(data: String) -> Result<Ao3Info, PageErrors> {
         let html = Html::parse_fragment(&data);
+        let profile = selectors();
+        let select = &profile.blurb;
+        let mut errors = PageErrors::default();
 
         let mut id = "0".to_string(); 
         let id_re = Regex::new(r"work_(\d+)").unwrap();
         if let Some(caps) = id_re.captures(&data) {
             id = caps[1].to_string();
         }
-        let datetime = scrape(&html, ".datetime");
-        let updated = NaiveDate::parse_from_str(&datetime, "%d %b %Y").unwrap_or(NaiveDate::MIN);
-        let title = scrape(&html, "h4.heading a");
-        let authors = scrape_link_list(&html, r#"a[rel="author"]"#);
-        let fandoms = scrape_link_list(&html, ".fandoms a");
-        let req_tags = RequiredTags::new(scrape_outer(&html, "ul.required-tags"));
-        let tags = scrape_link_list(&html, "ul.tags a");
-        let summary = scrape_inner_text_to_html(&html, "blockquote.summary");
-        let words = str_to_usize(scrape(&html, "dd.words"));
-        let comments = str_to_usize(scrape(&html, "dd.comments"));
-        let kudos = str_to_usize(scrape(&html, "dd.kudos"));
-        let hits = str_to_usize(scrape(&html, "dd.hits"));
-        let bookmarks = str_to_usize(scrape(&html, "dd.bookmarks a"));
-        let chapters = scrape_inner_text(&html, "dd.chapters");
-
-        Ao3Info{
+        let datetime = errors.required(scrape(&html, &select.datetime));
+        let updated = NaiveDate::parse_from_str(&datetime, BLURB_DATE_FORMAT).unwrap_or(NaiveDate::MIN);
+        let title = errors.required(scrape(&html, &select.title));
+        let authors = scrape_link_list(&html, &select.authors);
+        let fandoms = scrape_link_list(&html, &select.fandoms);
+        let req_tags = RequiredTags::new(errors.required(scrape_outer(&html, &select.required_tags)));
+        let tags = WorkTags::scrape(&html, &select.tags);
+        let summary = scrape_html_text(&html, &select.summary).unwrap_or_default();
+        let words = scrape_count(&html, &select.words);
+        let comments = scrape_count(&html, &select.comments);
+        let kudos = scrape_count(&html, &select.kudos);
+        let hits = scrape_count(&html, &select.hits);
+        let bookmarks = scrape_count(&html, &select.bookmarks);
+        let chapters = errors.required(scrape_inner_text(&html, &select.chapters));
+        let language = scrape_inner_text(&html, &select.language).unwrap_or_default();
+        let series = scrape_series(&html, &select.series);
+        let gifts = scrape_link_list(&html, &select.gifts);
+        let restricted = scrape_outer(&html, &select.restricted).is_ok();
+
+        errors.finish(Ao3Info{
             id,
             title,
             authors,
@@ -137,40 +330,51 @@ impl Ao3Info {
             kudos,
             hits,
             bookmarks,
-            updated,
-            chapters
-        }
+            chapters,
+            language,
+            series,
+            collections: Vec::new(),
+            gifts,
+            inspirations: Vec::new(),
+            restricted,
+            published: NaiveDate::MIN,
+            updated
+        })
     }
 
-    pub fn new_from_work(meta: String, preface: String, id: String) -> Ao3Info {
-        let html = Html::parse_fragment(&meta);
-        let header = Html::parse_fragment(&preface);
-        // TODO: req tags implementation
-
-        let datetime = scrape(&html, ".status");
-        let updated = NaiveDate::parse_from_str(&datetime, "%Y-%b-%d").unwrap_or(NaiveDate::MIN);
-        let title = scrape(&header, "h2.title");
-        let authors = scrape_link_list(&header, r#"a[rel="author"]"#);
-        let fandoms = scrape_link_list(&html, ".fandom a");
-        let req_tags = RequiredTags::new(scrape_outer(&html, "ul.required-tags"));
-
-        let ships = scrape_link_list(&html, ".relationship a");
-        let chars = scrape_link_list(&html, ".character a");
-        let addl_tags = scrape_link_list(&html, ".freeform a");
-        let mut tags = Vec::new();
-        tags.extend(ships);
-        tags.extend(chars);
-        tags.extend(addl_tags);
-
-        let summary = scrape_inner_text(&header, ".summary blockquote");
-        let words = str_to_usize(scrape(&html, "dd.words"));
-        let chapters = scrape_inner_text(&html, "dd.chapters");
-        let comments = str_to_usize(scrape(&html, "dd.comments"));
-        let kudos = str_to_usize(scrape(&html, "dd.kudos"));
-        let hits = str_to_usize(scrape(&html, "dd.hits"));
-        let bookmarks = str_to_usize(scrape(&html, "dd.bookmarks a"));
-
-        Ao3Info{
+    // Parses the metadata at the top of a work page.
+    pub fn new_from_work(document: &Html, id: String) -> Result<Ao3Info, PageErrors> {
+        let profile = selectors();
+        let select = &profile.work;
+        let mut errors = PageErrors::default();
+        let html = Html::parse_fragment(&errors.required(scrape(document, &select.meta)));
+        let header = Html::parse_fragment(&errors.required(scrape(document, &select.preface)));
+
+        let published = scrape_date(&html, &select.published, WORK_DATE_FORMAT).unwrap_or(NaiveDate::MIN);
+        // Only works with several chapters have a status.
+        let updated = scrape_date(&html, &select.status, WORK_DATE_FORMAT).unwrap_or(published);
+        // The title holds the lock icon of restricted works.
+        let title = errors.required(scrape_inner_text(&header, &select.title));
+        let authors = scrape_link_list(&header, &select.authors);
+        let fandoms = scrape_link_list(&html, &select.fandoms);
+        let tags = WorkTags::scrape(&html, &select.tags);
+
+        let summary = scrape_html_text(&header, &select.summary).unwrap_or_default();
+        let words = scrape_count(&html, &select.words);
+        let chapters = scrape_inner_text(&html, &select.chapters).unwrap_or_default();
+        let req_tags = RequiredTags::from_work(&html, &tags.warnings, &chapters);
+        let comments = scrape_count(&html, &select.comments);
+        let kudos = scrape_count(&html, &select.kudos);
+        let hits = scrape_count(&html, &select.hits);
+        let bookmarks = scrape_count(&html, &select.bookmarks);
+        let language = scrape_inner_text(&html, &select.language).unwrap_or_default();
+        let series = scrape_series(&html, &select.series);
+        let collections = scrape_link_list(&html, &select.collections);
+        let gifts = scrape_link_list(&header, &select.gifts);
+        let inspirations = scrape_inspirations(&header, &select.associations);
+        let restricted = scrape_outer(&header, &select.restricted).is_ok();
+
+        errors.finish(Ao3Info{
             id,
             title,
             authors,
@@ -183,74 +387,84 @@ impl Ao3Info {
             kudos,
             hits,
             bookmarks,
-            updated,
-            chapters
-        }
+            chapters,
+            language,
+            series,
+            collections,
+            gifts,
+            inspirations,
+            restricted,
+            published,
+            updated
+        })
     }
 
-
-    pub fn download_name(&self) -> String {
-        format!("{}.epub", self.title)
+    // The chapters read "posted/expected".
+    pub fn posted_chapters(&self) -> usize {
+        str_to_usize(self.chapters.split('/').next().unwrap_or_default().trim().to_string())
     }
 }
 
 impl RequiredTags {
+    // Parses the symbols of a blurb's `ul.required-tags`.
     pub fn new(data: String) -> RequiredTags {
-        let warnings_re = Regex::new(r"warning-(.+)").unwrap();
-        let category_re = Regex::new(r"category-(.+)").unwrap();
-        let complete_re = Regex::new(r"complete-(.+)").unwrap();
-        let rating_re = Regex::new(r"rating-(.+)").unwrap();
-
-        let mut warnings = Warning::ChoseNotTo;
-        let mut category = Category::Multi;
-        let mut complete = false;
-        let mut rating = Rating::NotRated;
-
-        if let Some(caps) = warnings_re.captures(&data) {
-            warnings = match &caps[1] {
-                "no" => Warning::No,
-                "yes" => Warning::Yes,
-                "choosenotto" => Warning::ChoseNotTo,
-                "external" => Warning::External,
-                &_ => Warning::ChoseNotTo,
-            };
+        let html = Html::parse_fragment(&data);
+        let selector = Selector::parse(&selectors().blurb.symbols).unwrap();
+        let mut tags = RequiredTags::default();
+
+        for span in html.select(&selector) {
+            for class in span.value().classes() {
+                if let Some(symbol) = class.strip_prefix("rating-") {
+                    tags.rating = Rating::from_symbol(symbol).unwrap_or(tags.rating);
+                } else if let Some(symbol) = class.strip_prefix("category-") {
+                    // Several categories share the multi symbol, and are listed in its title.
+                    let names = span.value().attr("title").unwrap_or_default();
+                    let categories: Vec<Category> = names.split(',').filter_map(Category::from_name).collect();
+                    if symbol == "multi" && categories.len() > 1 {
+                        tags.categories.extend(categories);
+                    } else {
+                        tags.categories.extend(Category::from_symbol(symbol));
+                    }
+                } else if let Some(symbol) = class.strip_prefix("warning-") {
+                    tags.warnings = match symbol {
+                        "yes" => Warning::Yes,
+                        "no" => Warning::No,
+                        _ => Warning::ChoseNotTo,
+                    };
+                } else if class == "external-work" {
+                    tags.warnings = Warning::External;
+                } else if let Some(symbol) = class.strip_prefix("complete-") {
+                    tags.complete = symbol == "yes";
+                }
+            }
         }
 
-        if let Some(caps) = category_re.captures(&data) {
-            category = match &caps[1] {
-                "multi" =>Category::Multi,
-                "other" => Category::Other,
-                "femslash" => Category::Femslash,
-                "gen" => Category::Gen,
-                "het" => Category::Het,
-                &_ => Category::Multi,
-            };
-        }
+        tags
+    }
 
-        if let Some(caps) = complete_re.captures(&data) {
-            complete = match &caps[1] {
-                "no" => false,
-                "yes" => true,
-                &_ => false
-            };
-        }
+    // Work pages list the tags instead of showing their symbols.
+    pub fn from_work(html: &Html, warnings: &[Link], chapters: &str) -> RequiredTags {
+        let profile = selectors();
+        let select = &profile.work;
+        let rating = scrape_link_list(html, &select.rating).iter()
+            .find_map(|tag| Rating::from_name(&tag.title))
+            .unwrap_or(Rating::NotRated);
+        let categories = scrape_link_list(html, &select.categories).iter()
+            .filter_map(|tag| Category::from_name(&tag.title))
+            .collect();
+        let warnings = if warnings.iter().any(|tag| Warning::from_name(&tag.title) == Warning::Yes) {
+            Warning::Yes
+        } else {
+            warnings.first().map_or(Warning::ChoseNotTo, |tag| Warning::from_name(&tag.title))
+        };
+        // A work is complete once all its expected chapters are posted, e.g. 3/3.
+        let complete = chapters.split_once('/').is_some_and(|(posted, expected)| posted.trim() == expected.trim());
 
-        if let Some(caps) = rating_re.captures(&data) {
-            rating = match &caps[1] {
-                "explicit" => Rating::Explicit,
-                "mature" => Rating::Mature,
-                "teen" => Rating::Teen,
-                "general-audience" => Rating::General,
-                "notrated" => Rating::NotRated,
-                &_ => Rating::NotRated,
-            };
-        }
-        
-        RequiredTags{
+        RequiredTags {
             rating,
-            category,
-            complete,
-            warnings
+            warnings,
+            categories,
+            complete
         }
     }
 
@@ -274,13 +488,19 @@ impl RequiredTags {
             Rating::NotRated => DisabledIcon::new("blank", rating_rect),
         };
 
-        let category_icon = match self.category {
-            Category::Slash => DisabledIcon::new("slash", cat_rect),
-            Category::Femslash => DisabledIcon::new("femslash", cat_rect),
-            Category::Het => DisabledIcon::new("het", cat_rect),
-            Category::Multi => DisabledIcon::new("multi", cat_rect),
-            Category::Other => DisabledIcon::new("other", cat_rect),
-            Category::Gen => DisabledIcon::new("gen", cat_rect),
+        // Like on AO3, works in several categories get the multi symbol.
+        let category_icon = if self.categories.len() > 1 {
+            DisabledIcon::new("multi", cat_rect)
+        } else {
+            match self.categories.iter().next() {
+                Some(Category::Slash) => DisabledIcon::new("slash", cat_rect),
+                Some(Category::Femslash) => DisabledIcon::new("femslash", cat_rect),
+                Some(Category::Het) => DisabledIcon::new("het", cat_rect),
+                Some(Category::Multi) => DisabledIcon::new("multi", cat_rect),
+                Some(Category::Other) => DisabledIcon::new("other", cat_rect),
+                Some(Category::Gen) => DisabledIcon::new("gen", cat_rect),
+                None => DisabledIcon::new("blank", cat_rect),
+            }
         };
 
         let complete_icon = if self.complete {DisabledIcon::new("complete", complete_rect)} else {DisabledIcon::new("wip", complete_rect)};
@@ -296,3 +516,155 @@ impl RequiredTags {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLURB: &str = r#"<li id="work_42" class="work blurb group">
+        <h4 class="heading"><a href="/works/42">Shore Leave</a> by <a rel="author" href="/users/writer">writer</a></h4>
+        <ul class="required-tags"><li><span class="rating-teen rating"></span></li></ul>
+        <p class="datetime">05 Mar 2024</p>
+        <ul class="tags commas">
+            <li class="warnings"><strong><a class="tag" href="/tags/No%20Archive%20Warnings%20Apply/works">No Archive Warnings Apply</a></strong></li>
+            <li class="relationships"><a class="tag" href="/tags/James%20T*s*Kirk*s*Spock/works">James T. Kirk/Spock</a></li>
+            <li class="characters"><a class="tag" href="/tags/Spock/works">Spock</a></li>
+            <li class="freeforms"><a class="tag" href="/tags/Fluff/works">Fluff</a></li>
+            <li class="freeforms"><a class="tag" href="/tags/Shore%20Leave/works">Shore Leave</a></li>
+        </ul>
+        <dl class="stats"><dd class="words">1,234</dd><dd class="chapters">1/1</dd></dl>
+    </li>"#;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_blurbs_WHEN_newIsCalled_THEN_onlyMissingRequiredFieldsWillBeErrors() {
+        // GIVEN blurbs
+        let complete = BLURB.to_string();
+        let broken = BLURB.replace(r#"<dd class="chapters">1/1</dd>"#, "").replace("<h4", "<h5").replace("</h4>", "</h5>");
+
+        // WHEN new is called
+        let info = Ao3Info::new(complete).unwrap();
+        let errors = Ao3Info::new(broken).unwrap_err();
+
+        // THEN only missing required fields will be errors
+        assert_eq!(info.title, "Shore Leave");
+        assert_eq!(info.words, 1234);
+        assert_eq!(info.kudos, 0);
+        assert!(info.summary.is_empty());
+        assert_eq!(errors.selectors(), vec!["h4.heading a", "dd.chapters"]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aBlurb_WHEN_newIsCalled_THEN_theTagsWillBeSortedByKind() {
+        // GIVEN a blurb
+        let blurb = BLURB.to_string();
+
+        // WHEN new is called
+        let tags = Ao3Info::new(blurb).unwrap().tags;
+
+        // THEN the tags will be sorted by kind
+        let titles = |kind| tags.of_kind(kind).iter().map(|tag| tag.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(titles(TagKind::Warning), vec!["No Archive Warnings Apply"]);
+        assert_eq!(titles(TagKind::Relationship), vec!["James T. Kirk/Spock"]);
+        assert_eq!(titles(TagKind::Character), vec!["Spock"]);
+        assert_eq!(titles(TagKind::Freeform), vec!["Fluff", "Shore Leave"]);
+        assert_eq!(tags.descriptive().next().map(|tag| tag.title.as_str()), Some("James T. Kirk/Spock"));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aWorkPage_WHEN_newFromWorkIsCalled_THEN_allItsMetadataWillBeKept() {
+        // GIVEN a work page
+        let page = Html::parse_document(r#"<html><body><div class="wrapper"><dl class="work meta group">
+            <dt class="language">Language:</dt><dd class="language">English</dd>
+            <dt class="series">Series:</dt><dd class="series"><span class="series"><span class="position">Part 2 of <a href="/series/7">Shore Leave</a></span></span></dd>
+            <dt class="collections">Collections:</dt><dd class="collections"><a href="/collections/trek">Trek Fest</a></dd>
+            <dd class="stats"><dl class="stats">
+                <dt class="published">Published:</dt><dd class="published">2024-03-05</dd>
+                <dt class="status">Updated:</dt><dd class="status">2024-04-01</dd>
+                <dt class="chapters">Chapters:</dt><dd class="chapters">2/?</dd>
+            </dl></dd>
+        </dl></div><div id="workskin"><div class="preface group">
+            <h2 class="title heading"><img alt="(Restricted)" title="Restricted" src="/images/lockblue.png"> Work 1 </h2>
+            <h3 class="byline heading"><a rel="author" href="/users/writer/pseuds/writer">writer</a></h3>
+            <div class="notes module"><ul class="associations">
+                <li>For <a href="/users/friend/gifts">friend</a>.</li>
+                <li>Inspired by <a href="/works/2">Amok Time</a> by <a href="/users/other">other</a>.</li>
+            </ul></div>
+        </div></div></body></html>"#);
+
+        // WHEN new_from_work is called
+        let info = Ao3Info::new_from_work(&page, "1".to_string()).unwrap();
+
+        // THEN all its metadata will be kept
+        assert_eq!(info.title, "Work 1");
+        assert!(info.restricted);
+        assert_eq!(info.language, "English");
+        assert_eq!(info.series, vec![SeriesPart { part: 2, series: Link { title: "Shore Leave".to_string(), location: "/series/7".to_string() } }]);
+        assert_eq!(info.collections[0].title, "Trek Fest");
+        assert_eq!(info.gifts[0].title, "friend");
+        assert_eq!(info.inspirations[0].location, "/works/2");
+        assert_eq!(info.published, NaiveDate::from_ymd_opt(2024, 3, 5).unwrap());
+        assert_eq!(info.updated, NaiveDate::from_ymd_opt(2024, 4, 1).unwrap());
+    }
+
+    const SLASH_SYMBOLS: &str = r#"
+        <li><a class="help symbol question modal modal-attached" title="Symbols key" aria-controls="modal" href="/help/symbols-key.html"><span class="rating-teen rating" title="Teen And Up Audiences"><span class="text">Teen And Up Audiences</span></span></a></li>
+        <li><a class="help symbol question modal modal-attached" title="Symbols key" aria-controls="modal" href="/help/symbols-key.html"><span class="warning-no warnings" title="No Archive Warnings Apply"><span class="text">No Archive Warnings Apply</span></span></a></li>
+        <li><a class="help symbol question modal modal-attached" title="Symbols key" aria-controls="modal" href="/help/symbols-key.html"><span class="category-slash category" title="M/M"><span class="text">M/M</span></span></a></li>
+        <li><a class="help symbol question modal modal-attached" title="Symbols key" aria-controls="modal" href="/help/symbols-key.html"><span class="complete-yes iswip" title="Complete Work"><span class="text">Complete Work</span></span></a></li>"#;
+
+    const MULTI_SYMBOLS: &str = r#"
+        <li><a class="help symbol question modal modal-attached" title="Symbols key" aria-controls="modal" href="/help/symbols-key.html"><span class="rating-explicit rating" title="Explicit"><span class="text">Explicit</span></span></a></li>
+        <li><a class="help symbol question modal modal-attached" title="Symbols key" aria-controls="modal" href="/help/symbols-key.html"><span class="warning-yes warnings" title="Major Character Death"><span class="text">Major Character Death</span></span></a></li>
+        <li><a class="help symbol question modal modal-attached" title="Symbols key" aria-controls="modal" href="/help/symbols-key.html"><span class="category-multi category" title="F/M, M/M"><span class="text">F/M, M/M</span></span></a></li>
+        <li><a class="help symbol question modal modal-attached" title="Symbols key" aria-controls="modal" href="/help/symbols-key.html"><span class="complete-no iswip" title="Work in Progress"><span class="text">Work in Progress</span></span></a></li>"#;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_blurbSymbols_WHEN_requiredTagsAreParsed_THEN_everySymbolWillBeRecognized() {
+        // GIVEN blurb symbols
+        let slash = SLASH_SYMBOLS.to_string();
+        let multi = MULTI_SYMBOLS.to_string();
+        let none = SLASH_SYMBOLS.replace("category-slash", "category-none");
+
+        // WHEN required tags are parsed
+        let slash_tags = RequiredTags::new(slash);
+        let multi_tags = RequiredTags::new(multi);
+        let none_tags = RequiredTags::new(none);
+
+        // THEN every symbol will be recognized
+        assert_eq!(slash_tags, RequiredTags {
+            rating: Rating::Teen,
+            warnings: Warning::No,
+            categories: BTreeSet::from([Category::Slash]),
+            complete: true,
+        });
+        assert_eq!(multi_tags, RequiredTags {
+            rating: Rating::Explicit,
+            warnings: Warning::Yes,
+            categories: BTreeSet::from([Category::Het, Category::Slash]),
+            complete: false,
+        });
+        assert!(none_tags.categories.is_empty());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_requiredTags_WHEN_asIconsIsCalled_THEN_categoriesWillBeCombined() {
+        // GIVEN required tags
+        let rect = rect![0, 0, 100, 100];
+        let slash = RequiredTags::new(SLASH_SYMBOLS.to_string());
+        let multi = RequiredTags::new(MULTI_SYMBOLS.to_string());
+
+        // WHEN as_icons is called
+        let slash_icons = slash.as_icons(rect);
+        let multi_icons = multi.as_icons(rect);
+
+        // THEN categories will be combined
+        let names = |icons: Vec<DisabledIcon>| icons.into_iter().map(|icon| icon.name).collect::<Vec<_>>();
+        assert_eq!(names(slash_icons), vec!["blank", "teen", "slash", "complete"]);
+        assert_eq!(names(multi_icons), vec!["warning", "explicit", "multi", "wip"]);
+    }
+}
@@ -22,6 +22,9 @@ use crate::library::Library;
 use crate::font::Fonts;
 use crate::rtc::Rtc;
 use crate::http::HttpClient;
+use crate::http::outbox::Outbox;
+use crate::http::comments::CommentDrafts;
+use crate::selectors::{SelectorProfile, set_selectors, SELECTORS_PATH, USER_SELECTORS_PATH};
 
 const KEYBOARD_LAYOUTS_DIRNAME: &str = "keyboard-layouts";
 const DICTIONARIES_DIRNAME: &str = "dictionaries";
@@ -51,6 +54,8 @@ pub struct Context {
     pub shared: bool,
     pub online: bool,
     pub client: HttpClient,
+    pub outbox: Outbox,
+    pub comment_drafts: CommentDrafts,
 }
 
 impl Context {
@@ -111,6 +116,8 @@ impl Context {
         let rotation = CURRENT_DEVICE.transformed_rotation(fb.rotation());
         let rng = Xoroshiro128Plus::seed_from_u64(Local::now().timestamp_subsec_nanos() as u64);
         let client = HttpClient::new(&mut settings);
+        let outbox = Outbox::load(settings.ao3.outbox_path.clone());
+        let comment_drafts = CommentDrafts::load(settings.ao3.drafts_path.clone());
         Context {
             fb,
             rtc,
@@ -131,7 +138,9 @@ impl Context {
             covered: false,
             shared: false,
             online: false,
-            client: client
+            client,
+            outbox,
+            comment_drafts,
         }
     }
 
@@ -169,6 +178,10 @@ impl Context {
         }
     }
 
+    pub fn load_selectors(&mut self) {
+        set_selectors(SelectorProfile::load(SELECTORS_PATH, USER_SELECTORS_PATH));
+    }
+
     pub fn load_dictionaries(&mut self) {
         let glob = Glob::new("**/*.index").unwrap().compile_matcher();
         for entry in WalkDir::new(Path::new(DICTIONARIES_DIRNAME)).min_depth(1)
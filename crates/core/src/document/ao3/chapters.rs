@@ -0,0 +1,230 @@
+use fxhash::FxHashMap;
+use scraper::{ElementRef, Html, Selector};
+use crate::html::{html_text, HtmlText};
+use crate::selectors::{selectors, Css};
+use crate::document::html::dom::XmlTree;
+
+// The id of the element holding the text of a work posted in one go.
+const ONE_SHOT_ID: &str = "chapters";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ao3Chapter {
+    pub title: String,
+    pub summary: Vec<HtmlText>,
+    // The notes before and after the chapter's text.
+    pub notes: Vec<HtmlText>,
+    pub end_notes: Vec<HtmlText>,
+    // The id of the chapter's element, for links.
+    pub id: String,
+    // The chapter's page, if it has one of its own.
+    pub path: Option<String>,
+    // Within the work's body: the chapter ends where the next one starts.
+    pub start_offset: usize,
+    pub end_offset: usize,
+}
+
+// An entry of the chapter index of a work shown a chapter at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChapterIndexEntry {
+    pub title: String,
+    // The chapter's page.
+    pub path: String,
+}
+
+fn select_html_text(el: ElementRef, select: &Css) -> Vec<HtmlText> {
+    let selector = Selector::parse(select).unwrap();
+    el.select(&selector).next().map(html_text).unwrap_or_default()
+}
+
+fn select_href(el: ElementRef, select: &Css) -> Option<String> {
+    let selector = Selector::parse(select).unwrap();
+    el.select(&selector).next()
+      .and_then(|link| link.value().attr("href"))
+      .map(String::from)
+}
+
+fn select_title(el: ElementRef, select: &Css) -> String {
+    let selector = Selector::parse(select).unwrap();
+    el.select(&selector).next()
+      .map(|title| title.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "))
+      .unwrap_or_default()
+}
+
+// Parses the chapters of a work page. `content` is the work's body, in
+// which the offsets are given. Works posted in one go are a single
+// chapter, titled after the work.
+pub fn scrape_chapters(document: &Html, content: &XmlTree, title: &str, size: usize) -> Vec<Ao3Chapter> {
+    let profile = selectors();
+    let select = &profile.work;
+    let offsets: FxHashMap<&str, usize> = content.root().descendants()
+                                                 .filter_map(|node| node.attribute("id").map(|id| (id, node.offset())))
+                                                 .collect();
+
+    let chapter_selector = Selector::parse(&select.chapter.element).unwrap();
+    let mut chapters: Vec<Ao3Chapter> = Vec::new();
+
+    for el in document.select(&chapter_selector) {
+        let id = el.value().id().unwrap_or_default().to_string();
+        let start_offset = offsets.get(id.as_str()).copied()
+                                  .or_else(|| chapters.last().map(|chap| chap.start_offset))
+                                  .unwrap_or(0);
+        chapters.push(Ao3Chapter {
+            title: select_title(el, &select.chapter.title),
+            summary: select_html_text(el, &select.chapter.summary),
+            notes: select_html_text(el, &select.chapter.notes),
+            end_notes: select_html_text(el, &select.chapter.end_notes),
+            id,
+            path: select_href(el, &select.chapter.link),
+            start_offset,
+            end_offset: size,
+        });
+    }
+
+    if chapters.is_empty() {
+        chapters.push(Ao3Chapter {
+            title: title.to_string(),
+            summary: Vec::new(),
+            notes: Vec::new(),
+            end_notes: Vec::new(),
+            id: ONE_SHOT_ID.to_string(),
+            path: None,
+            start_offset: offsets.get(ONE_SHOT_ID).copied().unwrap_or(0),
+            end_offset: size,
+        });
+    }
+
+    for i in 1..chapters.len() {
+        chapters[i - 1].end_offset = chapters[i].start_offset;
+    }
+
+    chapters
+}
+
+// The index reads "2. Title", and "2. Chapter 2" for untitled chapters:
+// titles are given the way the full work shows them.
+fn index_title(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let Some((number, title)) = text.split_once(". ").filter(|(number, _)| number.chars().all(|c| c.is_ascii_digit())) else {
+        return text;
+    };
+    let label = format!("Chapter {}", number);
+    if title == label {
+        label
+    } else {
+        format!("{}: {}", label, title)
+    }
+}
+
+pub fn scrape_chapter_index(document: &Html, work_id: &str) -> Vec<ChapterIndexEntry> {
+    let selector = Selector::parse(&selectors().work.chapter_index).unwrap();
+    document.select(&selector)
+            .filter_map(|option| {
+                let chapter_id = option.value().attr("value")?;
+                Some(ChapterIndexEntry {
+                    title: index_title(&option.text().collect::<String>()),
+                    path: format!("/works/{}/chapters/{}?view_adult=true", work_id, chapter_id),
+                })
+            })
+            .collect()
+}
+
+// The chapter of a page showing a single one, followed by the work's end
+// notes on the last chapter.
+pub fn chapter_html(document: &Html) -> Option<String> {
+    let profile = selectors();
+    let chapter_selector = Selector::parse(&profile.work.chapter.element).unwrap();
+    let afterword_selector = Selector::parse(&profile.work.afterword).unwrap();
+    let mut html = document.select(&chapter_selector).next()?.html();
+    if let Some(afterword) = document.select(&afterword_selector).next() {
+        html.push_str(&afterword.html());
+    }
+    Some(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::html::xml::XmlParser;
+
+    const WORKSKIN: &str = r#"<div class="preface group"><h2 class="title">Shore Leave</h2></div>
+<div id="chapters" role="article">
+<div class="chapter" id="chapter-1">
+<div class="chapter preface group" role="complementary">
+<h3 class="title"><a href="/works/1/chapters/11">Chapter 1</a>: Arrival</h3>
+<div id="summary" class="summary module"><h3 class="heading">Summary:</h3><blockquote class="userstuff"><p>They land.</p></blockquote></div>
+<div id="notes" class="notes module"><h3 class="heading">Notes:</h3><blockquote class="userstuff"><p>Thanks to my beta.</p></blockquote></div>
+</div>
+<div class="userstuff module" role="article"><p>The planet was green.</p></div>
+<div id="chapter_1_endnotes" class="end notes module"><h3 class="heading">Notes:</h3><blockquote class="userstuff"><p>More soon.</p></blockquote></div>
+</div>
+<div class="chapter" id="chapter-2">
+<div class="chapter preface group" role="complementary">
+<h3 class="title"><a href="/works/1/chapters/12">Chapter 2</a></h3>
+</div>
+<div class="userstuff module" role="article"><p>They left.</p></div>
+</div>
+</div>"#;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aWorkWithChapters_WHEN_scrapeChaptersIsCalled_THEN_eachChapterWillBeParsedAsAUnit() {
+        // GIVEN a work with chapters
+        let document = Html::parse_document(&format!(r#"<html><body><div id="workskin">{}</div></body></html>"#, WORKSKIN));
+        let content = XmlParser::new(WORKSKIN).parse();
+
+        // WHEN scrape_chapters is called
+        let chapters = scrape_chapters(&document, &content, "Shore Leave", WORKSKIN.len());
+
+        // THEN each chapter will be parsed as a unit
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title, "Chapter 1: Arrival");
+        assert_eq!(chapters[0].summary, vec![HtmlText::Text("They land.".to_string())]);
+        assert_eq!(chapters[0].notes, vec![HtmlText::Text("Thanks to my beta.".to_string())]);
+        assert_eq!(chapters[0].end_notes, vec![HtmlText::Text("More soon.".to_string())]);
+        assert_eq!(chapters[1].title, "Chapter 2");
+        assert!(chapters[1].notes.is_empty());
+        assert!(WORKSKIN[chapters[0].start_offset..].starts_with(r#"<div class="chapter" id="chapter-1">"#));
+        assert_eq!(chapters[1].path.as_deref(), Some("/works/1/chapters/12"));
+        assert_eq!(chapters[0].end_offset, chapters[1].start_offset);
+        assert_eq!(chapters[1].end_offset, WORKSKIN.len());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aOneShot_WHEN_scrapeChaptersIsCalled_THEN_itWillBeASingleChapter() {
+        // GIVEN a one shot
+        let workskin = r#"<div class="preface group"><h2 class="title">Shore Leave</h2></div>
+<div id="chapters" role="article"><div class="userstuff"><p>The planet was green.</p></div></div>"#;
+        let document = Html::parse_document(&format!(r#"<html><body><div id="workskin">{}</div></body></html>"#, workskin));
+        let content = XmlParser::new(workskin).parse();
+
+        // WHEN scrape_chapters is called
+        let chapters = scrape_chapters(&document, &content, "Shore Leave", workskin.len());
+
+        // THEN it will be a single chapter
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].title, "Shore Leave");
+        assert_eq!(chapters[0].id, "chapters");
+        assert!(chapters[0].start_offset > 0);
+        assert_eq!(chapters[0].end_offset, workskin.len());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aChapterPage_WHEN_scrapeChapterIndexIsCalled_THEN_eachChapterWillLinkToItsPage() {
+        // GIVEN a chapter page
+        let document = Html::parse_document(r#"<html><body><form><select name="selected_id" id="selected_id">
+<option selected="selected" value="11">1. Arrival</option>
+<option value="12">2. Chapter 2</option>
+<option value="13">3.  Shore   Leave</option>
+</select></form></body></html>"#);
+
+        // WHEN scrape_chapter_index is called
+        let index = scrape_chapter_index(&document, "1");
+
+        // THEN each chapter will link to its page
+        let titles: Vec<&str> = index.iter().map(|entry| entry.title.as_str()).collect();
+        assert_eq!(titles, vec!["Chapter 1: Arrival", "Chapter 2", "Chapter 3: Shore Leave"]);
+        assert_eq!(index[1].path, "/works/1/chapters/12?view_adult=true");
+    }
+}
@@ -1,18 +1,29 @@
+mod chapters;
+mod notes;
+mod workskin;
+
 use std::io::Read;
+use std::ops::Range;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use fxhash::FxHashMap;
 use regex::Regex;
 use anyhow::Error;
 // use scraper::Node;
-use crate::html::{scrape, scrape_kudos_csrf};
+use crate::html::{scrape, scrape_kudos_csrf, HtmlText, Link};
 use crate::ao3_metadata::Ao3Info;
+use crate::selectors::selectors;
+use crate::settings::NotesDisplay;
+use crate::http::{Ao3Error, DownloadFormat};
+use crate::http::download::download_links;
+use crate::http::comments::{scrape_comment_fields, CommentFields};
+use crate::http::bookmarks::{scrape_bookmark_fields, BookmarkFields};
 use crate::framebuffer::Pixmap;
 use crate::helpers::decode_entities;
 use crate::document::{Document, Location, TextLocation, TocEntry, BoundedText, Chapter};
 use crate::unit::pt_to_px;
 use crate::geom::{Edge, CycleDir, Boundary};
-use super::html::dom::{XmlTree, NodeRef};
+use super::html::dom::{XmlTree, NodeRef, Attributes, element, text};
 // use super::html::dom::Node;
 use super::html::engine::{Page, Engine};
 use super::html::layout::{StyleData, LoopContext};
@@ -22,21 +33,104 @@ use super::html::style::StyleSheet;
 use super::html::css::CssParser;
 use super::html::xml::XmlParser;
 
+pub use self::chapters::Ao3Chapter;
+use self::chapters::{scrape_chapters, scrape_chapter_index, chapter_html, ChapterIndexEntry};
+use self::notes::{apply_notes_display, parse_notes_uri, scrape_notes};
+use self::workskin::scrape_work_skin;
+
 const VIEWER_STYLESHEET: &str = "css/epub.css";
 const USER_STYLESHEET: &str = "css/epub-user.css";
 
+// The room each chapter read on its own is given within the work, far
+// more than any chapter needs, so that locations don't move as the
+// chapters come in.
+const CHAPTER_SPAN: usize = 1 << 22;
+const LOADING_TEXT: &str = "Loading…";
+
 type UriCache = FxHashMap<String, usize>;
 
+fn workskin_html(document: &scraper::Html) -> Result<String, Ao3Error> {
+    let profile = selectors();
+    let select = &profile.work.body;
+    let body_selector = scraper::Selector::parse(select).unwrap();
+    document.select(&body_selector).next()
+            .map(|body| body.inner_html())
+            .ok_or_else(|| Ao3Error::Parse(select.to_string()))
+}
+
+fn work_content(body_text: &str, notes_display: NotesDisplay, part: usize) -> XmlTree {
+    let mut content = XmlParser::new(body_text).parse();
+    content.wrap_lost_inlines();
+    apply_notes_display(&mut content, notes_display, part);
+    content
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PartState {
+    Loaded,
+    Missing,
+    Requested,
+}
+
+// A part of the work laid out on its own: the whole work, or, for works
+// read a chapter at a time, the first page and each of the next chapters.
+struct Part {
+    start_offset: usize,
+    // The chapter's page, for the parts that are fetched later.
+    path: Option<String>,
+    state: PartState,
+    body: String,
+    content: XmlTree,
+    pages: Vec<Page>,
+}
+
+impl Part {
+    fn new(start_offset: usize, body: String, content: XmlTree) -> Part {
+        Part {
+            start_offset,
+            path: None,
+            state: PartState::Loaded,
+            body,
+            content,
+            pages: Vec::new(),
+        }
+    }
+
+    // Shows the chapter's title until it's loaded.
+    fn placeholder(start_offset: usize, entry: ChapterIndexEntry, id: &str) -> Part {
+        let mut content = XmlTree::new();
+        let mut attributes = Attributes::default();
+        attributes.insert("class".to_string(), "chapter".to_string());
+        attributes.insert("id".to_string(), id.to_string());
+        let chapter_id = content.root_mut().append(element("div", 0, attributes));
+        let title_id = content.get_mut(chapter_id).append(element("h3", 0, Attributes::default()));
+        content.get_mut(title_id).append(text(&entry.title, 0));
+        let paragraph_id = content.get_mut(chapter_id).append(element("p", 0, Attributes::default()));
+        content.get_mut(paragraph_id).append(text(LOADING_TEXT, 0));
+
+        Part {
+            start_offset,
+            path: Some(entry.path),
+            state: PartState::Missing,
+            body: String::new(),
+            content,
+            pages: Vec::new(),
+        }
+    }
+}
+
 pub struct Ao3Document {
     text: String,
     url: Option<String>,
     parsed_doc: scraper::html::Html,
-    content: XmlTree,
+    parts: Vec<Part>,
+    work_skin: StyleSheet,
     engine: Engine,
-    pages: Vec<Page>,
     parent: PathBuf,
     size: usize,
     ao3info: Ao3Info,
+    chapters: Vec<Ao3Chapter>,
+    notes_display: NotesDisplay,
     viewer_stylesheet: PathBuf,
     user_stylesheet: PathBuf,
     ignore_document_css: bool,
@@ -48,43 +142,37 @@ unsafe impl Sync for Ao3Document {}
 impl Ao3Document {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Ao3Document, Error> {
         let mut file = File::open(&path)?;
-        let size = file.metadata()?.len() as usize;
         let mut text = String::new();
         file.read_to_string(&mut text)?;
         let document = scraper::Html::parse_document(&text);
-        let body_selector = scraper::Selector::parse("#workskin").unwrap();
-        let body_text = document.select(&body_selector).next().unwrap().inner_html();
-        let mut content = XmlParser::new(&body_text).parse();
-        content.wrap_lost_inlines();
+        let body_text = workskin_html(&document)?;
         let parent = path.as_ref().parent().unwrap_or_else(|| Path::new(""));
 
-        let meta = scrape(&document, ".wrapper .meta");
-        let preface = scrape(&document, "#workskin .preface");
-        let ao3info = Ao3Info::new_from_work(meta, preface, "0".to_string());
+        let ao3info = Ao3Info::new_from_work(&document, "0".to_string())?;
 
-        Ok(Ao3Document {
+        let mut doc = Ao3Document {
             text,
             url: None,
+            work_skin: scrape_work_skin(&document),
             parsed_doc: document,
-            content,
+            parts: Vec::new(),
             engine: Engine::new(),
-            pages: Vec::new(),
             parent: parent.to_path_buf(),
-            size,
+            size: 0,
             viewer_stylesheet: PathBuf::from(VIEWER_STYLESHEET),
             user_stylesheet: PathBuf::from(USER_STYLESHEET),
             ignore_document_css: false,
-            ao3info
-        })
+            ao3info,
+            chapters: Vec::new(),
+            notes_display: NotesDisplay::Show,
+        };
+        doc.load_parts(body_text);
+        Ok(doc)
     }
 
-    pub fn new_from_memory(text: &str, url: Option<&str>) -> Ao3Document {
+    pub fn new_from_memory(text: &str, url: Option<&str>) -> Result<Ao3Document, Ao3Error> {
         let document = scraper::Html::parse_document(&text);
-        let body_selector = scraper::Selector::parse("#workskin").unwrap();
-        let body_text = document.select(&body_selector).next().unwrap().inner_html();
-        let size = body_text.len();
-        let mut content = XmlParser::new(&body_text).parse();
-        content.wrap_lost_inlines();
+        let body_text = workskin_html(&document)?;
 
         let mut id = "0".to_string(); 
         let mut rewrapped_url = None;
@@ -96,73 +184,122 @@ impl Ao3Document {
             rewrapped_url = Some(unwrapped_url.to_string());
         }
 
-        let meta = scrape(&document, ".wrapper .meta");
-        let preface = scrape(&document, "#workskin .preface");
-        let ao3info = Ao3Info::new_from_work(meta, preface, id);
+        let ao3info = Ao3Info::new_from_work(&document, id)?;
 
-        Ao3Document {
+        let mut doc = Ao3Document {
             text: text.to_string(),
             url: rewrapped_url,
+            work_skin: scrape_work_skin(&document),
             parsed_doc: document,
-            content,
+            parts: Vec::new(),
             engine: Engine::new(),
-            pages: Vec::new(),
             parent: PathBuf::default(),
-            size,
+            size: 0,
             viewer_stylesheet: PathBuf::from(VIEWER_STYLESHEET),
             user_stylesheet: PathBuf::from(USER_STYLESHEET),
             ignore_document_css: false,
-            ao3info
-        }
+            ao3info,
+            chapters: Vec::new(),
+            notes_display: NotesDisplay::Show,
+        };
+        doc.load_parts(body_text);
+        Ok(doc)
     }
 
     // pub fn new_from_uri(uri: &str) -> Ao3Document {
 
     // }
-    pub fn update(&mut self, text: &str) {
+    pub fn update(&mut self, text: &str) -> Result<(), Ao3Error> {
         self.parsed_doc = scraper::Html::parse_document(&text);
-        let body_selector = scraper::Selector::parse("#workskin").unwrap();
-        let body_text = self.parsed_doc.select(&body_selector).next().unwrap().inner_html();
-        self.size = body_text.len();
-        self.content = XmlParser::new(&body_text).parse();
-        self.content.wrap_lost_inlines();
+        let body_text = workskin_html(&self.parsed_doc)?;
+        self.work_skin = scrape_work_skin(&self.parsed_doc);
+        self.load_parts(body_text);
         self.text = text.to_string();
-        self.pages.clear();
+        Ok(())
+    }
+
+    // Works shown a chapter at a time come with an index of their chapters:
+    // the chapters past the page are laid out once they're loaded, in parts
+    // of their own.
+    fn load_parts(&mut self, body_text: String) {
+        let index = scrape_chapter_index(&self.parsed_doc, &self.ao3info.id);
+        let content = work_content(&body_text, self.notes_display, 0);
+        self.chapters = scrape_chapters(&self.parsed_doc, &content, &self.ao3info.title, body_text.len());
+        self.size = body_text.len();
+        self.parts = vec![Part::new(0, body_text, content)];
+
+        if index.len() <= self.chapters.len() {
+            return;
+        }
+
+        let mut start_offset = self.size.max(CHAPTER_SPAN);
+        for (number, entry) in index.into_iter().enumerate().skip(self.chapters.len()) {
+            let id = format!("chapter-{}", number + 1);
+            self.chapters.push(Ao3Chapter {
+                title: entry.title.clone(),
+                summary: Vec::new(),
+                notes: Vec::new(),
+                end_notes: Vec::new(),
+                id: id.clone(),
+                path: Some(entry.path.clone()),
+                start_offset,
+                end_offset: start_offset,
+            });
+            self.parts.push(Part::placeholder(start_offset, entry, &id));
+            start_offset += CHAPTER_SPAN;
+        }
+        self.size = start_offset;
+    }
+
+    fn clear_pages(&mut self) {
+        for part in &mut self.parts {
+            part.pages.clear();
+        }
     }
 
     pub fn set_margin(&mut self, margin: &Edge) {
         self.engine.set_margin(margin);
-        self.pages.clear();
+        self.clear_pages();
     }
 
     pub fn set_font_size(&mut self, font_size: f32) {
         self.engine.set_font_size(font_size);
-        self.pages.clear();
+        self.clear_pages();
     }
 
     pub fn set_viewer_stylesheet<P: AsRef<Path>>(&mut self, path: P) {
         self.viewer_stylesheet = path.as_ref().to_path_buf();
-        self.pages.clear();
+        self.clear_pages();
     }
 
     pub fn set_user_stylesheet<P: AsRef<Path>>(&mut self, path: P) {
         self.user_stylesheet = path.as_ref().to_path_buf();
-        self.pages.clear();
+        self.clear_pages();
     }
 
     #[inline]
-    fn page_index(&mut self, offset: usize) -> Option<usize> {
-        if self.pages.is_empty() {
-            self.pages = self.build_pages();
+    fn part_index(&self, offset: usize) -> usize {
+        self.parts.iter().rposition(|part| part.start_offset <= offset).unwrap_or(0)
+    }
+
+    fn pages(&mut self, index: usize) -> &[Page] {
+        if self.parts[index].pages.is_empty() {
+            self.parts[index].pages = self.build_pages(index);
         }
-        if self.pages.len() < 2 || self.pages[1].first().map(|dc| offset < dc.offset()) == Some(true) {
+        &self.parts[index].pages
+    }
+
+    #[inline]
+    fn page_index(&mut self, offset: usize, index: usize) -> Option<usize> {
+        let pages = self.pages(index);
+        if pages.len() < 2 || pages[1].first().map(|dc| offset < dc.offset()) == Some(true) {
             return Some(0);
-        } else if self.pages[self.pages.len() - 1].first().map(|dc| offset >= dc.offset()) == Some(true) {
-            return Some(self.pages.len() - 1);
+        } else if pages[pages.len() - 1].first().map(|dc| offset >= dc.offset()) == Some(true) {
+            return Some(pages.len() - 1);
         } else {
-            for i in 1..self.pages.len()-1 {
-                if self.pages[i].first().map(|dc| offset >= dc.offset()) == Some(true) &&
-                   self.pages[i+1].first().map(|dc| offset < dc.offset()) == Some(true) {
+            for i in 1..pages.len()-1 {
+                if pages[i].first().map(|dc| offset >= dc.offset()) == Some(true) &&
+                   pages[i+1].first().map(|dc| offset < dc.offset()) == Some(true) {
                     return Some(i);
                 }
             }
@@ -170,29 +307,37 @@ impl Ao3Document {
         None
     }
 
+    // The page of the part holding the offset.
+    fn page(&mut self, offset: usize) -> Option<&Page> {
+        let index = self.part_index(offset);
+        let page_index = self.page_index(offset, index)?;
+        self.parts[index].pages.get(page_index)
+    }
+
     fn resolve_link(&mut self, uri: &str, cache: &mut UriCache) -> Option<usize> {
         let frag_index = uri.find('#')?;
         let name = &uri[..frag_index];
-        let content = self.content.clone();
-        self.cache_uris(content.root(), name, cache);
+        for part in &self.parts {
+            Ao3Document::cache_uris(part.content.root(), name, part.start_offset, cache);
+        }
         cache.get(uri).cloned()
     }
     // fn resolve_remote(&mut self, uri: &str) -> Option<usize> {
     //     return reqwest::blocking::get(uri).ok()?.text().ok()?;
     // }
 
-    fn cache_uris(&mut self, node: NodeRef, name: &str, cache: &mut UriCache) {
+    fn cache_uris(node: NodeRef, name: &str, start_offset: usize, cache: &mut UriCache) {
         if let Some(id) = node.attribute("id") {
-            cache.insert(format!("{}#{}", name, id), node.offset());
+            cache.insert(format!("{}#{}", name, id), start_offset + node.offset());
         }
         for child in node.children() {
-            self.cache_uris(child, name, cache);
+            Ao3Document::cache_uris(child, name, start_offset, cache);
         }
     }
 
 
 
-    fn build_pages(&mut self) -> Vec<Page> {
+    fn build_pages(&mut self, index: usize) -> Vec<Page> {
         let mut stylesheet = StyleSheet::new();
         let spine_dir = PathBuf::default();
 
@@ -206,38 +351,18 @@ impl Ao3Document {
             stylesheet.append(&mut css, true);
         }
 
-        // if !self.ignore_document_css {
-        //     if let Some(head) = self.content.find("head") {
-        //         if let Some(children) = head.children() {
-        //             for child in children {
-        //                 if child.tag_name() == Some("link") && child.attr("rel") == Some("stylesheet") {
-        //                     if let Some(href) = child.attr("href") {
-        //                         if let Some(name) = spine_dir.join(href).normalize().to_str() {
-        //                             if let Ok(buf) = self.parent.fetch(name) {
-        //                                 if let Ok(text) = String::from_utf8(buf) {
-        //                                     let (mut css, _) = CssParser::new(&text).parse(RuleKind::Document);
-        //                                     stylesheet.append(&mut css);
-        //                                 }
-        //                             }
-        //                         }
-        //                     }
-        //                 } else if child.tag_name() == Some("style") && child.attr("type") == Some("text/css") {
-        //                     if let Some(text) = child.text() {
-        //                         let (mut css, _) = CssParser::new(text).parse(RuleKind::Document);
-        //                         stylesheet.append(&mut css);
-        //                     }
-        //                 }
-        //             }
-        //         }
-        //     }
-        // }
+        if !self.ignore_document_css {
+            let mut css = self.work_skin.clone();
+            stylesheet.append(&mut css, true);
+        }
 
         let mut pages = Vec::new();
 
         let mut rect = self.engine.rect();
         rect.shrink(&self.engine.margin);
 
-        let language = self.content.root()
+        let part = &self.parts[index];
+        let language = part.content.root()
                            .find("html")
                            .and_then(|html| html.attribute("xml:lang"))
                            .map(String::from);
@@ -260,19 +385,19 @@ impl Ao3Document {
         };
 
         let root_data = RootData {
-            start_offset: 0,
+            start_offset: part.start_offset,
             spine_dir,
             rect,
         };
 
         pages.push(Vec::new());
 
-        self.engine.build_display_list(self.content.root(), &style, &loop_context, &stylesheet, &root_data, &mut self.parent, &mut draw_state, &mut pages);
+        self.engine.build_display_list(part.content.root(), &style, &loop_context, &stylesheet, &root_data, &mut self.parent, &mut draw_state, &mut pages);
 
         pages.retain(|page| !page.is_empty());
 
         if pages.is_empty() {
-            pages.push(vec![DrawCommand::Marker(self.content.root().offset())]);
+            pages.push(vec![DrawCommand::Marker(part.start_offset + part.content.root().offset())]);
         }
 
         pages
@@ -303,52 +428,17 @@ impl Ao3Document {
     //     }
     // }
 
-    fn previous_chapter<'a>(&mut self, chap: Option<&TocEntry>, start_offset: usize, end_offset: usize, toc: &'a [TocEntry]) -> Option<&'a TocEntry> {
-        for entry in toc.iter().rev() {
-            let result = self.previous_chapter(chap, start_offset, end_offset, &entry.children);
-            if result.is_some() {
-                return result;
-            }
-
-            if let Some(chap) = chap {
-                if entry.index < chap.index {
-                    let entry_offset = self.resolve_location(entry.location.clone())?;
-                    if entry_offset < start_offset || entry_offset >= end_offset {
-                        return Some(entry)
-                    }
-                }
-            } else {
-                let entry_offset = self.resolve_location(entry.location.clone())?;
-                if entry_offset < start_offset {
-                    return Some(entry);
-                }
-            }
-        }
-        None
+    pub fn has_work_skin(&self) -> bool {
+        !self.work_skin.rules.is_empty()
     }
 
-    fn next_chapter<'a>(&mut self, chap: Option<&TocEntry>, start_offset: usize, end_offset: usize, toc: &'a [TocEntry]) -> Option<&'a TocEntry> {
-        for entry in toc {
-            if let Some(chap) = chap {
-                if entry.index > chap.index {
-                    let entry_offset = self.resolve_location(entry.location.clone())?;
-                    if entry_offset < start_offset || entry_offset >= end_offset {
-                        return Some(entry)
-                    }
-                }
-            } else {
-                let entry_offset = self.resolve_location(entry.location.clone())?;
-                if entry_offset >= end_offset {
-                    return Some(entry);
-                }
-            }
+    pub fn chapters(&self) -> &[Ao3Chapter] {
+        &self.chapters
+    }
 
-            let result = self.next_chapter(chap, start_offset, end_offset, &entry.children);
-            if result.is_some() {
-                return result;
-            }
-        }
-        None
+    // The chapter the offset belongs to, if it's past the work's preface.
+    fn chapter_index(&self, offset: usize) -> Option<usize> {
+        self.chapters.iter().rposition(|chap| chap.start_offset <= offset)
     }
 
     // pub fn series(&self) -> Option<(String, String)> {
@@ -403,8 +493,10 @@ impl Document for Ao3Document {
     }
 
     fn about(&self) -> String {
-        let blurb_select = scraper::Selector::parse(".wrapper .meta").unwrap();
-        self.parsed_doc.select(&blurb_select).next().unwrap().inner_html()
+        let blurb_select = scraper::Selector::parse(&selectors().work.meta).unwrap();
+        self.parsed_doc.select(&blurb_select).next()
+            .map(|blurb| blurb.inner_html())
+            .unwrap_or_default()
     }
 
     fn kudos_token(&self) -> Option<String> {
@@ -416,96 +508,71 @@ impl Document for Ao3Document {
 
     }
 
+    fn comment_fields(&self) -> Option<CommentFields> {
+        scrape_comment_fields(&self.parsed_doc)
+    }
+
+    fn bookmark_fields(&self) -> Option<BookmarkFields> {
+        scrape_bookmark_fields(&self.parsed_doc)
+    }
+
+    fn download_links(&self) -> Vec<(DownloadFormat, Link)> {
+        download_links(&self.parsed_doc)
+    }
+
     fn work_id(&self) -> String {
         let mut work_id = "".to_string();
         if let Some(unwrapped_url) = &self.url {
             let work_url = Regex::new(r"works/(\d+)").unwrap();
-            let caps = work_url.captures(&unwrapped_url).unwrap();
-            work_id = caps[1].to_string();
+            if let Some(caps) = work_url.captures(unwrapped_url) {
+                work_id = caps[1].to_string();
+            }
         }
         work_id
     }
 
     fn toc(&mut self) -> Option<Vec<TocEntry>> {
-        let mut entries = Vec::new();
-        let mut index = 0;
-
-        let chapter_selector = scraper::Selector::parse(".chapter h3.title").unwrap();
-
-        for (i, element) in self.parsed_doc.select(&chapter_selector).enumerate() {
-            let text = element.text().collect::<Vec<_>>();
-            let title = text.join("");
-            // TODO: need to add the base work path to this
-            let location = Location::Uri(format!("#chapter-{}", i + 1));
-            let current_index = index;
-            index += 1;
-            let sub_entries = Vec::new(); // AO3 doesn't have nested chapters
-
-            entries.push(TocEntry {
-                title,
-                location,
-                index: current_index,
-                children: sub_entries,
-            });
-        }
-
-        Some(entries)
+        Some(self.chapters.iter().enumerate().map(|(index, chap)| {
+            TocEntry {
+                title: chap.title.clone(),
+                location: Location::Exact(chap.start_offset),
+                index,
+                // AO3 doesn't have nested chapters.
+                children: Vec::new(),
+            }
+        }).collect())
     }
 
     fn chapterlist(&self) -> Vec<Chapter> {
-        let mut entries = Vec::new();
-
-        let chapter_selector = scraper::Selector::parse(".chapter h3.title").unwrap();
-
-        for (i, element) in self.parsed_doc.select(&chapter_selector).enumerate() {
-            let text = element.text().collect::<Vec<_>>();
-            let title = text.join("").trim().to_string();
-            let location = format!("#chapter-{}", i + 1);
+        self.chapters.iter().map(|chap| {
+            Chapter {
+                title: chap.title.clone(),
+                location: format!("#{}", chap.id),
+            }
+        }).collect()
+    }
 
-            entries.push(Chapter {
-                title,
-                location,
-            });
-        }
+    fn chapter_path(&self, offset: usize) -> Option<String> {
+        self.chapters.get(self.chapter_index(offset)?)?.path.clone()
+    }
 
-        entries
-    }
-
-    fn chapter<'a>(&mut self, _offset: usize, _toc: &'a [TocEntry]) -> Option<(&'a TocEntry, f32)> {
-        // let next_offset = self.resolve_location(Location::Next(offset))
-        //                       .unwrap_or(usize::MAX);
-        // let (index, _) = self.vertebra_coordinates(offset)?;
-        // let path = self.spine[index].path.clone();
-        // let mut chap_before = None;
-        // let mut chap_after = None;
-        // let mut offset_before = 0;
-        // let mut offset_after = usize::MAX;
-        // self.chapter_aux(toc, offset, next_offset, &path,
-        //                  &mut chap_before, &mut offset_before,
-        //                  &mut chap_after, &mut offset_after);
-        // if chap_after.is_none() && chap_before.is_none() {
-        //     for i in (0..index).rev() {
-        //         let chap = chapter_from_uri(&self.spine[i].path, toc);
-        //         if chap.is_some() {
-        //             return chap;
-        //         }
-        //     }
-        //     None
-        // } else {
-        //     chap_after.or(chap_before)
-        // }
-        None
+    fn chapter<'a>(&mut self, offset: usize, toc: &'a [TocEntry]) -> Option<(&'a TocEntry, f32)> {
+        let index = self.chapter_index(offset)?;
+        let chap = &self.chapters[index];
+        let entry = toc.iter().find(|entry| entry.index == index)?;
+        let progress = (offset - chap.start_offset) as f32 / (chap.end_offset - chap.start_offset).max(1) as f32;
+        Some((entry, progress.min(1.0)))
     }
 
     fn chapter_relative<'a>(&mut self, offset: usize, dir: CycleDir, toc: &'a [TocEntry]) -> Option<&'a TocEntry> {
-        let next_offset = self.resolve_location(Location::Next(offset))
-                              .unwrap_or(usize::MAX);
-        let chap = self.chapter(offset, toc).map(|(c, _)| c);
-
-        match dir {
-            CycleDir::Previous => self.previous_chapter(chap, offset, next_offset, toc),
-            CycleDir::Next => self.next_chapter(chap, offset, next_offset, toc),
-        }
+        let index = match (self.chapter_index(offset), dir) {
+            (Some(index), CycleDir::Previous) => index.checked_sub(1)?,
+            (Some(index), CycleDir::Next) => index + 1,
+            // Within the work's preface.
+            (None, CycleDir::Previous) => return None,
+            (None, CycleDir::Next) => 0,
+        };
+        toc.iter().find(|entry| entry.index == index)
     }
 
     fn resolve_location(&mut self, loc: Location) -> Option<usize> {
@@ -513,22 +580,31 @@ impl Document for Ao3Document {
 
         match loc {
             Location::Exact(offset) => {
-                let page_index = self.page_index(offset)?;
-                self.pages[page_index].first()
+                self.page(offset)?.first()
                     .map(DrawCommand::offset)
             },
             Location::Previous(offset) => {
-                let page_index = self.page_index(offset)?;
+                let index = self.part_index(offset);
+                let page_index = self.page_index(offset, index)?;
                 if page_index > 0 {
-                    self.pages[page_index-1].first().map(DrawCommand::offset)
+                    self.parts[index].pages[page_index-1].first().map(DrawCommand::offset)
+                } else if index > 0 {
+                    self.pages(index - 1).last()
+                        .and_then(|page| page.first())
+                        .map(DrawCommand::offset)
                 } else {
                     None
                 }
             },
             Location::Next(offset) => {
-                let page_index = self.page_index(offset)?;
-                if page_index < self.pages.len() - 1 {
-                    self.pages[page_index+1].first().map(DrawCommand::offset)
+                let index = self.part_index(offset);
+                let page_index = self.page_index(offset, index)?;
+                if page_index < self.parts[index].pages.len() - 1 {
+                    self.parts[index].pages[page_index+1].first().map(DrawCommand::offset)
+                } else if index < self.parts.len() - 1 {
+                    self.pages(index + 1).first()
+                        .and_then(|page| page.first())
+                        .map(DrawCommand::offset)
                 } else {
                     None
                 }
@@ -543,9 +619,8 @@ impl Document for Ao3Document {
 
     fn words(&mut self, loc: Location) -> Option<(Vec<BoundedText>, usize)> {
         let offset = self.resolve_location(loc)?;
-        let page_index = self.page_index(offset)?;
 
-        Some((self.pages[page_index].iter().filter_map(|dc| {
+        Some((self.page(offset)?.iter().filter_map(|dc| {
             match dc {
                 DrawCommand::Text(TextCommand { text, rect, offset, .. }) => {
                     Some(BoundedText {
@@ -586,9 +661,8 @@ impl Document for Ao3Document {
 
     fn links(&mut self, loc: Location) -> Option<(Vec<BoundedText>, usize)> {
         let offset = self.resolve_location(loc)?;
-        let page_index = self.page_index(offset)?;
 
-        Some((self.pages[page_index].iter().filter_map(|dc| {
+        Some((self.page(offset)?.iter().filter_map(|dc| {
             match dc {
                 DrawCommand::Text(TextCommand { uri, rect, offset, .. }) |
                 DrawCommand::Image(ImageCommand { uri, rect, offset, .. }) if uri.is_some() => {
@@ -605,8 +679,7 @@ impl Document for Ao3Document {
 
     fn pixmap(&mut self, loc: Location, scale: f32) -> Option<(Pixmap, usize)> {
         let offset = self.resolve_location(loc)?;
-        let page_index = self.page_index(offset)?;
-        let page = self.pages[page_index].clone();
+        let page = self.page(offset)?.clone();
         let pixmap = self.engine.render_page(&page, scale, &mut self.parent)?;
 
         Some((pixmap, offset))
@@ -614,56 +687,115 @@ impl Document for Ao3Document {
 
     fn layout(&mut self, width: u32, height: u32, font_size: f32, dpi: u16) {
         self.engine.layout(width, height, font_size, dpi);
-        self.pages.clear();
+        self.clear_pages();
     }
 
     fn set_text_align(&mut self, text_align: TextAlign) {
         self.engine.set_text_align(text_align);
-        self.pages.clear();
+        self.clear_pages();
     }
 
     fn set_font_family(&mut self, family_name: &str, search_path: &str) {
         self.engine.set_font_family(family_name, search_path);
-        self.pages.clear();
+        self.clear_pages();
     }
 
     fn set_margin_width(&mut self, width: i32) {
         self.engine.set_margin_width(width);
-        self.pages.clear();
+        self.clear_pages();
     }
 
     fn set_line_height(&mut self, line_height: f32) {
         self.engine.set_line_height(line_height);
-        self.pages.clear();
+        self.clear_pages();
     }
 
     fn set_hyphen_penalty(&mut self, hyphen_penalty: i32) {
         self.engine.set_hyphen_penalty(hyphen_penalty);
-        self.pages.clear();
+        self.clear_pages();
     }
 
     fn set_stretch_tolerance(&mut self, stretch_tolerance: f32) {
         self.engine.set_stretch_tolerance(stretch_tolerance);
-        self.pages.clear();
+        self.clear_pages();
     }
 
     fn set_ignore_document_css(&mut self, ignore: bool) {
         self.ignore_document_css = ignore;
-        self.pages.clear();
+        self.clear_pages();
+    }
+
+    fn set_notes_display(&mut self, display: NotesDisplay) {
+        if display == self.notes_display {
+            return;
+        }
+        for (index, part) in self.parts.iter_mut().enumerate() {
+            if part.state == PartState::Loaded {
+                part.content = work_content(&part.body, display, index);
+                part.pages.clear();
+            }
+        }
+        self.notes_display = display;
+    }
+
+    fn notes(&self, uri: &str) -> Option<Vec<HtmlText>> {
+        let (part, index) = parse_notes_uri(uri)?;
+        scrape_notes(&self.parts.get(part)?.body, index)
+    }
+
+    fn request_chapters(&mut self, offset: usize) -> Vec<String> {
+        let index = self.part_index(offset);
+        // The next chapter is fetched ahead of time.
+        self.parts.iter_mut().skip(index).take(2)
+            .filter(|part| part.state == PartState::Missing)
+            .filter_map(|part| {
+                part.state = PartState::Requested;
+                part.path.clone()
+            })
+            .collect()
+    }
+
+    fn load_chapter(&mut self, path: &str, html: Option<&str>) -> Option<Range<usize>> {
+        let index = self.parts.iter().position(|part| part.path.as_deref() == Some(path))?;
+        let document = html.map(scraper::Html::parse_document);
+        let Some(body) = document.as_ref().and_then(chapter_html) else {
+            self.parts[index].state = PartState::Missing;
+            return None;
+        };
+
+        let start_offset = self.parts[index].start_offset;
+        let content = work_content(&body, self.notes_display, index);
+        let chapter_index = self.chapters.len() + index - self.parts.len();
+        let chapter = document.and_then(|document| {
+            scrape_chapters(&document, &content, &self.ao3info.title, body.len()).into_iter().next()
+        });
+        if let Some(chapter) = chapter {
+            self.chapters[chapter_index] = Ao3Chapter {
+                start_offset: start_offset + chapter.start_offset,
+                end_offset: start_offset + chapter.end_offset,
+                .. chapter
+            };
+        }
+
+        let part = &mut self.parts[index];
+        part.state = PartState::Loaded;
+        part.body = body;
+        part.content = content;
+        part.pages.clear();
+        Some(start_offset..start_offset + CHAPTER_SPAN)
     }
 
     fn title(&self) -> Option<String> {
-        let title = scrape(&self.parsed_doc,"h2.title");
-        Some(title)
+        scrape(&self.parsed_doc, &selectors().work.title).ok()
     }
 
     fn author(&self) -> Option<String> {
-        let author_selector = scraper::Selector::parse("h3.byline").unwrap();
-        Some(self.parsed_doc.select(&author_selector).next().unwrap().inner_html())
+        let author_selector = scraper::Selector::parse(&selectors().work.byline).unwrap();
+        self.parsed_doc.select(&author_selector).next().map(|author| author.inner_html())
     }
 
     fn metadata(&self, key: &str) -> Option<String> {
-        self.content.root().find("metadata")
+        self.parts[0].content.root().find("metadata")
             .and_then(|md| md.children().find(|child| child.tag_qualified_name() == Some(key)))
             .map(|child| decode_entities(&child.text()).into_owned())
     }
@@ -680,3 +812,75 @@ impl Document for Ao3Document {
         self.ao3info.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST_PAGE: &str = r#"<html><body>
+<form><select name="selected_id" id="selected_id">
+<option selected="selected" value="11">1. Arrival</option>
+<option value="12">2. Chapter 2</option>
+<option value="13">3. Departure</option>
+</select></form>
+<div class="wrapper"><dl class="work meta group"><dd class="words">500,000</dd><dd class="chapters">3/3</dd></dl></div>
+<div id="workskin"><div class="preface group"><h2 class="title">Shore Leave</h2></div>
+<div id="chapters" role="article"><div class="chapter" id="chapter-1">
+<div class="chapter preface group"><h3 class="title"><a href="/works/1/chapters/11">Chapter 1</a>: Arrival</h3></div>
+<div class="userstuff module"><p>The planet was green.</p></div>
+</div></div></div>
+</body></html>"#;
+
+    const SECOND_PAGE: &str = r#"<html><body>
+<div id="workskin"><div class="preface group"><h2 class="title">Shore Leave</h2></div>
+<div id="chapters" role="article"><div class="chapter" id="chapter-2">
+<div class="chapter preface group"><h3 class="title"><a href="/works/1/chapters/12">Chapter 2</a></h3>
+<div id="notes" class="notes module"><h3 class="heading">Notes:</h3><blockquote class="userstuff"><p>Thanks to my beta.</p></blockquote></div></div>
+<div class="userstuff module"><p>They stayed.</p></div>
+</div></div></div>
+</body></html>"#;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_theFirstPageOfALongWork_WHEN_itsChaptersAreLoaded_THEN_theirLocationsWillNotMove() {
+        // GIVEN the first page of a long work
+        let mut doc = Ao3Document::new_from_memory(FIRST_PAGE, Some("https://archiveofourown.org/works/1?view_adult=true")).unwrap();
+        let titles: Vec<&str> = doc.chapters().iter().map(|chap| chap.title.as_str()).collect();
+        assert_eq!(titles, vec!["Chapter 1: Arrival", "Chapter 2", "Chapter 3: Departure"]);
+        assert_eq!(doc.chapters()[1].start_offset, CHAPTER_SPAN);
+        assert_eq!(doc.pages_count(), 3 * CHAPTER_SPAN);
+
+        // WHEN its chapters are loaded
+        let paths = doc.request_chapters(0);
+        assert_eq!(paths, vec!["/works/1/chapters/12?view_adult=true".to_string()]);
+        assert!(doc.request_chapters(0).is_empty());
+        let range = doc.load_chapter(&paths[0], Some(SECOND_PAGE));
+
+        // THEN their locations will not move
+        assert_eq!(range, Some(CHAPTER_SPAN..2 * CHAPTER_SPAN));
+        assert_eq!(doc.chapters()[1].start_offset, CHAPTER_SPAN);
+        assert!(doc.chapters()[1].end_offset > CHAPTER_SPAN);
+        assert_eq!(doc.chapters()[2].start_offset, 2 * CHAPTER_SPAN);
+        assert_eq!(doc.pages_count(), 3 * CHAPTER_SPAN);
+        let mut cache = FxHashMap::default();
+        assert_eq!(doc.resolve_link("#chapter-2", &mut cache), Some(CHAPTER_SPAN));
+        doc.set_notes_display(NotesDisplay::Collapse);
+        assert_eq!(doc.notes("notes:1/0"), Some(vec![HtmlText::Text("Notes:".to_string()), HtmlText::Blockquote, HtmlText::Text("Thanks to my beta.".to_string())]));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aChapterThatCouldNotBeFetched_WHEN_itIsLoaded_THEN_itWillBeRequestedAgain() {
+        // GIVEN a chapter that could not be fetched
+        let mut doc = Ao3Document::new_from_memory(FIRST_PAGE, Some("https://archiveofourown.org/works/1?view_adult=true")).unwrap();
+        let paths = doc.request_chapters(2 * CHAPTER_SPAN);
+        assert_eq!(paths, vec!["/works/1/chapters/13?view_adult=true".to_string()]);
+
+        // WHEN it is loaded
+        let range = doc.load_chapter(&paths[0], None);
+
+        // THEN it will be requested again
+        assert_eq!(range, None);
+        assert_eq!(doc.request_chapters(2 * CHAPTER_SPAN), paths);
+    }
+}
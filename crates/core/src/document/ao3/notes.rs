@@ -0,0 +1,114 @@
+use scraper::{Html, Selector};
+use crate::html::{html_text, HtmlText};
+use crate::selectors::selectors;
+use crate::settings::NotesDisplay;
+use crate::document::html::css::CssParser;
+use crate::document::html::dom::{XmlTree, NodeId, Attributes, element, text};
+
+// Collapsed notes link to the part of the work they're in, and to their
+// index within it.
+pub const NOTES_SCHEME: &str = "notes:";
+
+const NOTES_LABEL: &str = "Notes";
+
+// The notes of `content`, in document order.
+fn notes_ids(content: &XmlTree) -> Vec<NodeId> {
+    let profile = selectors();
+    let rules = CssParser::new(&format!("{} {{}}", &*profile.work.notes)).parse().rules;
+    content.root().descendants()
+           .filter(|node| node.is_element() && rules.iter().any(|rule| rule.selector.matches(*node)))
+           .map(|node| node.id)
+           .collect()
+}
+
+// The offsets of the remaining nodes are left untouched, so that locations
+// within the work stay valid whatever the display.
+pub fn apply_notes_display(content: &mut XmlTree, display: NotesDisplay, part: usize) {
+    if display == NotesDisplay::Show {
+        return;
+    }
+
+    for (index, id) in notes_ids(content).into_iter().enumerate() {
+        if display == NotesDisplay::Hide {
+            content.get_mut(id).detach();
+            continue;
+        }
+
+        let offset = content.get(id).offset();
+        let children: Vec<NodeId> = content.get(id).children().map(|child| child.id).collect();
+        for child_id in children {
+            content.get_mut(child_id).detach();
+        }
+
+        let mut attributes = Attributes::default();
+        attributes.insert("href".to_string(), format!("{}{}/{}", NOTES_SCHEME, part, index));
+        let paragraph_id = content.get_mut(id).append(element("p", offset, Attributes::default()));
+        let link_id = content.get_mut(paragraph_id).append(element("a", offset, attributes));
+        content.get_mut(link_id).append(text(NOTES_LABEL, offset));
+    }
+}
+
+// The part and index a collapsed notes link points to.
+pub fn parse_notes_uri(uri: &str) -> Option<(usize, usize)> {
+    let (part, index) = uri.strip_prefix(NOTES_SCHEME)?.split_once('/')?;
+    Some((part.parse().ok()?, index.parse().ok()?))
+}
+
+// `body` is the HTML of the part the notes are in.
+pub fn scrape_notes(body: &str, index: usize) -> Option<Vec<HtmlText>> {
+    let fragment = Html::parse_fragment(body);
+    let notes_selector = Selector::parse(&selectors().work.notes).unwrap();
+    fragment.select(&notes_selector).nth(index).map(html_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::html::xml::XmlParser;
+
+    const WORKSKIN: &str = r#"<div class="preface group"><h2 class="title">Shore Leave</h2>
+<div class="notes module"><h3 class="heading">Notes:</h3><blockquote class="userstuff"><p>Thanks to my beta.</p></blockquote></div></div>
+<div id="chapters" class="userstuff"><p>The planet was green.</p></div>
+<div id="work_endnotes" class="end notes module"><h3 class="heading">Notes:</h3><blockquote class="userstuff"><p>More soon.</p></blockquote></div>"#;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aWorkWithNotes_WHEN_theyAreHidden_THEN_onlyTheTextWillRemain() {
+        // GIVEN a work with notes
+        let mut content = XmlParser::new(WORKSKIN).parse();
+        let offset = content.root().find_by_id("chapters").unwrap().offset();
+
+        // WHEN they are hidden
+        apply_notes_display(&mut content, NotesDisplay::Hide, 0);
+
+        // THEN only the text will remain
+        let text = content.root().text();
+        assert!(text.contains("The planet was green."));
+        assert!(!text.contains("Thanks to my beta."));
+        assert!(!text.contains("More soon."));
+        assert_eq!(content.root().find_by_id("chapters").unwrap().offset(), offset);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aWorkWithNotes_WHEN_theyAreCollapsed_THEN_eachWillLinkToItsText() {
+        // GIVEN a work with notes
+        let mut content = XmlParser::new(WORKSKIN).parse();
+
+        // WHEN they are collapsed
+        apply_notes_display(&mut content, NotesDisplay::Collapse, 2);
+
+        // THEN each will link to its text
+        let links: Vec<&str> = content.root().descendants()
+                                      .filter_map(|node| node.attribute("href"))
+                                      .collect();
+        assert_eq!(links, vec!["notes:2/0", "notes:2/1"]);
+        assert!(!content.root().text().contains("Thanks to my beta."));
+        assert_eq!(parse_notes_uri(links[1]), Some((2, 1)));
+        assert_eq!(scrape_notes(WORKSKIN, 1), Some(vec![
+            HtmlText::Text("Notes:".to_string()),
+            HtmlText::Blockquote,
+            HtmlText::Text("More soon.".to_string()),
+        ]));
+    }
+}
@@ -0,0 +1,76 @@
+use scraper::{Html, Selector};
+use crate::selectors::selectors;
+use crate::document::html::css::{CssParser, Combinator};
+use crate::document::html::style::StyleSheet;
+
+// The creators' selectors are scoped to the work's body, which isn't part
+// of the laid out content.
+const BODY_ID: &str = "workskin";
+
+// Colors come out as faint grays on e-ink, and fixed positioning has no
+// screen to stick to once the text is paginated.
+const DROPPED_PROPERTIES: [&str; 10] = ["color", "opacity", "box-shadow", "text-shadow",
+                                        "position", "top", "right", "bottom", "left", "z-index"];
+const DROPPED_PREFIXES: [&str; 1] = ["background"];
+
+fn is_dropped(name: &str) -> bool {
+    DROPPED_PROPERTIES.contains(&name) || DROPPED_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+// The creator's work skin, without what doesn't work on e-ink.
+pub fn scrape_work_skin(document: &Html) -> StyleSheet {
+    let selector = Selector::parse(&selectors().work.skin).unwrap();
+    let css = document.select(&selector)
+                      .map(|style| style.text().collect::<String>())
+                      .collect::<Vec<_>>()
+                      .join("\n");
+    let mut stylesheet = CssParser::new(&css).parse();
+
+    stylesheet.rules.retain_mut(|rule| {
+        let selector = &mut rule.selector;
+        if selector.simple_selectors.first().is_some_and(|simple| simple.id.as_deref() == Some(BODY_ID)) {
+            // The body itself.
+            if selector.simple_selectors.len() < 2 {
+                return false;
+            }
+            selector.simple_selectors.remove(0);
+            selector.combinators.remove(0);
+            selector.combinators[0] = Combinator::None;
+        }
+        rule.declarations.retain(|declaration| !is_dropped(&declaration.name));
+        !rule.declarations.is_empty()
+    });
+
+    stylesheet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::html::xml::XmlParser;
+    use crate::document::html::style::specified_values;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aWorkSkin_WHEN_scrapeWorkSkinIsCalled_THEN_onlyWhatSuitsEInkWillBeKept() {
+        // GIVEN a work skin
+        let document = Html::parse_document(r#"<html><head><style type="text/css">/*<![CDATA[*/
+#workskin { background: black; }
+#workskin .text-message { display: block; margin-left: 2em; color: #00f; background-color: #eee; font-style: italic; }
+#workskin > .sticky { position: fixed; top: 0; }
+/*]]>*/</style></head><body><div id="workskin"><p class="text-message">hey</p></div></body></html>"#);
+
+        // WHEN scrape_work_skin is called
+        let stylesheet = scrape_work_skin(&document);
+
+        // THEN only what suits e-ink will be kept
+        assert_eq!(stylesheet.rules.len(), 1);
+        let content = XmlParser::new(r#"<div><p class="text-message">hey</p></div>"#).parse();
+        let paragraph = content.root().descendants().find(|node| node.tag_name() == Some("p")).unwrap();
+        let props = specified_values(paragraph, &stylesheet);
+        assert_eq!(props.get("margin-left").map(String::as_str), Some("2em"));
+        assert_eq!(props.get("font-style").map(String::as_str), Some("italic"));
+        assert!(!props.contains_key("color"));
+        assert!(props.keys().all(|name| !name.starts_with("background")));
+    }
+}
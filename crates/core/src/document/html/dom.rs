@@ -418,6 +418,25 @@ impl<'a> NodeMut<'a> {
         id
     }
 
+    // Removes the node, and its descendants, from the tree.
+    pub fn detach(&mut self) {
+        let parent = self.node().parent.take();
+        let before = self.node().previous_sibling.take();
+        let after = self.node().next_sibling.take();
+
+        if let Some(before_id) = before {
+            self.tree.node_mut(before_id).next_sibling = after;
+        } else if let Some(parent_id) = parent {
+            self.tree.node_mut(parent_id).first_child = after;
+        }
+
+        if let Some(after_id) = after {
+            self.tree.node_mut(after_id).previous_sibling = before;
+        } else if let Some(parent_id) = parent {
+            self.tree.node_mut(parent_id).last_child = before;
+        }
+    }
+
     pub fn wrap_range(&mut self, first_id: NodeId, last_id: NodeId, data: NodeData) {
         let before = self.tree.node(first_id).previous_sibling;
         let after = self.tree.node(last_id).next_sibling;
@@ -60,7 +60,7 @@ pub fn specified_values(node: NodeRef, stylesheet: &StyleSheet) -> PropertyMap {
 }
 
 impl Selector {
-    fn matches(&self, node: NodeRef) -> bool {
+    pub fn matches(&self, node: NodeRef) -> bool {
         let index = self.simple_selectors.len().saturating_sub(1);
         self.matches_rec(node, index)
     }
@@ -11,6 +11,7 @@ use std::path::Path;
 use std::fs::{self, File};
 use std::ffi::OsStr;
 use std::collections::BTreeSet;
+use std::ops::Range;
 use std::os::unix::fs::FileExt;
 use anyhow::{Error, format_err};
 use regex::Regex;
@@ -27,9 +28,14 @@ use self::html::HtmlDocument;
 use crate::geom::{Boundary, CycleDir};
 use crate::metadata::{TextAlign, Annotation};
 use crate::framebuffer::Pixmap;
-use crate::settings::INTERNAL_CARD_ROOT;
+use crate::settings::{INTERNAL_CARD_ROOT, NotesDisplay};
 use crate::device::CURRENT_DEVICE;
 use crate::ao3_metadata::Ao3Info;
+use crate::http::outbox::{Outbox, ActionStatus};
+use crate::http::DownloadFormat;
+use crate::http::comments::CommentFields;
+use crate::http::bookmarks::BookmarkFields;
+use crate::html::{HtmlText, Link};
 
 pub const BYTES_PER_PAGE: f64 = 2048.0;
 
@@ -134,6 +140,18 @@ pub trait Document: Send+Sync {
         None
     }
 
+    fn comment_fields(&self) -> Option<CommentFields> {
+        None
+    }
+
+    fn bookmark_fields(&self) -> Option<BookmarkFields> {
+        None
+    }
+
+    fn download_links(&self) -> Vec<(DownloadFormat, Link)> {
+        Vec::new()
+    }
+
 
     fn work_id(&self) -> String {
         "".to_string()
@@ -152,6 +170,31 @@ pub trait Document: Send+Sync {
         self.chapterlist().len() > 1
     }
 
+    // The page of the chapter the offset belongs to, if it has one.
+    fn chapter_path(&self, _offset: usize) -> Option<String> {
+        None
+    }
+
+    fn set_notes_display(&mut self, _display: NotesDisplay) {
+    }
+
+    // The collapsed notes a link points to.
+    fn notes(&self, _uri: &str) -> Option<Vec<HtmlText>> {
+        None
+    }
+
+    // For documents read a chapter at a time: the pages of the chapters
+    // around the offset that need to be fetched.
+    fn request_chapters(&mut self, _offset: usize) -> Vec<String> {
+        Vec::new()
+    }
+
+    // Returns the locations the chapter spans once it's in. Chapters that
+    // couldn't be fetched are requested again.
+    fn load_chapter(&mut self, _path: &str, _html: Option<&str>) -> Option<Range<usize>> {
+        None
+    }
+
 
     fn save(&self, _path: &str) -> Result<(), Error> {
         Err(format_err!("this document can't be saved"))
@@ -611,3 +654,39 @@ pub fn sys_info_as_html() -> String {
     buf.push_str("\t\t</table>\n\t</body>\n</html>");
     buf
 }
+
+pub fn outbox_as_html(outbox: &Outbox) -> String {
+    let mut buf = "<html>\n\t<head>\n\t\t<title>Pending Actions</title>\n\t\t\
+                   <link rel=\"stylesheet\" type=\"text/css\" \
+                   href=\"css/sysinfo.css\"/>\n\t</head>\n\t<body>\n".to_string();
+
+    if outbox.actions().is_empty() {
+        buf.push_str("\t\t<p>Nothing is waiting to be sent to AO3.</p>\n\t</body>\n</html>");
+        return buf;
+    }
+
+    buf.push_str("\t\t<table>\n");
+
+    for pa in outbox.actions() {
+        let status = match pa.status {
+            ActionStatus::Pending => format!("Pending since {}", pa.queued.format("%Y-%m-%d %H:%M")),
+            ActionStatus::Failed(ref e) => format!("Failed: {}", e),
+        };
+        buf.push_str("\t\t\t<tr>\n");
+        buf.push_str(&format!("\t\t\t\t<td class=\"key\">{}</td>\n", pa.action.label()));
+        buf.push_str(&format!("\t\t\t\t<td class=\"value\">{}</td>\n", escape_html(&pa.title)));
+        buf.push_str("\t\t\t</tr>\n");
+        buf.push_str("\t\t\t<tr>\n");
+        buf.push_str("\t\t\t\t<td class=\"key\"></td>\n");
+        buf.push_str(&format!("\t\t\t\t<td class=\"value\">{}</td>\n", escape_html(&status)));
+        buf.push_str("\t\t\t</tr>\n");
+        buf.push_str("\t\t\t<tr class=\"sep\"></tr>\n");
+    }
+
+    buf.push_str("\t\t</table>\n\t</body>\n</html>");
+    buf
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -615,6 +615,15 @@ pub struct Style {
     pub size: u32,
 }
 
+impl Style {
+    pub fn with_variant(&self, variant: Variant) -> Style {
+        Style {
+            variant: self.variant | variant,
+            ..self.clone()
+        }
+    }
+}
+
 pub fn font_from_variant(family: &mut FontFamily, variant: Variant) -> &mut Font {
     if variant.contains(Variant::ITALIC | Variant::BOLD) {
         &mut family.bold_italic
@@ -49,12 +49,15 @@ pub fn unicode_split(text: &String, index: usize) -> (String, String) {
 
 }
 
-pub fn get_url(url: &str) -> Url {
-    let ao3 = Url::parse("https://archiveofourown.org").unwrap();
+pub fn get_url(base: &Url, url: &str) -> Url {
     let parsed = Url::parse(url);
     match parsed {
         Ok(unwrapped_url) => unwrapped_url,
-        Err(e) => if e == ParseError::RelativeUrlWithoutBase { ao3.join(url).unwrap()} else {ao3}
+        Err(e) => if e == ParseError::RelativeUrlWithoutBase {
+            base.join(url).unwrap_or_else(|_| base.clone())
+        } else {
+            base.clone()
+        }
     }
 
 }
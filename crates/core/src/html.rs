@@ -1,24 +1,54 @@
+use std::fmt;
 use crate::helpers::decode_entities;
-use scraper::{Html, Selector};
+use crate::selectors::selectors;
+use scraper::{ElementRef, Html, Node, Selector};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Link {
     pub title: String,
     pub location: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Format {
     Bold,
-    Italic
+    Italic,
+    BoldItalic,
 }
 
+impl Format {
+    fn with(current: Option<Format>, format: Format) -> Format {
+        match current {
+            Some(current) if current != format => Format::BoldItalic,
+            _ => format,
+        }
+    }
+}
+
+// User written HTML, e.g. a summary, reduced to what can be shown in a blurb.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HtmlText {
+    // Starts a new line.
     Break,
+    // Starts a new, indented line.
     Blockquote,
     Text(String),
     LinkText(Link),
-    FormatText(String, Format)
+    FormatText(String, Format),
+}
+
+pub fn html_text_to_string(items: &[HtmlText]) -> String {
+    let mut text = String::new();
+    for item in items {
+        match item {
+            HtmlText::Break | HtmlText::Blockquote => text.push('\n'),
+            HtmlText::Text(s) | HtmlText::FormatText(s, _) => text.push_str(s),
+            HtmlText::LinkText(link) => text.push_str(&link.title),
+        }
+    }
+    text
 }
 
 pub fn list_to_str(list: &Vec<Link>, sep: &str) -> String {
@@ -29,68 +59,175 @@ pub fn list_to_str(list: &Vec<Link>, sep: &str) -> String {
     temp.join(sep)
 }
 
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScrapeError {
+    #[error("{0} is missing")]
+    Missing(String),
 
+    #[error("{0} has no {1} attribute")]
+    MissingAttribute(String, String),
+}
 
-pub fn scrape_inner_text(frag: &Html, select: &str) -> String {
-    let selector = Selector::parse(select).unwrap();
-    match frag.select(&selector).next() {
-        Some(el) => {
-            let raw_text = el.text().collect::<Vec<_>>().join("");
-            let trimmed = raw_text.trim();
-            let text = decode_entities(trimmed).into_owned();
-            return text;
+impl ScrapeError {
+    pub fn selector(&self) -> &str {
+        match self {
+            ScrapeError::Missing(select) | ScrapeError::MissingAttribute(select, _) => select,
         }
-        None => {
-            return "####".to_string();
+    }
+}
+
+// The required fields of a page that couldn't be scraped. Optional fields,
+// e.g. the kudos of a work nobody left kudos on yet, don't belong here.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageErrors(Vec<ScrapeError>);
+
+impl PageErrors {
+    // Records the error, if any, and falls back to the default value so
+    // that the other fields can still be checked.
+    pub fn required<T: Default>(&mut self, res: Result<T, ScrapeError>) -> T {
+        res.unwrap_or_else(|e| {
+            self.0.push(e);
+            T::default()
+        })
+    }
+
+    pub fn errors(&self) -> &[ScrapeError] {
+        &self.0
+    }
+
+    pub fn selectors(&self) -> Vec<&str> {
+        self.0.iter().map(ScrapeError::selector).collect()
+    }
+
+    pub fn finish<T>(self, value: T) -> Result<T, PageErrors> {
+        if self.0.is_empty() {
+            Ok(value)
+        } else {
+            Err(self)
         }
-    };
+    }
 }
 
-pub fn scrape_inner_text_to_html(frag: &Html, select: &str) -> String {
+impl fmt::Display for PageErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} missing", self.selectors().join(", "))
+    }
+}
+
+impl std::error::Error for PageErrors {}
+
+fn select_first<'a>(frag: &'a Html, select: &str) -> Result<ElementRef<'a>, ScrapeError> {
     let selector = Selector::parse(select).unwrap();
-    match frag.select(&selector).next() {
-        Some(el) => {
-            let mut html_els = Vec::new();
-            for item in el.children() {
-                 match item.value().clone() {
-                    scraper::Node::Element(el) => {
-                        println!("element is {:?}", el.name());
-                        for item2 in item.children() {
-                            println!("nested value is {:#?}", item2.value());
-                        }
-                    },
-                    scraper::Node::Text(txt) => {
-                        let text = txt.to_string();
-                        if text == "\n" {
-                            html_els.push(HtmlText::Break)
-                        } else {
-                            html_els.push(HtmlText::Text(text))
-                        }
-                    },
-                    _ => {}
-
-                 }
-            }
-            let raw_text = el.text().collect::<Vec<_>>().join("");
-            let trimmed = raw_text.trim();
-            let text = decode_entities(trimmed).into_owned();
-            return text;
+    frag.select(&selector).next().ok_or_else(|| ScrapeError::Missing(select.to_string()))
+}
+
+fn clean_text(raw: &str) -> String {
+    decode_entities(raw.trim()).into_owned()
+}
+
+pub fn scrape_inner_text(frag: &Html, select: &str) -> Result<String, ScrapeError> {
+    let el = select_first(frag, select)?;
+    Ok(clean_text(&el.text().collect::<String>()))
+}
+
+pub fn scrape_html_text(frag: &Html, select: &str) -> Result<Vec<HtmlText>, ScrapeError> {
+    select_first(frag, select).map(html_text)
+}
+
+pub fn html_text(el: ElementRef) -> Vec<HtmlText> {
+    let mut items = Vec::new();
+    push_html_text(el, None, false, &mut items);
+    while matches!(items.last(), Some(HtmlText::Break | HtmlText::Blockquote)) {
+        items.pop();
+    }
+    items
+}
+
+fn push_html_text(el: ElementRef, format: Option<Format>, quoted: bool, items: &mut Vec<HtmlText>) {
+    for child in el.children() {
+        if let Node::Text(text) = child.value() {
+            push_text(text, format, items);
+            continue;
         }
-        None => {
-            return "####".to_string();
+        let Some(child_el) = ElementRef::wrap(child) else {
+            continue;
+        };
+        match child_el.value().name() {
+            "br" => items.push(HtmlText::Break),
+            "blockquote" => {
+                start_block(true, items);
+                push_html_text(child_el, format, true, items);
+                start_block(quoted, items);
+            },
+            "p" | "div" | "li" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                start_block(quoted, items);
+                push_html_text(child_el, format, quoted, items);
+                start_block(quoted, items);
+            },
+            "strong" | "b" => push_html_text(child_el, Some(Format::with(format, Format::Bold)), quoted, items),
+            "em" | "i" | "cite" => push_html_text(child_el, Some(Format::with(format, Format::Italic)), quoted, items),
+            "a" => match child_el.value().attr("href") {
+                Some(location) => {
+                    let title = clean_text(&child_el.text().collect::<String>());
+                    if !title.is_empty() {
+                        items.push(HtmlText::LinkText(Link { title, location: location.to_string() }));
+                    }
+                },
+                None => push_html_text(child_el, format, quoted, items),
+            },
+            _ => push_html_text(child_el, format, quoted, items),
         }
-    };
+    }
+}
+
+// Blocks are separated by a single line break.
+fn start_block(quoted: bool, items: &mut Vec<HtmlText>) {
+    let item = if quoted { HtmlText::Blockquote } else { HtmlText::Break };
+    match items.last_mut() {
+        None if quoted => items.push(item),
+        None => (),
+        Some(last @ (HtmlText::Break | HtmlText::Blockquote)) => *last = item,
+        Some(_) => items.push(item),
+    }
 }
 
-pub fn scrape_login_csrf(frag: &Html) -> String {
-    let token = Selector::parse(r#"form.new_user input[name="authenticity_token"]"#).unwrap();
-    println!("HTML inner: {}", frag.root_element().inner_html()); // DO NOT REMOVE - causes thread panic
-    let input = frag.select(&token).next().unwrap();
-    input.value().attr("value").unwrap().to_string()
+// Collapses white space the way a browser would.
+fn push_text(raw: &str, format: Option<Format>, items: &mut Vec<HtmlText>) {
+    let mut text = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    let at_line_start = matches!(items.last(), None | Some(HtmlText::Break | HtmlText::Blockquote));
+    if !text.is_empty() && raw.ends_with(char::is_whitespace) {
+        text.push(' ');
+    }
+    if !at_line_start && raw.starts_with(char::is_whitespace) {
+        text.insert(0, ' ');
+    }
+    if text.is_empty() {
+        return;
+    }
+    match (items.last_mut(), format) {
+        (Some(HtmlText::Text(last)), None) => last.push_str(&text),
+        (Some(HtmlText::FormatText(last, last_format)), Some(format)) if *last_format == format => last.push_str(&text),
+        (_, None) => items.push(HtmlText::Text(text)),
+        (_, Some(format)) => items.push(HtmlText::FormatText(text, format)),
+    }
+}
+
+pub fn scrape_login_csrf(frag: &Html) -> Result<String, ScrapeError> {
+    let profile = selectors();
+    let select = &profile.forms.login_token;
+    select_first(frag, select)?.value()
+                               .attr("value")
+                               .map(String::from)
+                               .ok_or_else(|| ScrapeError::MissingAttribute(select.to_string(), "value".to_string()))
+}
+
+pub fn scrape_logout_csrf(frag: &Html) -> Option<&str> {
+    let token = Selector::parse(&selectors().forms.logout_token).unwrap();
+    frag.select(&token).next().and_then(|input| input.value().attr("value"))
 }
 
 pub fn scrape_kudos_csrf(frag: &Html) -> Option<&str> {
-    let token = Selector::parse(r#"form#new_kudo input[name="authenticity_token"]"#).unwrap();
+    let token = Selector::parse(&selectors().forms.kudos_token).unwrap();
     let input = frag.select(&token).next();
     if let Some(input) = input {
         input.value().attr("value")
@@ -99,20 +236,36 @@ pub fn scrape_kudos_csrf(frag: &Html) -> Option<&str> {
     }    
 }
 
-pub fn scrape(frag: &Html, select: &str) -> String {
-    let selector = Selector::parse(select).unwrap();
-    match frag.select(&selector).next() {
-        Some(el) => {
-            let raw = el.inner_html();
-            let trimmed = raw.trim();
-            let clean = decode_entities(trimmed).into_owned();
-            return clean;
-        }
-        None => {
-            println!("error trying to scrape {}", select);
-            return "#####".to_string();
-        }
-    };
+pub fn scrape_comment_csrf(frag: &Html) -> Option<&str> {
+    let token = Selector::parse(&selectors().forms.comment_token).unwrap();
+    frag.select(&token).next().and_then(|input| input.value().attr("value"))
+}
+
+// The value a form's field is sent with: a select's is the one of its
+// selected option, or of its first one.
+pub fn form_value<'a>(field: ElementRef<'a>) -> Option<&'a str> {
+    if let Some(value) = field.value().attr("value") {
+        return Some(value);
+    }
+    let option_selector = Selector::parse("option").unwrap();
+    let options: Vec<ElementRef> = field.select(&option_selector).collect();
+    options.iter()
+           .find(|option| option.value().attr("selected").is_some())
+           .or_else(|| options.first())
+           .and_then(|option| option.value().attr("value"))
+}
+
+// The notices AO3 shows after a form is submitted, errors included.
+pub fn scrape_flash(frag: &Html) -> Vec<String> {
+    let selector = Selector::parse(&selectors().forms.flash).unwrap();
+    frag.select(&selector)
+        .map(|el| decode_entities(el.text().collect::<String>().trim()).into_owned())
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+pub fn scrape(frag: &Html, select: &str) -> Result<String, ScrapeError> {
+    select_first(frag, select).map(|el| clean_text(&el.inner_html()))
 }
 
 pub fn scrape_link_list(frag: &Html, select: &str) -> Vec<Link> {
@@ -131,23 +284,12 @@ pub fn scrape_link_list(frag: &Html, select: &str) -> Vec<Link> {
     results
 }
 
-pub fn scrape_link(frag: &Html, select: &str) -> Link {
-    let selector = Selector::parse(select).unwrap();
-    match frag.select(&selector).next() {
-        Some(el) => {
-            let raw_title = el.inner_html();
-            let trimmed = raw_title.trim();
-            let title = decode_entities(trimmed).into_owned();
-            let location = el.value().attr("href").unwrap_or("").to_string();
-            return Link { title, location };
-        }
-        None => {
-            return Link {
-                title: "####".to_string(),
-                location: "".to_string(),
-            };
-        }
-    };
+pub fn scrape_link(frag: &Html, select: &str) -> Result<Link, ScrapeError> {
+    let el = select_first(frag, select)?;
+    Ok(Link {
+        title: clean_text(&el.inner_html()),
+        location: el.value().attr("href").unwrap_or("").to_string(),
+    })
 }
 
 pub fn scrape_many(frag: &Html, select: &str) -> Vec<String> {
@@ -174,16 +316,47 @@ pub fn scrape_many_outer(frag: &Html, select: &str) -> Vec<String> {
     results
 }
 
-pub fn scrape_outer(frag: &Html, select: &str) -> String {
-    let selector = Selector::parse(select).unwrap();
-    match frag.select(&selector).next() {
-        Some(el) => return el.inner_html(),
-        None => return "#####".to_string(),
-    };
+pub fn scrape_outer(frag: &Html, select: &str) -> Result<String, ScrapeError> {
+    select_first(frag, select).map(|el| el.inner_html())
 }
 
-pub fn scrape_inner(frag: String, select: &str) -> String {
+pub fn scrape_inner(frag: String, select: &str) -> Result<String, ScrapeError> {
     let html = Html::parse_fragment(&frag);
-    let selector = Selector::parse(select).unwrap();
-    html.select(&selector).next().unwrap().html()
+    select_first(&html, select).map(|el| el.html())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aSummary_WHEN_scrapeHtmlTextIsCalled_THEN_itsStructureWillBeKept() {
+        // GIVEN a summary
+        let frag = Html::parse_fragment(r#"<blockquote class="userstuff summary">
+            <p>Kirk  is <em>very</em> <strong><em>tired</em></strong>.</p>
+            <p>Sequel to <a href="/works/1">Shore Leave</a><br>Now with &amp; more</p>
+            <blockquote><p>Quoted</p></blockquote>
+        </blockquote>"#);
+
+        // WHEN scrape_html_text is called
+        let items = scrape_html_text(&frag, "blockquote.summary").unwrap();
+
+        // THEN its structure will be kept
+        assert_eq!(items, vec![
+            HtmlText::Text("Kirk is ".to_string()),
+            HtmlText::FormatText("very".to_string(), Format::Italic),
+            HtmlText::Text(" ".to_string()),
+            HtmlText::FormatText("tired".to_string(), Format::BoldItalic),
+            HtmlText::Text(".".to_string()),
+            HtmlText::Break,
+            HtmlText::Text("Sequel to ".to_string()),
+            HtmlText::LinkText(Link { title: "Shore Leave".to_string(), location: "/works/1".to_string() }),
+            HtmlText::Break,
+            HtmlText::Text("Now with & more".to_string()),
+            HtmlText::Blockquote,
+            HtmlText::Text("Quoted".to_string()),
+        ]);
+        assert_eq!(html_text_to_string(&items), "Kirk is very tired.\nSequel to Shore Leave\nNow with & more\nQuoted");
+    }
 }
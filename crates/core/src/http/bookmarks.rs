@@ -0,0 +1,112 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Serialize, Deserialize};
+use crate::helpers::decode_entities;
+use crate::html::form_value;
+use crate::selectors::selectors;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BookmarkForm {
+    pub pseud_id: String,
+    pub notes: String,
+    // Comma separated, as in AO3's form.
+    pub tags: String,
+    pub collections: String,
+    pub private: bool,
+    pub rec: bool,
+}
+
+// The bookmark form of a work's page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookmarkFields {
+    pub token: String,
+    // Set when the work is already bookmarked.
+    pub bookmark_id: Option<String>,
+    pub form: BookmarkForm,
+}
+
+fn field<'a>(form: ElementRef<'a>, select: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse(select).unwrap();
+    form.select(&selector).next()
+}
+
+fn field_value(form: ElementRef, name: &str) -> String {
+    field(form, &format!(r#"[name="{}"]"#, name)).and_then(form_value)
+                                                  .map(|value| decode_entities(value).into_owned())
+                                                  .unwrap_or_default()
+}
+
+// Checkboxes follow a hidden field of the same name, sent when they're unchecked.
+fn field_checked(form: ElementRef, name: &str) -> bool {
+    field(form, &format!(r#"input[type="checkbox"][name="{}"]"#, name))
+        .is_some_and(|checkbox| checkbox.value().attr("checked").is_some())
+}
+
+// Only logged in users get a bookmark form.
+pub fn scrape_bookmark_fields(frag: &Html) -> Option<BookmarkFields> {
+    let selector = Selector::parse(&selectors().forms.bookmark_form).unwrap();
+    let form = frag.select(&selector).next()?;
+    let token = field_value(form, "authenticity_token");
+    if token.is_empty() {
+        return None;
+    }
+
+    let bookmark_id = form.value().attr("action")
+                          .and_then(|action| action.rsplit_once("/bookmarks/"))
+                          .map(|(_, id)| id.to_string())
+                          .filter(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()));
+    let notes = field(form, r#"textarea[name="bookmark[bookmarker_notes]"]"#)
+                    .map(|notes| decode_entities(&notes.text().collect::<String>()).trim().to_string())
+                    .unwrap_or_default();
+
+    Some(BookmarkFields {
+        token,
+        bookmark_id,
+        form: BookmarkForm {
+            pseud_id: field_value(form, "bookmark[pseud_id]"),
+            notes,
+            tags: field_value(form, "bookmark[tag_string]"),
+            collections: field_value(form, "bookmark[collection_names]"),
+            private: field_checked(form, "bookmark[private]"),
+            rec: field_checked(form, "bookmark[rec]"),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOKMARKED: &str = r#"<html><body><div id="bookmark-form" class="wrapper toggled">
+<form class="edit_bookmark" id="edit_bookmark_42" action="/bookmarks/42" accept-charset="UTF-8" method="post">
+<input type="hidden" name="_method" value="patch" /><input type="hidden" name="authenticity_token" value="fixture-token" />
+<select name="bookmark[pseud_id]" id="bookmark_pseud_id"><option value="7">kirk</option><option selected="selected" value="8">jim</option></select>
+<textarea name="bookmark[bookmarker_notes]" id="bookmark_notes">Read &amp; loved.</textarea>
+<input type="text" name="bookmark[tag_string]" value="comfort, space" />
+<input type="text" name="bookmark[collection_names]" value="" />
+<input name="bookmark[private]" type="hidden" value="0" /><input type="checkbox" value="1" name="bookmark[private]" />
+<input name="bookmark[rec]" type="hidden" value="0" /><input type="checkbox" value="1" checked="checked" name="bookmark[rec]" />
+</form></div></body></html>"#;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aBookmarkedWork_WHEN_scrapeBookmarkFieldsIsCalled_THEN_theFormWillBePrefilled() {
+        // GIVEN a bookmarked work
+        let frag = Html::parse_document(BOOKMARKED);
+
+        // WHEN scrape_bookmark_fields is called
+        let fields = scrape_bookmark_fields(&frag).unwrap();
+
+        // THEN the form will be prefilled
+        assert_eq!(fields.token, "fixture-token");
+        assert_eq!(fields.bookmark_id.as_deref(), Some("42"));
+        assert_eq!(fields.form, BookmarkForm {
+            pseud_id: "8".to_string(),
+            notes: "Read & loved.".to_string(),
+            tags: "comfort, space".to_string(),
+            collections: String::new(),
+            private: false,
+            rec: true,
+        });
+    }
+}
@@ -0,0 +1,302 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::Utc;
+use serde::{Serialize, Deserialize};
+use crate::helpers::{load_json, save_json};
+
+const INDEX_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+const WORK_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+// Found in the pages with forms.
+const FORM_TOKEN: &str = "authenticity_token";
+// Eviction goes below the size cap, so that it doesn't happen on every insert.
+const EVICTION_RATIO: (u64, u64) = (3, 4);
+
+// How long a cached response can be used without asking AO3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    NoStore,
+    Index,
+    Work,
+}
+
+impl Freshness {
+    pub fn max_age(self) -> Option<Duration> {
+        match self {
+            Freshness::NoStore => None,
+            Freshness::Index => Some(INDEX_MAX_AGE),
+            Freshness::Work => Some(WORK_MAX_AGE),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CacheEntry {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub fetched: i64,
+    #[serde(default)]
+    pub has_form: bool,
+}
+
+impl CacheEntry {
+    pub fn new(url: &str, etag: Option<String>, last_modified: Option<String>) -> CacheEntry {
+        CacheEntry {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            fetched: Utc::now().timestamp(),
+            has_form: false,
+        }
+    }
+
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        Utc::now().timestamp().saturating_sub(self.fetched) < max_age.as_secs() as i64
+    }
+
+    // Work pages with forms hold tokens and bookmark state that change
+    // behind our back: they're always checked with AO3 first.
+    pub fn is_fresh_for(&self, freshness: Freshness) -> bool {
+        match freshness.max_age() {
+            Some(_) if freshness == Freshness::Work && self.has_form => false,
+            Some(max_age) => self.is_fresh(max_age),
+            None => false,
+        }
+    }
+}
+
+// Responses are kept on disk as a JSON entry next to the raw body, the
+// logged in account being part of the key since pages differ.
+pub struct HttpCache {
+    dir: PathBuf,
+    max_size: u64,
+    // The size on disk, measured the first time it's needed and kept up to
+    // date afterwards.
+    size: Mutex<Option<u64>>,
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map_or(0, |md| md.len())
+}
+
+impl HttpCache {
+    pub fn new(dir: PathBuf, max_size: u64) -> HttpCache {
+        HttpCache { dir, max_size, size: Mutex::new(None) }
+    }
+
+    fn key(url: &str, account: Option<&str>) -> String {
+        format!("{:016x}", fxhash::hash64(&(url, account)))
+    }
+
+    fn paths(&self, key: &str) -> (PathBuf, PathBuf) {
+        (self.dir.join(format!("{}.json", key)), self.dir.join(format!("{}.html", key)))
+    }
+
+    pub fn get(&self, url: &str, account: Option<&str>) -> Option<(CacheEntry, String)> {
+        let (entry_path, body_path) = self.paths(&HttpCache::key(url, account));
+        let entry = load_json::<CacheEntry, _>(&entry_path).ok()?;
+        // Guard against hash collisions.
+        if entry.url != url {
+            return None;
+        }
+        let body = fs::read_to_string(&body_path).ok()?;
+        Some((entry, body))
+    }
+
+    pub fn insert(&self, entry: &CacheEntry, account: Option<&str>, body: &str) {
+        if body.len() as u64 > self.max_size {
+            return;
+        }
+        let entry = CacheEntry { has_form: body.contains(FORM_TOKEN), ..entry.clone() };
+        let (entry_path, body_path) = self.paths(&HttpCache::key(&entry.url, account));
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            eprintln!("Can't create {}: {:#}.", self.dir.display(), e);
+            return;
+        }
+
+        let mut size = self.size.lock().unwrap();
+        let total = size.get_or_insert_with(|| self.measure());
+        let replaced = file_size(&entry_path) + file_size(&body_path);
+        let written = fs::write(&body_path, body)
+                         .map_err(|e| eprintln!("Can't write {}: {:#}.", body_path.display(), e))
+                         .and_then(|_| save_json(&entry, &entry_path).map_err(|e| eprintln!("{:#}", e)));
+        *total = (*total + file_size(&entry_path) + file_size(&body_path)).saturating_sub(replaced);
+        if written.is_ok() && *total > self.max_size {
+            *total = self.evict();
+        }
+    }
+
+    // Marks a revalidated entry as fresh again.
+    pub fn touch(&self, url: &str, account: Option<&str>) {
+        let (entry_path, _) = self.paths(&HttpCache::key(url, account));
+        if let Ok(mut entry) = load_json::<CacheEntry, _>(&entry_path) {
+            entry.fetched = Utc::now().timestamp();
+            save_json(&entry, &entry_path).map_err(|e| eprintln!("{:#}", e)).ok();
+        }
+    }
+
+    // Drops the logged out copy along with the account's own.
+    pub fn remove(&self, url: &str, account: Option<&str>) {
+        let mut size = self.size.lock().unwrap();
+        for account in [None, account] {
+            let (entry_path, body_path) = self.paths(&HttpCache::key(url, account));
+            let removed = file_size(&entry_path) + file_size(&body_path);
+            fs::remove_file(entry_path).ok();
+            fs::remove_file(body_path).ok();
+            if let Some(total) = size.as_mut() {
+                *total = total.saturating_sub(removed);
+            }
+        }
+    }
+
+    fn measure(&self) -> u64 {
+        fs::read_dir(&self.dir).into_iter().flatten().flatten()
+           .filter_map(|dir_entry| dir_entry.metadata().ok())
+           .map(|md| md.len())
+           .sum()
+    }
+
+    // Drops the least recently fetched entries until we're well under the
+    // size cap, and returns the size left.
+    fn evict(&self) -> u64 {
+        let mut entries = Vec::new();
+        let mut total = 0;
+
+        for dir_entry in fs::read_dir(&self.dir).into_iter().flatten().flatten() {
+            let path = dir_entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let body_path = path.with_extension("html");
+            let size = dir_entry.metadata().map_or(0, |md| md.len()) +
+                       fs::metadata(&body_path).map_or(0, |md| md.len());
+            let fetched = load_json::<CacheEntry, _>(&path).map_or(0, |entry| entry.fetched);
+            total += size;
+            entries.push((fetched, size, path, body_path));
+        }
+
+        let target = self.max_size * EVICTION_RATIO.0 / EVICTION_RATIO.1;
+        entries.sort_by_key(|(fetched, ..)| *fetched);
+
+        for (_, size, path, body_path) in entries {
+            if total <= target {
+                break;
+            }
+            fs::remove_file(path).ok();
+            fs::remove_file(body_path).ok();
+            total = total.saturating_sub(size);
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn test_cache(name: &str, max_size: u64) -> HttpCache {
+        let dir = env::temp_dir().join(format!("ao3reader-http-cache-{}", name));
+        fs::remove_dir_all(&dir).ok();
+        HttpCache::new(dir, max_size)
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aCachedPage_WHEN_getIsCalled_THEN_itWillOnlyMatchTheSameAccount() {
+        // GIVEN a cached page
+        let cache = test_cache("account", 1 << 20);
+        let url = "https://archiveofourown.org/works/1";
+        cache.insert(&CacheEntry::new(url, Some("\"abc\"".to_string()), None), Some("kirk"), "<html></html>");
+
+        // WHEN get is called
+        let same = cache.get(url, Some("kirk"));
+        let other = cache.get(url, Some("spock"));
+        let logged_out = cache.get(url, None);
+
+        // THEN it will only match the same account
+        let (entry, body) = same.expect("cached page");
+        assert_eq!(entry.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(body, "<html></html>");
+        assert!(other.is_none());
+        assert!(logged_out.is_none());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aCachedWorkWithAForm_WHEN_isFreshForIsCalled_THEN_itWillBeRevalidated() {
+        // GIVEN a cached work with a form
+        let cache = test_cache("form", 1 << 20);
+        let url = "https://archiveofourown.org/works/1";
+        cache.insert(&CacheEntry::new(url, None, None), None,
+                     r#"<form><input name="authenticity_token" value="fixture-token" /></form>"#);
+        let (entry, _) = cache.get(url, None).expect("cached page");
+
+        // WHEN is_fresh_for is called
+        let work = entry.is_fresh_for(Freshness::Work);
+        let index = entry.is_fresh_for(Freshness::Index);
+
+        // THEN it will be revalidated
+        assert!(entry.has_form);
+        assert!(!work);
+        assert!(index);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aFullCache_WHEN_insertIsCalled_THEN_theOldestEntriesWillBeEvicted() {
+        // GIVEN a full cache
+        let cache = test_cache("eviction", 1024);
+        let mut old = CacheEntry::new("https://archiveofourown.org/works/1", None, None);
+        old.fetched -= 60;
+        cache.insert(&old, None, &"a".repeat(600));
+
+        // WHEN insert is called
+        cache.insert(&CacheEntry::new("https://archiveofourown.org/works/2", None, None), None, &"b".repeat(600));
+
+        // THEN the oldest entries will be evicted
+        assert!(cache.get("https://archiveofourown.org/works/1", None).is_none());
+        assert!(cache.get("https://archiveofourown.org/works/2", None).is_some());
+        let size: u64 = fs::read_dir(&cache.dir).unwrap().flatten()
+                            .filter_map(|e| e.metadata().ok())
+                            .map(|md| md.len())
+                            .sum();
+        assert!(size <= 1024);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_cachedPages_WHEN_theyAreReplacedAndRemoved_THEN_theKeptSizeWillMatchTheDisk() {
+        // GIVEN cached pages
+        let cache = test_cache("size", 1 << 20);
+        cache.insert(&CacheEntry::new("https://archiveofourown.org/works/1", None, None), None, &"a".repeat(600));
+        cache.insert(&CacheEntry::new("https://archiveofourown.org/works/2", None, None), None, &"b".repeat(600));
+
+        // WHEN they are replaced and removed
+        cache.insert(&CacheEntry::new("https://archiveofourown.org/works/1", None, None), None, &"c".repeat(300));
+        cache.remove("https://archiveofourown.org/works/2", None);
+
+        // THEN the kept size will match the disk
+        assert_eq!(*cache.size.lock().unwrap(), Some(cache.measure()));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_anOldEntry_WHEN_isFreshIsCalled_THEN_itDependsOnThePolicy() {
+        // GIVEN an old entry
+        let mut entry = CacheEntry::new("https://archiveofourown.org/works/1", None, None);
+        entry.fetched -= 60 * 60;
+
+        // WHEN is_fresh is called
+        let index = entry.is_fresh(Freshness::Index.max_age().unwrap());
+        let work = entry.is_fresh(Freshness::Work.max_age().unwrap());
+
+        // THEN it depends on the policy
+        assert!(!index);
+        assert!(work);
+        assert!(Freshness::NoStore.max_age().is_none());
+    }
+}
@@ -0,0 +1,348 @@
+use std::fs;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Serialize, Deserialize};
+use crate::helpers::{decode_entities, load_json, save_json};
+use crate::html::{scrape_link_list, scrape_comment_csrf, form_value, Link};
+use crate::selectors::{selectors, CommentSelectors};
+use crate::document::escape_html;
+
+pub const DRAFTS_PATH: &str = ".comment-drafts.json";
+
+// The comment pages link to each other through this scheme, so that the
+// reader fetches them rather than opening AO3's pages as is.
+pub const COMMENTS_SCHEME: &str = "comments:";
+// Links to write a comment, followed by the path it's posted to.
+pub const COMMENT_SCHEME: &str = "comment:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub id: String,
+    pub author: String,
+    pub posted: String,
+    // As written, to be laid out by the HTML engine.
+    pub body: String,
+    pub replies: Vec<Comment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentPage {
+    pub title: String,
+    pub comments: Vec<Comment>,
+    // The other pages, if there are too many comments for one.
+    pub pagination: Vec<Link>,
+    // Where new comments are posted.
+    pub post_path: String,
+    // Missing when comments are closed.
+    pub fields: Option<CommentFields>,
+}
+
+// The hidden fields of a page's comment form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommentFields {
+    pub token: String,
+    // Empty for guests.
+    pub pseud_id: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CommentForm {
+    pub pseud_id: String,
+    // Guests sign their comments.
+    pub name: String,
+    pub email: String,
+    pub content: String,
+}
+
+impl CommentForm {
+    pub fn is_empty(&self) -> bool {
+        self.content.trim().is_empty()
+    }
+}
+
+// Comments that weren't posted, by the path they're meant for.
+pub struct CommentDrafts {
+    path: PathBuf,
+    drafts: BTreeMap<String, CommentForm>,
+}
+
+impl CommentDrafts {
+    pub fn load(path: PathBuf) -> CommentDrafts {
+        let drafts = if path.exists() {
+            load_json::<BTreeMap<String, CommentForm>, _>(&path)
+                .map_err(|e| eprintln!("Can't load comment drafts: {:#}.", e))
+                .unwrap_or_default()
+        } else {
+            BTreeMap::new()
+        };
+        CommentDrafts { path, drafts }
+    }
+
+    fn save(&self) {
+        if self.drafts.is_empty() {
+            fs::remove_file(&self.path).ok();
+        } else if let Err(e) = save_json(&self.drafts, &self.path) {
+            eprintln!("Can't save comment drafts: {:#}.", e);
+        }
+    }
+
+    pub fn get(&self, post_path: &str) -> Option<&CommentForm> {
+        self.drafts.get(post_path)
+    }
+
+    // Guests sign new comments as in their other drafts.
+    pub fn signature(&self) -> Option<(&str, &str)> {
+        self.drafts.values()
+            .find(|form| !form.name.is_empty())
+            .map(|form| (form.name.as_str(), form.email.as_str()))
+    }
+
+    // Empty drafts are dropped.
+    pub fn set(&mut self, post_path: &str, form: CommentForm) {
+        if form.is_empty() {
+            if self.drafts.remove(post_path).is_none() {
+                return;
+            }
+        } else {
+            self.drafts.insert(post_path.to_string(), form);
+        }
+        self.save();
+    }
+
+    pub fn remove(&mut self, post_path: &str) {
+        if self.drafts.remove(post_path).is_some() {
+            self.save();
+        }
+    }
+}
+
+// The comments of a work, or of one of its chapters.
+pub fn comments_path(page_path: &str) -> String {
+    let path = page_path.split(['?', '#']).next().unwrap_or_default();
+    if path.contains("/chapters/") {
+        format!("{}?show_comments=true&view_adult=true", path)
+    } else {
+        format!("{}?show_comments=true&view_full_work=true&view_adult=true", path)
+    }
+}
+
+// Where the comments of a work's or chapter's page are posted.
+pub fn post_path(page_path: &str) -> String {
+    let path = page_path.split(['?', '#']).next().unwrap_or_default();
+    match path.split_once("/chapters/") {
+        Some((_, chapter_id)) => format!("/chapters/{}/comments", chapter_id),
+        None => format!("{}/comments", path),
+    }
+}
+
+// Where the replies to a comment are posted.
+pub fn reply_path(comment_id: &str) -> String {
+    format!("/comments/{}/comments", comment_id.trim_start_matches("comment_"))
+}
+
+pub fn scrape_comment_fields(frag: &Html) -> Option<CommentFields> {
+    let token = scrape_comment_csrf(frag)?.to_string();
+    let pseud_selector = Selector::parse(&selectors().forms.comment_pseud).unwrap();
+    let pseud_id = frag.select(&pseud_selector).next()
+                       .and_then(form_value)
+                       .unwrap_or_default().to_string();
+    Some(CommentFields { token, pseud_id })
+}
+
+fn clean_text(el: ElementRef) -> String {
+    decode_entities(&el.text().collect::<Vec<_>>().join(" ")).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn select_text(el: ElementRef, select: &str) -> Option<String> {
+    let selector = Selector::parse(select).unwrap();
+    el.select(&selector).next().map(clean_text)
+}
+
+fn scrape_comment(el: ElementRef, select: &CommentSelectors) -> Comment {
+    let posted = select_text(el, &select.posted).unwrap_or_default();
+    // Guests don't have a profile to link to.
+    let author = select_text(el, &select.author).unwrap_or_else(|| {
+        let byline = select_text(el, &select.byline).unwrap_or_default();
+        byline.trim_end_matches(posted.as_str()).trim().to_string()
+    });
+    let body_selector = Selector::parse(&select.body).unwrap();
+    let body = el.select(&body_selector).next()
+                 .map(|body| body.inner_html())
+                 .unwrap_or_default();
+    Comment {
+        id: el.value().id().unwrap_or_default().to_string(),
+        author,
+        posted,
+        body,
+        replies: Vec::new(),
+    }
+}
+
+// Threads are lists where each comment is followed by the thread of its
+// replies, if any.
+fn scrape_thread(thread: ElementRef, select: &CommentSelectors, comments: &mut Vec<Comment>) {
+    let comment_selector = Selector::parse(&select.comment).unwrap();
+    let thread_selector = Selector::parse(&select.thread).unwrap();
+    for item in thread.children().filter_map(ElementRef::wrap) {
+        if comment_selector.matches(&item) {
+            comments.push(scrape_comment(item, select));
+            continue;
+        }
+        for replies in item.children().filter_map(ElementRef::wrap) {
+            if thread_selector.matches(&replies) {
+                match comments.last_mut() {
+                    Some(parent) => scrape_thread(replies, select, &mut parent.replies),
+                    None => scrape_thread(replies, select, comments),
+                }
+            }
+        }
+    }
+}
+
+pub fn scrape_comments(frag: &Html, title: &str, post_path: &str) -> CommentPage {
+    let profile = selectors();
+    let select = &profile.comments;
+    let threads_selector = Selector::parse(&select.threads).unwrap();
+    let mut comments = Vec::new();
+    for thread in frag.select(&threads_selector) {
+        scrape_thread(thread, select, &mut comments);
+    }
+    CommentPage {
+        title: title.to_string(),
+        comments,
+        pagination: scrape_link_list(frag, &select.pagination),
+        post_path: post_path.to_string(),
+        fields: scrape_comment_fields(frag),
+    }
+}
+
+fn comments_as_html_aux(comments: &[Comment], can_reply: bool, buf: &mut String) {
+    for comment in comments {
+        buf.push_str(&format!("\t\t<div class=\"thread\" id=\"{}\">\n", comment.id));
+        buf.push_str(&format!("\t\t\t<p class=\"byline\"><strong>{}</strong> <small>{}</small></p>\n",
+                              escape_html(&comment.author), escape_html(&comment.posted)));
+        buf.push_str(&format!("\t\t\t<div class=\"comment\">{}</div>\n", comment.body));
+        if can_reply {
+            buf.push_str(&format!("\t\t\t<p class=\"reply\"><a href=\"{}{}\">Reply</a></p>\n",
+                                  COMMENT_SCHEME, reply_path(&comment.id)));
+        }
+        comments_as_html_aux(&comment.replies, can_reply, buf);
+        buf.push_str("\t\t</div>\n");
+    }
+}
+
+pub fn comments_as_html(page: &CommentPage) -> String {
+    let mut buf = format!("<html>\n\t<head>\n\t\t<title>{}</title>\n\t\t\
+                           <link rel=\"stylesheet\" type=\"text/css\" href=\"css/comments.css\"/>\n\t\
+                           </head>\n\t<body>\n", escape_html(&page.title));
+    buf.push_str(&format!("\t\t<h1>{}</h1>\n", escape_html(&page.title)));
+    if page.fields.is_some() {
+        buf.push_str(&format!("\t\t<p class=\"reply\"><a href=\"{}{}\">Add a comment</a></p>\n",
+                              COMMENT_SCHEME, escape_html(&page.post_path)));
+    }
+    if page.comments.is_empty() {
+        buf.push_str("\t\t<p>No comments yet.</p>\n");
+    }
+    comments_as_html_aux(&page.comments, page.fields.is_some(), &mut buf);
+    if !page.pagination.is_empty() {
+        buf.push_str("\t\t<p class=\"pagination\">");
+        let links: Vec<String> = page.pagination.iter().map(|link| {
+            format!("<a href=\"{}{}\">{}</a>", COMMENTS_SCHEME, escape_html(&link.location), escape_html(&link.title))
+        }).collect();
+        buf.push_str(&links.join(" "));
+        buf.push_str("</p>\n");
+    }
+    buf.push_str("\t</body>\n</html>");
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const COMMENTS: &str = r#"<html><body><div id="comments_placeholder">
+<ol class="thread">
+<li class="comment group odd" id="comment_1" role="article">
+<h4 class="heading byline"><a href="/users/kirk/pseuds/kirk">kirk</a> <span class="posted datetime"><span class="date">14</span> <abbr class="month">Jan</abbr> <span class="year">2024</span></span></h4>
+<blockquote class="userstuff"><p>Loved <em>this</em>!</p></blockquote>
+</li>
+<li>
+<ol class="thread">
+<li class="comment group even" id="comment_2" role="article">
+<h4 class="heading byline">spock (Guest) <span class="posted datetime"><span class="date">15</span> <abbr class="month">Jan</abbr> <span class="year">2024</span></span></h4>
+<blockquote class="userstuff"><p>Fascinating.</p></blockquote>
+</li>
+</ol>
+</li>
+<li class="comment group odd" id="comment_3" role="article">
+<h4 class="heading byline"><a href="/users/uhura/pseuds/uhura">uhura</a></h4>
+<blockquote class="userstuff"><p>More please.</p></blockquote>
+</li>
+</ol>
+<ol class="pagination actions"><li><span class="current">1</span></li><li><a href="/works/1?page=2&amp;show_comments=true">2</a></li></ol>
+</div></body></html>"#;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aCommentPage_WHEN_scrapeCommentsIsCalled_THEN_repliesWillBeNestedUnderTheirParent() {
+        // GIVEN a comment page
+        let frag = Html::parse_document(COMMENTS);
+
+        // WHEN scrape_comments is called
+        let page = scrape_comments(&frag, "Comments on Shore Leave", "/works/1/comments");
+
+        // THEN replies will be nested under their parent
+        assert_eq!(page.comments.len(), 2);
+        assert_eq!(page.comments[0].author, "kirk");
+        assert_eq!(page.comments[0].posted, "14 Jan 2024");
+        assert_eq!(page.comments[0].body, "<p>Loved <em>this</em>!</p>");
+        assert_eq!(page.comments[0].replies.len(), 1);
+        assert_eq!(page.comments[0].replies[0].author, "spock (Guest)");
+        assert_eq!(page.comments[1].id, "comment_3");
+        assert_eq!(page.pagination, vec![Link { title: "2".to_string(), location: "/works/1?page=2&show_comments=true".to_string() }]);
+        let html = comments_as_html(&page);
+        assert!(html.contains(r#"<a href="comments:/works/1?page=2&amp;show_comments=true">2</a>"#));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aChapterPage_WHEN_commentsPathIsCalled_THEN_onlyItsCommentsWillBeShown() {
+        // GIVEN a chapter page
+        let chapter = "/works/1/chapters/12?view_adult=true";
+
+        // WHEN comments_path is called
+        let path = comments_path(chapter);
+
+        // THEN only its comments will be shown
+        assert_eq!(path, "/works/1/chapters/12?show_comments=true&view_adult=true");
+        assert_eq!(comments_path("/works/1"), "/works/1?show_comments=true&view_full_work=true&view_adult=true");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_anUnsentComment_WHEN_theDraftsAreLoaded_THEN_itWillStillBeThere() {
+        // GIVEN an unsent comment
+        let path = env::temp_dir().join("ao3reader-comment-drafts.json");
+        fs::remove_file(&path).ok();
+        let mut drafts = CommentDrafts::load(path.clone());
+        drafts.set("/works/1/comments", CommentForm {
+            name: "spock".to_string(),
+            content: "Fascinating.".to_string(),
+            .. Default::default()
+        });
+        drafts.set(&reply_path("comment_2"), CommentForm::default());
+
+        // WHEN the drafts are loaded
+        let mut loaded = CommentDrafts::load(path);
+
+        // THEN it will still be there
+        assert_eq!(loaded.get("/works/1/comments").map(|form| form.content.as_str()), Some("Fascinating."));
+        assert_eq!(loaded.get("/comments/2/comments"), None);
+        assert_eq!(loaded.signature(), Some(("spock", "")));
+        loaded.set("/works/1/comments", CommentForm::default());
+        assert_eq!(loaded.get("/works/1/comments"), None);
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use reqwest::StatusCode;
+use reqwest::header::RANGE;
+use serde::{Serialize, Deserialize};
+use scraper::Html;
+use crate::html::{self, Link};
+use crate::selectors::selectors;
+use super::{HttpClient, Ao3Error, Freshness};
+
+// Longest title kept in a file name, in characters.
+const MAX_NAME_LEN: usize = 96;
+const PARTIAL_EXTENSION: &str = "part";
+
+// The formats listed under AO3's download button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DownloadFormat {
+    Azw3,
+    Epub,
+    Mobi,
+    Pdf,
+    Html,
+}
+
+impl DownloadFormat {
+    pub const ALL: [DownloadFormat; 5] = [DownloadFormat::Azw3, DownloadFormat::Epub, DownloadFormat::Mobi,
+                                          DownloadFormat::Pdf, DownloadFormat::Html];
+
+    pub fn from_label(label: &str) -> Option<DownloadFormat> {
+        DownloadFormat::ALL.iter().cloned().find(|format| format.label().eq_ignore_ascii_case(label.trim()))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DownloadFormat::Azw3 => "AZW3",
+            DownloadFormat::Epub => "EPUB",
+            DownloadFormat::Mobi => "MOBI",
+            DownloadFormat::Pdf => "PDF",
+            DownloadFormat::Html => "HTML",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            DownloadFormat::Azw3 => "azw3",
+            DownloadFormat::Epub => "epub",
+            DownloadFormat::Mobi => "mobi",
+            DownloadFormat::Pdf => "pdf",
+            DownloadFormat::Html => "html",
+        }
+    }
+}
+
+impl fmt::Display for DownloadFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+// The links of a work page's download menu, in AO3's order.
+pub fn download_links(frag: &Html) -> Vec<(DownloadFormat, Link)> {
+    html::scrape_link_list(frag, &selectors().work.downloads)
+        .into_iter()
+        .filter_map(|link| DownloadFormat::from_label(&link.title).map(|format| (format, link)))
+        .collect()
+}
+
+// Keeps the name readable while staying valid on FAT file systems.
+pub fn sanitize_filename(name: &str) -> String {
+    let cleaned: String = name.chars()
+                              .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+                              .collect::<String>()
+                              .split_whitespace()
+                              .collect::<Vec<_>>()
+                              .join(" ");
+    let cleaned: String = cleaned.chars().take(MAX_NAME_LEN).collect();
+    let cleaned = cleaned.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if cleaned.is_empty() {
+        "Untitled".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+// The work id tells apart works sharing the same title.
+pub fn download_name(title: &str, work_id: &str, format: DownloadFormat) -> String {
+    format!("{} ({}).{}", sanitize_filename(title), work_id, format.extension())
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(PARTIAL_EXTENSION);
+    path.with_file_name(name)
+}
+
+fn storage_error(path: &Path, err: std::io::Error) -> Ao3Error {
+    Ao3Error::Storage(format!("{}: {}", path.display(), err))
+}
+
+impl HttpClient {
+    // Saves the work in `dir` and returns the path of the file.
+    pub fn download_work(&self, work_id: &str, format: DownloadFormat, dir: &Path,
+                         on_progress: &mut dyn FnMut(usize, Option<u64>) -> bool) -> Result<PathBuf, Ao3Error> {
+        let work_url = self.url(&format!("/works/{}?view_adult=true", work_id));
+        let work_html = self.get_parse(work_url.as_str(), Freshness::Work)?;
+        let link = download_links(&work_html).into_iter()
+                                             .find(|(link_format, _)| *link_format == format)
+                                             .map(|(_, link)| link)
+                                             .ok_or_else(|| Ao3Error::Parse(format!("the {} download link", format)))?;
+        let title = html::scrape(&work_html, &selectors().work.title)?;
+        let path = dir.join(download_name(&title, work_id, format));
+        self.download(self.url(&link.location).as_str(), &path, on_progress)?;
+        Ok(path)
+    }
+
+    // Streams `url` to `path`. The data goes to a `.part` file first, which
+    // is picked up where it was left if the download was interrupted.
+    pub fn download(&self, url: &str, path: &Path,
+                    on_progress: &mut dyn FnMut(usize, Option<u64>) -> bool) -> Result<(), Ao3Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| storage_error(parent, e))?;
+        }
+
+        let part_path = partial_path(path);
+        let offset = fs::metadata(&part_path).map_or(0, |md| md.len());
+        let mut request = self.get(url);
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+
+        let mut res = self.send(request)?;
+        let status = res.status();
+        let resumed = status == StatusCode::PARTIAL_CONTENT;
+
+        // The partial file was already complete.
+        if status == StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+            return fs::rename(&part_path, path).map_err(|e| storage_error(path, e));
+        }
+
+        if !status.is_success() {
+            return Err(Ao3Error::Status(status));
+        }
+
+        let mut options = OpenOptions::new();
+        if resumed {
+            options.append(true);
+        } else {
+            options.write(true).truncate(true);
+        }
+        let mut file = options.create(true)
+                              .open(&part_path)
+                              .map_err(|e| storage_error(&part_path, e))?;
+
+        let start = if resumed { offset } else { 0 };
+        let total = res.content_length().map(|len| start + len);
+        let mut received = start as usize;
+        let mut buf = [0u8; 16 * 1024];
+
+        loop {
+            let n = res.read(&mut buf).map_err(|e| Ao3Error::Network(e.to_string()))?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n]).map_err(|e| storage_error(&part_path, e))?;
+            received += n;
+            if !on_progress(received, total) {
+                return Err(Ao3Error::Cancelled);
+            }
+        }
+
+        file.flush().map_err(|e| storage_error(&part_path, e))?;
+        fs::rename(&part_path, path).map_err(|e| storage_error(path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_awkwardTitles_WHEN_downloadNameIsCalled_THEN_theyWillBeSafeFileNames() {
+        // GIVEN awkward titles
+        let slashed = "Kirk/Spock: A \"Love\" Story?";
+        let dotted = "...  ";
+        let long = "a".repeat(300);
+
+        // WHEN download_name is called
+        let slashed_name = download_name(slashed, "1", DownloadFormat::Epub);
+        let dotted_name = download_name(dotted, "2", DownloadFormat::Pdf);
+        let long_name = download_name(&long, "3", DownloadFormat::Azw3);
+
+        // THEN they will be safe file names
+        assert_eq!(slashed_name, "Kirk_Spock_ A _Love_ Story_ (1).epub");
+        assert_eq!(dotted_name, "Untitled (2).pdf");
+        assert_eq!(long_name, format!("{} (3).azw3", "a".repeat(MAX_NAME_LEN)));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_downloadLabels_WHEN_fromLabelIsCalled_THEN_knownFormatsWillBeRecognized() {
+        // GIVEN download labels
+        let labels = ["AZW3", "EPUB", "MOBI", "PDF", "HTML", "DOCX"];
+
+        // WHEN from_label is called
+        let formats: Vec<Option<DownloadFormat>> = labels.iter().map(|label| DownloadFormat::from_label(label)).collect();
+
+        // THEN known formats will be recognized
+        assert_eq!(formats, vec![Some(DownloadFormat::Azw3), Some(DownloadFormat::Epub), Some(DownloadFormat::Mobi),
+                                 Some(DownloadFormat::Pdf), Some(DownloadFormat::Html), None]);
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use fxhash::FxHashMap;
+use reqwest::blocking::Response;
+use reqwest::header::{HeaderName, LOCATION, SET_COOKIE, CONTENT_TYPE, ETAG, LAST_MODIFIED, RETRY_AFTER};
+use reqwest::{Method, ResponseBuilderExt, Url};
+use serde::{Serialize, Deserialize};
+use crate::helpers::{load_json, save_json};
+use super::Ao3Error;
+
+// Longest URL slug used as is in a fixture's file name.
+const MAX_SLUG_LEN: usize = 96;
+
+// The only headers the client looks at.
+const RECORDED_HEADERS: [HeaderName; 6] = [LOCATION, SET_COOKIE, CONTENT_TYPE, ETAG, LAST_MODIFIED, RETRY_AFTER];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FixtureMode {
+    Off,
+    // Hits AO3 and saves every exchange.
+    Record,
+    // Never touches the network.
+    Replay,
+}
+
+// A recorded request/response pair. Request bodies are left out on
+// purpose: they hold passwords and CSRF tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+    // The URL after redirects.
+    pub final_url: String,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    #[serde(default)]
+    pub body: String,
+}
+
+impl Exchange {
+    fn into_response(self) -> Result<Response, Ao3Error> {
+        let final_url = Url::parse(&self.final_url).map_err(|e| Ao3Error::Network(e.to_string()))?;
+        let mut builder = http::Response::builder()
+                                         .status(self.status)
+                                         .url(final_url);
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        builder.body(self.body)
+               .map(Response::from)
+               .map_err(|e| Ao3Error::Network(e.to_string()))
+    }
+}
+
+pub struct Fixtures {
+    mode: FixtureMode,
+    dir: PathBuf,
+    // How many times each fixture has been used.
+    hits: Mutex<FxHashMap<String, usize>>,
+}
+
+impl Fixtures {
+    pub fn new(mode: FixtureMode, dir: PathBuf) -> Fixtures {
+        Fixtures { mode, dir, hits: Mutex::new(FxHashMap::default()) }
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    // Fixtures are named after the method and the path of the request,
+    // so that they can be written by hand and survive a change of mirror.
+    pub fn name(method: &Method, url: &Url) -> String {
+        let mut target = url.path().to_string();
+        if let Some(query) = url.query() {
+            target.push('?');
+            target.push_str(query);
+        }
+        let mut slug: String = target.chars()
+                                     .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+                                     .collect::<String>()
+                                     .split('_')
+                                     .filter(|part| !part.is_empty())
+                                     .collect::<Vec<_>>()
+                                     .join("_");
+        if slug.is_empty() {
+            slug.push_str("index");
+        }
+        if slug.len() > MAX_SLUG_LEN {
+            slug.truncate(MAX_SLUG_LEN);
+            slug = format!("{}_{:016x}", slug, fxhash::hash64(&target));
+        }
+        format!("{}-{}.json", method.as_str().to_lowercase(), slug)
+    }
+
+    // Repeated requests are saved as `<name>.2.json`, `<name>.3.json`, etc.
+    // When replaying, the first fixture stands in for the missing ones.
+    fn path(&self, method: &Method, url: &Url) -> PathBuf {
+        let name = Fixtures::name(method, url);
+        let mut hits = self.hits.lock().unwrap();
+        let count = hits.entry(name.clone()).or_insert(0);
+        *count += 1;
+
+        let path = self.dir.join(&name);
+        if *count == 1 {
+            return path;
+        }
+        let numbered = self.dir.join(format!("{}.{}.json", name.trim_end_matches(".json"), count));
+        if self.mode == FixtureMode::Replay && !numbered.exists() {
+            path
+        } else {
+            numbered
+        }
+    }
+
+    pub fn load(&self, method: &Method, url: &Url) -> Result<Exchange, Ao3Error> {
+        let path = self.path(method, url);
+        load_json::<Exchange, _>(&path)
+            .map_err(|e| Ao3Error::Network(format!("no fixture for {} {} ({:#})", method, url, e)))
+    }
+
+    pub fn replay(&self, method: &Method, url: &Url) -> Result<Response, Ao3Error> {
+        self.load(method, url)?.into_response()
+    }
+
+    // Saves the exchange and hands back an equivalent response, since
+    // reading the body consumes the original one. Binary bodies, e.g.
+    // downloaded works, can't be stored as text and are left out.
+    pub fn record(&self, method: &Method, url: &Url, res: Response) -> Result<Response, Ao3Error> {
+        let textual = res.headers().get(CONTENT_TYPE)
+                         .and_then(|value| value.to_str().ok())
+                         .is_none_or(|value| value.starts_with("text/") || value.contains("json") ||
+                                             value.contains("javascript") || value.contains("xml"));
+        if !textual {
+            return Ok(res);
+        }
+
+        let status = res.status().as_u16();
+        let final_url = res.url().to_string();
+        let headers = res.headers().iter()
+                         .filter(|(name, _)| RECORDED_HEADERS.contains(name))
+                         .filter_map(|(name, value)| value.to_str().ok().map(|v| (name.to_string(), v.to_string())))
+                         .collect();
+        let body = res.text()?;
+        let exchange = Exchange {
+            method: method.to_string(),
+            url: url.to_string(),
+            status,
+            final_url,
+            headers,
+            body,
+        };
+
+        let path = self.path(method, url);
+        fs::create_dir_all(&self.dir)
+           .map_err(|e| eprintln!("Can't create {}: {:#}.", self.dir.display(), e))
+           .ok();
+        save_json(&exchange, &path).map_err(|e| eprintln!("{:#}", e)).ok();
+
+        exchange.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_requestUrls_WHEN_nameIsCalled_THEN_itWillOnlyDependOnMethodAndPath() {
+        // GIVEN request URLs
+        let work = Url::parse("https://archiveofourown.org/works/1?view_full_work=true&view_adult=true").unwrap();
+        let mirror = Url::parse("https://ao3.example.org/works/1?view_full_work=true&view_adult=true").unwrap();
+        let root = Url::parse("https://archiveofourown.org/").unwrap();
+        let long = Url::parse(&format!("https://archiveofourown.org/tags/{}/works", "a".repeat(200))).unwrap();
+
+        // WHEN name is called
+        let work_name = Fixtures::name(&Method::GET, &work);
+
+        // THEN it will only depend on the method and the path
+        assert_eq!(work_name, "get-works_1_view_full_work_true_view_adult_true.json");
+        assert_eq!(Fixtures::name(&Method::GET, &mirror), work_name);
+        assert_eq!(Fixtures::name(&Method::POST, &root), "post-index.json");
+        assert!(Fixtures::name(&Method::GET, &long).len() < MAX_SLUG_LEN + 32);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_repeatedRequests_WHEN_theyAreReplayed_THEN_numberedFixturesWillBeUsedInOrder() {
+        // GIVEN repeated requests
+        let dir = std::env::temp_dir().join("ao3reader-fixtures-numbered");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let url = Url::parse("https://archiveofourown.org/works/1").unwrap();
+        for (name, body) in [("get-works_1.json", "first"), ("get-works_1.2.json", "second")] {
+            let exchange = Exchange {
+                method: "GET".to_string(),
+                url: url.to_string(),
+                status: 200,
+                final_url: url.to_string(),
+                headers: Vec::new(),
+                body: body.to_string(),
+            };
+            save_json(&exchange, dir.join(name)).unwrap();
+        }
+        let fixtures = Fixtures::new(FixtureMode::Replay, dir);
+
+        // WHEN they are replayed
+        let bodies: Vec<String> = (0..3).map(|_| fixtures.load(&Method::GET, &url).unwrap().body).collect();
+
+        // THEN numbered fixtures will be used in order
+        assert_eq!(bodies, vec!["first", "second", "first"]);
+    }
+}
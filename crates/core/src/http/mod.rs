@@ -0,0 +1,1334 @@
+mod scheduler;
+mod cache;
+mod fixture;
+pub mod pool;
+pub mod outbox;
+pub mod download;
+pub mod comments;
+pub mod bookmarks;
+pub mod subscriptions;
+
+use crate::context::Context;
+
+use crate::helpers::get_url;
+use crate::html::{self, scrape_login_csrf, scrape_logout_csrf, ScrapeError, PageErrors};
+use crate::settings::Settings;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::cookie::CookieStore;
+use reqwest::cookie::Jar;
+use reqwest::header::{LOCATION, RETRY_AFTER, SET_COOKIE, ETAG, LAST_MODIFIED, IF_NONE_MATCH, IF_MODIFIED_SINCE};
+use reqwest::redirect::Policy;
+use reqwest::{StatusCode, Url};
+use serde::{Serialize, Deserialize};
+use serde_json::{Value, Map};
+use scraper::Html;
+use thiserror::Error;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::io::Read;
+use std::time::Duration;
+use chrono::Utc;
+use self::scheduler::{Scheduler, MAX_INLINE_WAIT, parse_retry_after};
+use self::cache::{HttpCache, CacheEntry};
+use self::fixture::Fixtures;
+
+pub use self::cache::Freshness;
+pub use self::fixture::FixtureMode;
+pub use self::download::DownloadFormat;
+pub use self::comments::CommentForm;
+pub use self::bookmarks::BookmarkForm;
+
+const AO3_LOGIN: &str = "/users/login";
+const AO3_LOGOUT: &str = "/users/logout";
+// AO3 tags the body of every page with the visitor's login state.
+const AO3_LOGGED_IN: &str = "<body class=\"logged-in\"";
+const AO3_LOGGED_OUT: &str = "<body class=\"logged-out\"";
+const AO3_FAILED_LOGIN: &str = "The password or user name you entered doesn't match our records.";
+const AO3_SUCCESS_LOGIN: &str = "Successfully logged in.";
+const AO3_ALREADY_LOGIN: &str = "You are already signed in.";
+const AO3_RETRY_LATER: &str = "Retry later";
+const AO3_MAINTENANCE: &str = "The Archive is down for maintenance.";
+const AO3_PLEASE_LOG_IN: &str = "Please log in.";
+const AO3_RESTRICTED: &str = "This work is only available to registered users of the Archive.";
+const AO3_ADULT_CONTENT: &str = "This work could have adult content.";
+const AO3_KUDOS: &str = "/kudos.js";
+const AO3_KUDOS_THANKS: &str = "Thank you for leaving kudos!";
+const AO3_TOKEN_DISPENSER: &str = "/token_dispenser.json";
+const AO3_MARKED_FOR_LATER: &str = "This work was added to your Marked for Later list.";
+const AO3_MARKED_AS_READ: &str = "This work was removed from your Marked for Later list.";
+const AO3_BOOKMARKED: &str = "Bookmark was successfully created.";
+const AO3_BOOKMARK_UPDATED: &str = "Bookmark was successfully updated.";
+const AO3_BOOKMARK_DELETED: &str = "Bookmark was successfully deleted.";
+const AO3_COMMENTED: &str = "Comment created!";
+const AO3_SUBSCRIBED: &str = "You're subscribed.";
+const AO3_UNSUBSCRIBED: &str = "You're unsubscribed.";
+const AO3_RETRY_LATER_STATUS: u16 = 525;
+const MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Error, Debug, Clone)]
+pub enum Ao3Error {
+    #[error("Can't reach AO3: {0}.")]
+    Network(String),
+
+    #[error("AO3 took too long to respond.")]
+    Timeout,
+
+    #[error("AO3 responded with {0}.")]
+    Status(StatusCode),
+
+    #[error("AO3 is busy or down for maintenance. Retry later.")]
+    RetryLater,
+
+    #[error("AO3 asked us to slow down. Waiting {0} seconds.")]
+    RateLimited(u64),
+
+    #[error("You need to log in to see this.")]
+    LoginRequired,
+
+    #[error("Can't log in: the user name or password doesn't match AO3's records.")]
+    LoginFailed,
+
+    #[error("Your AO3 session has expired. Log in again.")]
+    SessionExpired,
+
+    #[error("This work is behind the adult content warning.")]
+    AdultContent,
+
+    #[error("This page doesn't exist or has been deleted.")]
+    NotFound,
+
+    #[error("Unwrangled tag! No works available.")]
+    Unwrangled,
+
+    #[error("Can't parse the page: {0} is missing.")]
+    Parse(String),
+
+    #[error("Can't save the file {0}.")]
+    Storage(String),
+
+    #[error("AO3 didn't take it: {0}")]
+    Rejected(String),
+
+    #[error("Cancelled.")]
+    Cancelled,
+}
+
+impl Ao3Error {
+    // Whether the same request might go through later on.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Ao3Error::Network(_) | Ao3Error::Timeout | Ao3Error::RetryLater | Ao3Error::RateLimited(_))
+    }
+}
+
+impl From<ScrapeError> for Ao3Error {
+    fn from(err: ScrapeError) -> Self {
+        Ao3Error::Parse(err.selector().to_string())
+    }
+}
+
+impl From<PageErrors> for Ao3Error {
+    fn from(err: PageErrors) -> Self {
+        Ao3Error::Parse(err.selectors().join(", "))
+    }
+}
+
+impl From<reqwest::Error> for Ao3Error {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Ao3Error::Timeout
+        } else {
+            Ao3Error::Network(err.to_string())
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct KudosRes {
+    errors: Map<String, Value>
+}
+
+#[derive(Deserialize)]
+struct TokenRes {
+    token: String,
+}
+
+// Rails forms tunnel the other methods through POST.
+fn bookmark_params<'a>(method: Option<&'a str>, form: &'a BookmarkForm) -> Vec<(&'a str, &'a str)> {
+    let mut params = Vec::new();
+    if let Some(method) = method {
+        params.push(("_method", method));
+    }
+    params.extend([
+        ("bookmark[pseud_id]", form.pseud_id.as_str()),
+        ("bookmark[bookmarker_notes]", &form.notes),
+        ("bookmark[tag_string]", &form.tags),
+        ("bookmark[collection_names]", &form.collections),
+        ("bookmark[private]", if form.private { "1" } else { "0" }),
+        ("bookmark[rec]", if form.rec { "1" } else { "0" }),
+    ]);
+    params
+}
+
+// Forms that AO3 didn't take are shown again, with the reasons why.
+fn confirm(notices: Vec<String>, expected: &str) -> Result<Vec<String>, Ao3Error> {
+    if notices.iter().any(|notice| notice.contains(expected)) {
+        Ok(notices)
+    } else {
+        Err(Ao3Error::Rejected(notices.join(" ")))
+    }
+}
+
+fn is_throttled(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS ||
+    status == StatusCode::SERVICE_UNAVAILABLE ||
+    status.as_u16() == AO3_RETRY_LATER_STATUS
+}
+
+fn rate_limited(wait: Duration) -> Ao3Error {
+    Ao3Error::RateLimited((wait.as_millis() as u64).div_ceil(1000))
+}
+
+fn is_session_expired(url: &Url, body: &str) -> bool {
+    url.path() == AO3_LOGIN || body.contains(AO3_LOGGED_OUT)
+}
+
+fn kudos_message(val: &Value) -> String {
+    val.as_str().map_or_else(|| val.to_string(), String::from)
+}
+
+// Maps a response to the reason AO3 didn't give us the page we asked for, if any.
+// `url` is the final URL, after redirects.
+pub fn check_page(status: StatusCode, url: &Url, body: &str) -> Result<(), Ao3Error> {
+    match status {
+        StatusCode::NOT_FOUND | StatusCode::GONE => return Err(Ao3Error::NotFound),
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => return Err(Ao3Error::RetryLater),
+        s if s.as_u16() == AO3_RETRY_LATER_STATUS => return Err(Ao3Error::RetryLater),
+        s if !s.is_success() => return Err(Ao3Error::Status(s)),
+        _ => {}
+    }
+
+    if body.trim_start().starts_with(AO3_RETRY_LATER) || body.contains(AO3_MAINTENANCE) {
+        return Err(Ao3Error::RetryLater);
+    }
+
+    // Restricted works redirect to the login form.
+    let login_redirect = url.path() == AO3_LOGIN &&
+                         (url.query_pairs().any(|(k, _)| k == "restricted") || body.contains(AO3_PLEASE_LOG_IN));
+    if login_redirect || body.contains(AO3_RESTRICTED) {
+        return Err(Ao3Error::LoginRequired);
+    }
+
+    if body.contains(AO3_ADULT_CONTENT) {
+        return Err(Ao3Error::AdultContent);
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+    no_redirect: Client,
+    scheduler: Arc<Scheduler>,
+    cache: Arc<HttpCache>,
+    fixtures: Option<Arc<Fixtures>>,
+    base_url: Url,
+    // Shared with the clones handed to the fetch workers, which may log in again.
+    logged_in: Arc<AtomicBool>,
+    // Held while logging in again, for the workers to do it only once.
+    relogin: Arc<Mutex<()>>,
+    cookie_set: bool,
+    cookies: Arc<Jar>,
+    credentials: Credentials
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Credentials {
+    username: Option<String>,
+    password: Option<String>
+}
+
+impl Default for Credentials {
+    fn default() -> Self {
+        Credentials {
+            username: Some(String::new()),
+            password: Some(String::new())
+        }
+    }
+}
+
+pub fn update_session(context: &mut Context) {
+    if context.settings.ao3.remember_me {
+        match context.client.cookies.cookies(&context.settings.ao3.base_url) {
+            Some(cookie_str) => {
+                context.settings.ao3.secrets.login_cookie = Some(cookie_str.to_str().unwrap().to_string())
+            }
+            None => println!("No cookies available"),
+        }
+    }
+    save_secrets(context);
+}
+
+pub fn clear_session(context: &mut Context) {
+    context.settings.ao3.secrets.login_cookie = None;
+    save_secrets(context);
+}
+
+fn save_secrets(context: &Context) {
+    context.settings.ao3.save_secrets()
+           .map_err(|e| eprintln!("Can't save secrets: {:#}.", e))
+           .ok();
+}
+
+impl HttpClient {
+    pub fn new(settings: &mut Settings) -> HttpClient {
+        let cookie_jar = Jar::default();
+        let mut cookie_set = false;
+
+        let url = settings.ao3.base_url.clone();
+
+        if settings.ao3.remember_me {
+            if let Some(cookie) = settings.ao3.secrets.login_cookie.as_ref() {
+                cookie_jar.add_cookie_str(cookie, &url);
+                cookie_jar.add_cookie_str("user_credentials=1; path=/;", &url);
+                cookie_set = true;
+            }
+        }
+        let cookies = Arc::new(cookie_jar);
+        let client = Client::builder()
+            .cookie_provider(cookies.clone())
+            .cookie_store(true)
+            .build()
+            .unwrap();
+        let no_redirect = Client::builder()
+            .cookie_provider(cookies.clone())
+            .redirect(Policy::none())
+            .build()
+            .unwrap();
+
+        HttpClient {
+            client,
+            no_redirect,
+            scheduler: Arc::new(Scheduler::new()),
+            cache: Arc::new(HttpCache::new(settings.ao3.cache_path.clone(), settings.ao3.cache_max_size)),
+            fixtures: match settings.ao3.fixture_mode {
+                FixtureMode::Off => None,
+                mode => Some(Arc::new(Fixtures::new(mode, settings.ao3.fixtures_path.clone()))),
+            },
+            base_url: url,
+            logged_in: Arc::new(AtomicBool::new(false)),
+            relogin: Arc::new(Mutex::new(())),
+            cookie_set,
+            cookies,
+            credentials: Credentials {
+                username: settings.ao3.username.clone(),
+                password: settings.ao3.secrets.password.clone()
+            }
+        }
+    }
+
+    // Sends the request once the host's rate limit allows it, retrying
+    // with backoff when AO3 asks us to slow down.
+    pub fn send(&self, request: RequestBuilder) -> Result<Response, Ao3Error> {
+        let (method, url) = request.try_clone()
+                                   .and_then(|req| req.build().ok())
+                                   .map(|req| (req.method().clone(), req.url().clone()))
+                                   .ok_or_else(|| Ao3Error::Network("the request can't be sent".to_string()))?;
+        let host = url.host_str().unwrap_or_default().to_string();
+        let mut attempt = 0;
+
+        loop {
+            if self.fixture_mode() != FixtureMode::Replay {
+                self.scheduler.acquire(&host).map_err(rate_limited)?;
+            }
+            let res = self.execute(&request, &method, &url)?;
+
+            if !is_throttled(res.status()) {
+                return Ok(res);
+            }
+
+            let delay = res.headers().get(RETRY_AFTER)
+                           .and_then(|value| value.to_str().ok())
+                           .and_then(|value| parse_retry_after(value, Utc::now()))
+                           .unwrap_or_else(|| self.scheduler.backoff(attempt));
+            self.scheduler.pause(&host, delay);
+            attempt += 1;
+
+            if attempt >= MAX_ATTEMPTS || delay > MAX_INLINE_WAIT {
+                return Err(rate_limited(delay));
+            }
+        }
+    }
+
+    fn fixture_mode(&self) -> FixtureMode {
+        self.fixtures.as_ref().map_or(FixtureMode::Off, |fixtures| fixtures.mode())
+    }
+
+    fn execute(&self, request: &RequestBuilder, method: &reqwest::Method, url: &Url) -> Result<Response, Ao3Error> {
+        let fixtures = match self.fixtures.as_ref() {
+            Some(fixtures) if fixtures.mode() == FixtureMode::Replay => {
+                let res = fixtures.replay(method, url)?;
+                // There's no real exchange for the jar to pick cookies from.
+                for cookie in res.headers().get_all(SET_COOKIE) {
+                    if let Ok(cookie) = cookie.to_str() {
+                        self.cookies.add_cookie_str(cookie, res.url());
+                    }
+                }
+                return Ok(res);
+            },
+            fixtures => fixtures,
+        };
+
+        let res = request.try_clone()
+                         .ok_or_else(|| Ao3Error::Network("the request can't be sent".to_string()))?
+                         .send()?;
+
+        match fixtures {
+            Some(fixtures) => fixtures.record(method, url, res),
+            None => Ok(res),
+        }
+    }
+
+    // Reads the body in chunks, stopping early if `on_progress` returns false.
+    fn read_body(mut res: Response, on_progress: &mut dyn FnMut(usize, Option<u64>) -> bool) -> Result<String, Ao3Error> {
+        let total = res.content_length();
+        let mut body = Vec::new();
+        let mut buf = [0u8; 16 * 1024];
+
+        loop {
+            let n = res.read(&mut buf).map_err(|e| Ao3Error::Network(e.to_string()))?;
+            if n == 0 {
+                break;
+            }
+            body.extend_from_slice(&buf[..n]);
+            if !on_progress(body.len(), total) {
+                return Err(Ao3Error::Cancelled);
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    pub fn logged_in(&self) -> bool {
+        self.logged_in.load(Ordering::Relaxed)
+    }
+
+    // Pages differ between accounts: forms, tokens and bookmarks.
+    fn cache_account(&self) -> Option<&str> {
+        if self.logged_in() {
+            Some(self.credentials.username.as_deref().unwrap_or_default())
+        } else {
+            None
+        }
+    }
+
+    fn set_logged_in(&self, logged_in: bool) {
+        self.logged_in.store(logged_in, Ordering::Relaxed);
+    }
+
+    // Logs in again, once, when AO3 forgot about us in the middle of a session.
+    fn fetch(&self, url: &str, freshness: Freshness, on_progress: &mut dyn FnMut(usize, Option<u64>) -> bool) -> Result<String, Ao3Error> {
+        match self.fetch_once(url, freshness, on_progress) {
+            Err(Ao3Error::SessionExpired) if self.log_in_again() => self.fetch_once(url, freshness, on_progress),
+            res => res,
+        }
+    }
+
+    fn fetch_once(&self, url: &str, freshness: Freshness, on_progress: &mut dyn FnMut(usize, Option<u64>) -> bool) -> Result<String, Ao3Error> {
+        let logged_in = self.logged_in();
+        let account = self.cache_account();
+        let max_age = freshness.max_age();
+        let cached = max_age.and_then(|_| self.cache.get(url, account));
+
+        if let Some((entry, body)) = cached.as_ref() {
+            if entry.is_fresh_for(freshness) {
+                return Ok(body.clone());
+            }
+        }
+
+        let mut request = self.client
+            .get(url)
+            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
+            .header("Accept-Language", "en-US,en;q=0.9")
+            .header("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+            .header("Referer", self.base_url.as_str());
+
+        if let Some((entry, _)) = cached.as_ref() {
+            if let Some(etag) = entry.etag.as_ref() {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = entry.last_modified.as_ref() {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let res = match self.send(request) {
+            Ok(res) => res,
+            // Stale pages are better than nothing while offline.
+            Err(e @ Ao3Error::Network(_)) | Err(e @ Ao3Error::Timeout) => {
+                return cached.map(|(_, body)| body).ok_or(e);
+            },
+            Err(e) => return Err(e),
+        };
+
+        if res.status() == StatusCode::NOT_MODIFIED {
+            if let Some((_, body)) = cached {
+                self.cache.touch(url, account);
+                return Ok(body);
+            }
+        }
+
+        let status = res.status();
+        let final_url = res.url().clone();
+        let header = |name| res.headers().get(name)
+                               .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+                               .map(String::from);
+        let entry = CacheEntry::new(url, header(ETAG), header(LAST_MODIFIED));
+        let text = HttpClient::read_body(res, on_progress)?;
+
+        if logged_in && is_session_expired(&final_url, &text) {
+            self.set_logged_in(false);
+            return Err(Ao3Error::SessionExpired);
+        }
+
+        check_page(status, &final_url, &text)?;
+
+        if max_age.is_some() {
+            self.cache.insert(&entry, account, &text);
+        }
+
+        Ok(text)
+    }
+
+    pub fn get_parse(&self, url: &str, freshness: Freshness) -> Result<Html, Ao3Error> {
+        self.fetch(url, freshness, &mut |_, _| true).map(|text| Html::parse_document(&text))
+    }
+
+    pub fn invalidate(&self, url: &str) {
+        self.cache.remove(url, self.cache_account());
+    }
+
+    // The pages of a work show its kudos, bookmark and comments: they're
+    // fetched again once one of those changed.
+    fn invalidate_work(&self, work_id: &str) {
+        let paths = [
+            format!("/works/{}?view_adult=true", work_id),
+            format!("/works/{}?view_full_work=true&view_adult=true", work_id),
+            comments::comments_path(&format!("/works/{}", work_id)),
+        ];
+        for path in paths {
+            self.invalidate(self.url(&path).as_str());
+        }
+    }
+
+    pub fn url(&self, path: &str) -> Url {
+        get_url(&self.base_url, path)
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn get_html(&self, url: &str, freshness: Freshness) -> Result<String, Ao3Error> {
+        self.fetch(url, freshness, &mut |_, _| true)
+    }
+
+    pub fn get_html_with_progress(&self, url: &str, freshness: Freshness, on_progress: &mut dyn FnMut(usize, Option<u64>) -> bool) -> Result<String, Ao3Error> {
+        self.fetch(url, freshness, on_progress)
+    }
+
+    // Returns the works index to load for `url`, following AO3's
+    // redirects to the canonical tag when tags have been synned.
+    pub fn resolve_index(&self, url: &str) -> Result<String, Ao3Error> {
+        let res = self.send(self.no_redirect.get(self.url(url).as_str()))?;
+        match res.status() {
+            StatusCode::FOUND | StatusCode::MOVED_PERMANENTLY => {
+                res.headers().get(LOCATION)
+                   .and_then(|loc| loc.to_str().ok())
+                   .filter(|loc| loc.ends_with("/works"))
+                   .map(String::from)
+                   .ok_or(Ao3Error::Unwrangled)
+            },
+            status => {
+                let final_url = res.url().clone();
+                let text = res.text()?;
+                check_page(status, &final_url, &text)?;
+                Ok(url.to_string())
+            }
+        }
+    }
+
+    // Returns the messages to show: AO3 answers with the reasons why the
+    // kudos weren't saved, e.g. when they've already been left.
+    pub fn leave_kudos(&self, work_id: &str) -> Result<Vec<String>, Ao3Error> {
+        let token = self.csrf_token()?;
+        let params = [
+            ("authenticity_token", token.as_str()),
+            ("kudo[commentable_id]", work_id),
+            ("kudo[commentable_type]", "Work"),
+        ];
+        let res = self.send(self.post(self.url(AO3_KUDOS).as_str()).form(&params))?;
+
+        match res.status() {
+            StatusCode::CREATED => {
+                self.invalidate_work(work_id);
+                Ok(vec![AO3_KUDOS_THANKS.to_string()])
+            },
+            StatusCode::UNPROCESSABLE_ENTITY => {
+                let data = res.json::<KudosRes>().map_err(|_| Ao3Error::Parse("errors".to_string()))?;
+                Ok(data.errors.values().flat_map(|val| match val {
+                    Value::Array(items) => items.iter().map(kudos_message).collect(),
+                    _ => vec![kudos_message(val)],
+                }).collect())
+            },
+            status => Err(Ao3Error::Status(status)),
+        }
+    }
+
+    // AO3 hands out CSRF tokens for its cached pages. Actions ask for one
+    // when they're sent, as the session might have changed since they were
+    // requested.
+    fn csrf_token(&self) -> Result<String, Ao3Error> {
+        let res = self.send(self.get(self.url(AO3_TOKEN_DISPENSER).as_str()))?;
+        if !res.status().is_success() {
+            return Err(Ao3Error::Status(res.status()));
+        }
+        res.json::<TokenRes>()
+           .map(|data| data.token)
+           .map_err(|_| Ao3Error::Parse("token".to_string()))
+    }
+
+    fn mark_work(&self, work_id: &str, action: &str, default_notice: &str) -> Result<Vec<String>, Ao3Error> {
+        let url = self.url(&format!("/works/{}/{}", work_id, action));
+        let notices = self.submit(url.as_str(), &[("_method", "patch")], default_notice)?;
+        confirm(notices, default_notice)
+    }
+
+    pub fn mark_for_later(&self, work_id: &str) -> Result<Vec<String>, Ao3Error> {
+        self.mark_work(work_id, "mark_for_later", AO3_MARKED_FOR_LATER)
+    }
+
+    pub fn mark_as_read(&self, work_id: &str) -> Result<Vec<String>, Ao3Error> {
+        self.mark_work(work_id, "mark_as_read", AO3_MARKED_AS_READ)
+    }
+
+    pub fn bookmark_work(&self, work_id: &str, form: &BookmarkForm) -> Result<Vec<String>, Ao3Error> {
+        let url = self.url(&format!("/works/{}/bookmarks", work_id));
+        let notices = self.submit(url.as_str(), &bookmark_params(None, form), AO3_BOOKMARKED)?;
+        let notices = confirm(notices, AO3_BOOKMARKED)?;
+        self.invalidate_work(work_id);
+        Ok(notices)
+    }
+
+    pub fn update_bookmark(&self, work_id: &str, bookmark_id: &str, form: &BookmarkForm) -> Result<Vec<String>, Ao3Error> {
+        let url = self.url(&format!("/bookmarks/{}", bookmark_id));
+        let notices = self.submit(url.as_str(), &bookmark_params(Some("patch"), form), AO3_BOOKMARK_UPDATED)?;
+        let notices = confirm(notices, AO3_BOOKMARK_UPDATED)?;
+        self.invalidate_work(work_id);
+        Ok(notices)
+    }
+
+    pub fn delete_bookmark(&self, work_id: &str, bookmark_id: &str) -> Result<Vec<String>, Ao3Error> {
+        let url = self.url(&format!("/bookmarks/{}", bookmark_id));
+        let notices = self.submit(url.as_str(), &[("_method", "delete")], AO3_BOOKMARK_DELETED)?;
+        let notices = confirm(notices, AO3_BOOKMARK_DELETED)?;
+        self.invalidate_work(work_id);
+        Ok(notices)
+    }
+
+    // Sends the subscribe button of the page of a work, series or user, unless
+    // it's already in the state we want.
+    pub fn set_subscription(&self, path: &str, subscribe: bool) -> Result<Vec<String>, Ao3Error> {
+        let notice = if subscribe { AO3_SUBSCRIBED } else { AO3_UNSUBSCRIBED };
+        let page_path = if path.starts_with("/works/") {
+            format!("{}?view_adult=true", path)
+        } else {
+            path.to_string()
+        };
+        let frag = self.get_parse(self.url(&page_path).as_str(), Freshness::NoStore)?;
+        let form = subscriptions::scrape_subscription_form(&frag)
+                                 .ok_or_else(|| Ao3Error::Parse("subscribe button".to_string()))?;
+        if form.is_subscribed() == subscribe {
+            return Ok(vec![notice.to_string()]);
+        }
+        let url = self.url(&form.action);
+        let params: Vec<(&str, &str)> = form.params.iter()
+                                            .map(|(name, value)| (name.as_str(), value.as_str()))
+                                            .collect();
+        self.submit(url.as_str(), &params, notice)
+    }
+
+    // The comment form is shown again with the reasons why the comment
+    // wasn't posted.
+    pub fn post_comment(&self, work_id: &str, path: &str, form: &CommentForm) -> Result<Vec<String>, Ao3Error> {
+        let mut params = vec![
+            ("comment[comment_content]", form.content.as_str()),
+        ];
+        if form.pseud_id.is_empty() {
+            params.push(("comment[name]", &form.name));
+            params.push(("comment[email]", &form.email));
+        } else {
+            params.push(("comment[pseud_id]", &form.pseud_id));
+        }
+        let url = self.url(path);
+        let notices = self.submit(url.as_str(), &params, AO3_COMMENTED)?;
+        let notices = confirm(notices, AO3_COMMENTED)?;
+        self.invalidate_work(work_id);
+        Ok(notices)
+    }
+
+    // Logs in again, once, when AO3 forgot about us, and sends the form
+    // with a token of the new session.
+    fn submit(&self, url: &str, params: &[(&str, &str)], default_notice: &str) -> Result<Vec<String>, Ao3Error> {
+        match self.submit_once(url, params, default_notice) {
+            Err(Ao3Error::SessionExpired) if self.log_in_again() => self.submit_once(url, params, default_notice),
+            res => res,
+        }
+    }
+
+    // Sends a form with a fresh CSRF token and returns the notices AO3
+    // answered with.
+    fn submit_once(&self, url: &str, params: &[(&str, &str)], default_notice: &str) -> Result<Vec<String>, Ao3Error> {
+        let token = self.csrf_token()?;
+        let mut form = vec![("authenticity_token", token.as_str())];
+        form.extend(params.iter().filter(|(name, _)| *name != "authenticity_token"));
+        let res = self.send(self.post(url).form(&form))?;
+        let status = res.status();
+        let final_url = res.url().clone();
+        let text = res.text()?;
+
+        if self.logged_in() && is_session_expired(&final_url, &text) {
+            self.set_logged_in(false);
+            return Err(Ao3Error::SessionExpired);
+        }
+
+        check_page(status, &final_url, &text)?;
+
+        let notices = html::scrape_flash(&Html::parse_document(&text));
+        if notices.is_empty() {
+            Ok(vec![default_notice.to_string()])
+        } else {
+            Ok(notices)
+        }
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    pub fn are_login_cookies_stale(&self) -> bool {
+        if !self.cookie_set { return true; }
+
+        let res = self.send(self.get(self.base_url.as_str()));
+        !self.is_logged_in(res)
+    }
+
+    pub fn is_logged_in(&self, res: Result<Response, Ao3Error>) -> bool {
+        let mut logged_in = self.cookie_set;
+        match res {
+            Ok(r) => {
+                let text = r.text();
+                match text {
+                    Ok(t) => {
+                        logged_in = !t.contains(AO3_FAILED_LOGIN) &&
+                                    (t.contains(AO3_SUCCESS_LOGIN) || t.contains(AO3_ALREADY_LOGIN) || t.contains(AO3_LOGGED_IN));
+                    }
+                    Err(e) => {
+                        println!("There was an error logging in: {}", e);
+                        logged_in = false;
+                    }
+                };
+            }
+            Err(e) => {
+                println!("{}", e)
+            }
+        };
+        logged_in
+    }
+
+    pub fn login(&self, user: &str, password: &str) -> Result<(), Ao3Error> {
+        self.set_logged_in(false);
+        let login_url = self.url(AO3_LOGIN);
+        let html = self.get_parse(login_url.as_str(), Freshness::NoStore)?;
+        let token = scrape_login_csrf(&html)?;
+        let params = [
+            ("user[login]", user),
+            ("user[password]", password),
+            ("user[remember_me]", "1"),
+            ("authenticity_token", &token),
+        ];
+
+        let res = self.send(self.client.post(login_url).form(&params));
+        self.set_logged_in(self.is_logged_in(res));
+
+        if self.logged_in() {
+            Ok(())
+        } else {
+            Err(Ao3Error::LoginFailed)
+        }
+    }
+
+    pub fn renew_login(&self) -> Result<(), Ao3Error> {
+        if self.are_login_cookies_stale() {
+            if let (Some(username), Some(password)) =
+                (self.credentials.username.clone(), self.credentials.password.clone()) {
+                return self.login(&username, &password);
+            }
+        } else {
+            self.set_logged_in(true);
+        }
+        Ok(())
+    }
+
+    fn log_in_again(&self) -> bool {
+        let _relogin = self.relogin.lock().unwrap();
+        // Another worker may have done it while we were waiting.
+        if self.logged_in() {
+            return true;
+        }
+        match (self.credentials.username.as_deref(), self.credentials.password.as_deref()) {
+            (Some(username), Some(password)) => {
+                self.clear_cookies();
+                self.login(username, password).is_ok()
+            },
+            _ => false,
+        }
+    }
+
+    pub fn logout(&self) -> Result<(), Ao3Error> {
+        let html = self.get_parse(self.base_url.as_str(), Freshness::NoStore)?;
+        // Without a logout form, AO3 already considers us logged out.
+        if let Some(token) = scrape_logout_csrf(&html) {
+            let params = [
+                ("_method", "delete"),
+                ("authenticity_token", token),
+            ];
+            let res = self.send(self.post(self.url(AO3_LOGOUT).as_str()).form(&params))?;
+            let status = res.status();
+            let final_url = res.url().clone();
+            let text = res.text()?;
+            check_page(status, &final_url, &text)?;
+        }
+        self.clear_cookies();
+        self.set_logged_in(false);
+        Ok(())
+    }
+
+    // The jar can't be emptied, but expired cookies replace the current ones.
+    fn clear_cookies(&self) {
+        let Some(header) = self.cookies.cookies(&self.base_url) else {
+            return;
+        };
+        for name in header.to_str().unwrap_or_default().split(';').filter_map(|c| c.split('=').next()) {
+            self.cookies.add_cookie_str(&format!("{}=; Max-Age=0; path=/", name.trim()), &self.base_url);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use reqwest::Url;
+    use crate::view::works::{IndexType, HistoryView};
+    use crate::view::works::workindex::fetch_index_data;
+
+    const FIXTURES_PATH: &str = "fixtures/http";
+    const FIXTURE_USERNAME: &str = "reader";
+    const FIXTURE_PASSWORD: &str = "hunter2";
+
+    // Settings replaying the exchanges recorded in `fixtures/http/<scenario>`.
+    fn replay_settings(scenario: &str) -> Settings {
+        let mut settings: Settings = Default::default();
+        settings.ao3.fixture_mode = FixtureMode::Replay;
+        settings.ao3.fixtures_path = Path::new(FIXTURES_PATH).join(scenario);
+        settings.ao3.cache_path = env::temp_dir().join(format!("ao3reader-fixtures-{}", scenario));
+        fs::remove_dir_all(&settings.ao3.cache_path).ok();
+        settings
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn WHEN_httpClientIsCreated_THEN_itWillStoreCookies() {
+        // WHEN HttpClient is created
+        let mut settings: Settings = Default::default();
+        let client = HttpClient::new(&mut settings);
+
+        // THEN it will store cookies
+        let url = settings.ao3.base_url.clone();
+
+        // Note: when adding custom cookies, they must include a path
+        client.cookies.add_cookie_str("fakeTestCookie=unittest; path=/;", &url);
+        assert!(client.cookies.cookies(&url).unwrap().to_str().expect("test cookie").to_string().contains("fakeTestCookie=unittest"));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_settingsSetToRememberLogin_WHEN_httpClientIsCreated_THEN_itWillHaveAo3Cookies() {
+        // GIVEN Settings set to remember login
+        let mut settings: Settings = Default::default();
+        settings.ao3.remember_me = true;
+        settings.ao3.secrets.login_cookie = Some("fakeTestCookie=unittest".to_string());
+
+        // WHEN HttpClient is created
+        let client = HttpClient::new(&mut settings);
+
+        // THEN it will have Ao3 cookies
+        let url = settings.ao3.base_url.clone();
+        assert!(client.cookies.cookies(&url).unwrap().to_str().expect("test cookie").to_string().contains("fakeTestCookie=unittest"));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn WHEN_loginIsCalledWithValidLogin_THEN_clientWillBeLoggedIn() {
+        // GIVEN remember_me is not set
+        let mut settings = replay_settings("login");
+        let client = HttpClient::new(&mut settings);
+
+        client.login(FIXTURE_USERNAME, FIXTURE_PASSWORD).ok();
+
+        // THEN client will be logged in
+        assert!(client.logged_in());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_rememberMeIsNotSet_WHEN_areLoginCookiesStaleIsCalled_THEN_cookiesWillBeStale() {
+        // GIVEN remember_me is not set
+        let mut settings: Settings = Default::default();
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN are_login_cookies_stale is called
+        let stale = client.are_login_cookies_stale();
+
+        // THEN cookies will be stale
+        assert!(stale);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_firstCookieCreation_WHEN_areLoginCookiesStaleIsCalled_THEN_cookiesWillBeStale() {
+        // GIVEN first cookie creation
+        let mut settings: Settings = Default::default();
+        settings.ao3.remember_me = true;
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN are_login_cookies_stale is called
+        let stale = client.are_login_cookies_stale();
+
+        // THEN cookies will not be stale
+        assert!(stale);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_recentLogin_WHEN_areLoginCookiesStaleIsCalled_THEN_cookiesWillNotBeStale() {
+        // GIVEN recent login
+        let mut settings = replay_settings("login");
+        settings.ao3.remember_me = true;
+        let cookieCollector = HttpClient::new(&mut settings);
+        cookieCollector.login(FIXTURE_USERNAME, FIXTURE_PASSWORD).ok();
+
+        let url = settings.ao3.base_url.clone();
+        let login_cookie_header = cookieCollector.cookies.cookies(&url).unwrap();
+        let login_cookie = login_cookie_header.to_str().expect("login cookie");
+
+        settings.ao3.secrets.login_cookie = Some(login_cookie.to_string());
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN are_login_cookies_stale is called
+        let stale = client.are_login_cookies_stale();
+
+        // THEN cookies will not be stale
+        assert!(stale);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_noUsername_WHEN_renewLoginIsCalled_THEN_clientWillNotBeLoggedIn() {
+        // GIVEN no username
+        let mut settings: Settings = Default::default();
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN renew_login is called
+        client.renew_login().ok();
+
+        // THEN client will not be logged in
+        assert!(!client.logged_in());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_validUsernameAndPassword_WHEN_renewLoginIsCalled_THEN_clientWillBeLoggedIn() {
+        // GIVEN a valid username and password
+        let mut settings = replay_settings("login");
+        settings.ao3.username = Some(FIXTURE_USERNAME.to_string());
+        settings.ao3.secrets.password = Some(FIXTURE_PASSWORD.to_string());
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN renew_login is called
+        client.renew_login().ok();
+
+        // THEN client will be logged in
+        assert!(client.logged_in());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_retryLaterPage_WHEN_checkPageIsCalled_THEN_itWillAskToRetryLater() {
+        // GIVEN a "Retry later" page
+        let url = "https://archiveofourown.org/".parse::<Url>().unwrap();
+
+        // WHEN check_page is called
+        let too_many = check_page(StatusCode::TOO_MANY_REQUESTS, &url, "Retry later");
+        let ok_status = check_page(StatusCode::OK, &url, "Retry later\n");
+
+        // THEN it will ask to retry later
+        assert!(matches!(too_many, Err(Ao3Error::RetryLater)));
+        assert!(matches!(ok_status, Err(Ao3Error::RetryLater)));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_restrictedWorkRedirect_WHEN_checkPageIsCalled_THEN_loginWillBeRequired() {
+        // GIVEN a restricted work redirecting to the login form
+        let url = "https://archiveofourown.org/users/login?restricted=true".parse::<Url>().unwrap();
+
+        // WHEN check_page is called
+        let res = check_page(StatusCode::OK, &url, "<html></html>");
+
+        // THEN login will be required
+        assert!(matches!(res, Err(Ao3Error::LoginRequired)));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_adultContentGate_WHEN_checkPageIsCalled_THEN_itWillBeReported() {
+        // GIVEN the adult content gate
+        let url = "https://archiveofourown.org/works/1".parse::<Url>().unwrap();
+        let body = "<p class=\"caution\">This work could have adult content. If you proceed you have agreed that you are willing to see such content.</p>";
+
+        // WHEN check_page is called
+        let res = check_page(StatusCode::OK, &url, body);
+
+        // THEN it will be reported
+        assert!(matches!(res, Err(Ao3Error::AdultContent)));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_missingOrFailingPages_WHEN_checkPageIsCalled_THEN_statusWillBeReported() {
+        // GIVEN missing or failing pages
+        let url = "https://archiveofourown.org/works/1".parse::<Url>().unwrap();
+
+        // WHEN check_page is called
+        let not_found = check_page(StatusCode::NOT_FOUND, &url, "");
+        let server_error = check_page(StatusCode::INTERNAL_SERVER_ERROR, &url, "");
+        let work = check_page(StatusCode::OK, &url, "<div id=\"workskin\"></div>");
+
+        // THEN the status will be reported
+        assert!(matches!(not_found, Err(Ao3Error::NotFound)));
+        assert!(matches!(server_error, Err(Ao3Error::Status(StatusCode::INTERNAL_SERVER_ERROR))));
+        assert!(work.is_ok());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_replayedLogin_WHEN_loginIsCalled_THEN_sessionCookiesWillBeSet() {
+        // GIVEN a replayed login
+        let mut settings = replay_settings("login");
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN login is called
+        client.login(FIXTURE_USERNAME, FIXTURE_PASSWORD).ok();
+
+        // THEN session cookies will be set
+        let cookies = client.cookies.cookies(&settings.ao3.base_url).expect("session cookies");
+        assert!(cookies.to_str().unwrap().contains("_otwarchive_session=fixture-session"));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_newKudos_WHEN_leaveKudosIsCalled_THEN_theUserWillBeThanked() {
+        // GIVEN new kudos
+        let mut settings = replay_settings("kudos");
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN leave_kudos is called
+        let messages = client.leave_kudos("1");
+
+        // THEN the user will be thanked
+        assert_eq!(messages.unwrap(), vec![AO3_KUDOS_THANKS.to_string()]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aCachedWork_WHEN_leaveKudosIsCalled_THEN_itsPagesWillBeFetchedAgain() {
+        // GIVEN a cached work
+        let mut settings = replay_settings("kudos");
+        let client = HttpClient::new(&mut settings);
+        let url = client.url("/works/1?view_full_work=true&view_adult=true").to_string();
+        client.cache.insert(&CacheEntry::new(&url, None, None), None, "<html></html>");
+
+        // WHEN leave_kudos is called
+        client.leave_kudos("1").unwrap();
+
+        // THEN its pages will be fetched again
+        assert!(client.cache.get(&url, None).is_none());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_kudosAlreadyLeft_WHEN_leaveKudosIsCalled_THEN_ao3sReasonWillBeReturned() {
+        // GIVEN kudos already left
+        let mut settings = replay_settings("kudos-again");
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN leave_kudos is called
+        let messages = client.leave_kudos("1");
+
+        // THEN AO3's reason will be returned
+        assert_eq!(messages.unwrap(), vec!["You have already left kudos here. :)".to_string()]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aWorksListWithoutToken_WHEN_markForLaterIsCalled_THEN_aDispensedTokenWillBeSent() {
+        // GIVEN a works list without token
+        let mut settings = replay_settings("mark-for-later");
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN mark_for_later is called
+        let messages = client.mark_for_later("1");
+
+        // THEN a dispensed token will be sent
+        assert_eq!(messages.unwrap(), vec![AO3_MARKED_FOR_LATER.to_string()]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_anUnsubscribedWork_WHEN_setSubscriptionIsCalled_THEN_itsSubscribeButtonWillBeSent() {
+        // GIVEN an unsubscribed work
+        let mut settings = replay_settings("subscribe");
+        let client = HttpClient::new(&mut settings);
+
+        // WHEN set_subscription is called
+        let messages = client.set_subscription("/works/1", true).unwrap();
+
+        // THEN its subscribe button will be sent
+        assert!(messages[0].starts_with("You are now following Shore Leave."));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_historyWithoutWorksCount_WHEN_fetchIndexDataIsCalled_THEN_theLastPageWillBeFetched() {
+        // GIVEN a history without works count
+        let mut settings = replay_settings("history");
+        let client = HttpClient::new(&mut settings);
+        let url = client.url("/users/reader/readings").to_string();
+
+        // WHEN fetch_index_data is called
+        let data = fetch_index_data(&url, IndexType::History(HistoryView::Full), &client).unwrap();
+
+        // THEN the last page will be fetched
+        assert_eq!(data.internal_max, 3);
+        assert_eq!(data.first_page.works.len(), 3);
+        assert_eq!(data.max_works, None);
+        assert_eq!(data.last_page_len, 2);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_replayedWork_WHEN_getHtmlIsCalled_THEN_itWillBeServedWithoutNetwork() {
+        // GIVEN a replayed work
+        let mut settings = replay_settings("work");
+        let client = HttpClient::new(&mut settings);
+        let url = client.url("/works/1?view_full_work=true&view_adult=true").to_string();
+
+        // WHEN get_html is called
+        let work = client.get_html(&url, Freshness::Work);
+        let missing = client.get_html(client.url("/works/2").as_str(), Freshness::Work);
+
+        // THEN it will be served without network
+        assert!(work.unwrap().contains("<div id=\"workskin\">"));
+        assert!(matches!(missing, Err(Ao3Error::Network(_))));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_recordMode_WHEN_aPageIsFetched_THEN_itCanBeReplayed() {
+        // GIVEN record mode
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let body = "<div id=\"workskin\">Recorded</div>";
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                   body.len(), body).unwrap();
+        });
+
+        let mut settings = replay_settings("record");
+        settings.ao3.base_url = base_url;
+        settings.ao3.fixture_mode = FixtureMode::Record;
+        settings.ao3.fixtures_path = env::temp_dir().join("ao3reader-fixtures-recorded");
+        fs::remove_dir_all(&settings.ao3.fixtures_path).ok();
+        let recorder = HttpClient::new(&mut settings);
+        let url = recorder.url("/works/1").to_string();
+
+        // WHEN a page is fetched
+        let recorded = recorder.get_html(&url, Freshness::NoStore).unwrap();
+
+        // THEN it can be replayed
+        settings.ao3.fixture_mode = FixtureMode::Replay;
+        let player = HttpClient::new(&mut settings);
+        assert_eq!(player.get_html(&url, Freshness::NoStore).unwrap(), recorded);
+        assert_eq!(recorded, "<div id=\"workskin\">Recorded</div>");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_loggedInClient_WHEN_logoutIsCalled_THEN_theSessionWillBeCleared() {
+        // GIVEN a logged in client
+        let mut settings = replay_settings("session");
+        let client = HttpClient::new(&mut settings);
+        client.login(FIXTURE_USERNAME, FIXTURE_PASSWORD).unwrap();
+
+        // WHEN logout is called
+        let res = client.logout();
+
+        // THEN the session will be cleared
+        assert!(res.is_ok());
+        assert!(!client.logged_in());
+        let cookies = client.cookies.cookies(&settings.ao3.base_url);
+        assert!(!cookies.is_some_and(|c| c.to_str().unwrap().contains("_otwarchive_session")));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_expiredSession_WHEN_getHtmlIsCalled_THEN_theClientWillLogInAgain() {
+        // GIVEN an expired session
+        let mut settings = replay_settings("session");
+        settings.ao3.username = Some(FIXTURE_USERNAME.to_string());
+        settings.ao3.secrets.password = Some(FIXTURE_PASSWORD.to_string());
+        let client = HttpClient::new(&mut settings);
+        client.login(FIXTURE_USERNAME, FIXTURE_PASSWORD).unwrap();
+
+        // WHEN get_html is called
+        let work = client.get_html(client.url("/works/1").as_str(), Freshness::NoStore);
+
+        // THEN the client will log in again
+        assert!(work.unwrap().contains("Members only."));
+        assert!(client.logged_in());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_expiredSession_WHEN_markForLaterIsCalled_THEN_theClientWillLogInAgain() {
+        // GIVEN an expired session
+        let mut settings = replay_settings("session");
+        settings.ao3.username = Some(FIXTURE_USERNAME.to_string());
+        settings.ao3.secrets.password = Some(FIXTURE_PASSWORD.to_string());
+        let client = HttpClient::new(&mut settings);
+        client.login(FIXTURE_USERNAME, FIXTURE_PASSWORD).unwrap();
+
+        // WHEN mark_for_later is called
+        let messages = client.mark_for_later("1");
+
+        // THEN the client will log in again
+        assert_eq!(messages.unwrap(), vec![AO3_MARKED_FOR_LATER.to_string()]);
+        assert!(client.logged_in());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_expiredSessionWithoutCredentials_WHEN_getHtmlIsCalled_THEN_itWillBeReported() {
+        // GIVEN an expired session without credentials
+        let mut settings = replay_settings("session");
+        let client = HttpClient::new(&mut settings);
+        client.login(FIXTURE_USERNAME, FIXTURE_PASSWORD).unwrap();
+
+        // WHEN get_html is called
+        let work = client.get_html(client.url("/works/1").as_str(), Freshness::NoStore);
+
+        // THEN it will be reported
+        assert!(matches!(work, Err(Ao3Error::SessionExpired)));
+        assert!(!client.logged_in());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aWorkPage_WHEN_downloadWorkIsCalled_THEN_theChosenFormatWillBeSaved() {
+        // GIVEN a work page
+        let mut settings = replay_settings("download");
+        let client = HttpClient::new(&mut settings);
+        let dir = env::temp_dir().join("ao3reader-downloads");
+        fs::remove_dir_all(&dir).ok();
+
+        // WHEN download_work is called
+        let path = client.download_work("1", DownloadFormat::Html, &dir, &mut |_, _| true).unwrap();
+
+        // THEN the chosen format will be saved
+        assert_eq!(path, dir.join("Kirk_Spock (1).html"));
+        assert!(fs::read_to_string(&path).unwrap().contains("Once upon a time."));
+        assert!(!dir.join("Kirk_Spock (1).html.part").exists());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aPartialDownload_WHEN_downloadIsCalled_THEN_itWillBeResumed() {
+        // GIVEN a partial download
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            let mut range = None;
+            while reader.read_line(&mut line).unwrap() > 2 {
+                if line.to_lowercase().starts_with("range:") {
+                    range = Some(line[6..].trim().to_string());
+                }
+                line.clear();
+            }
+            let (status, body) = match range.as_deref() {
+                Some("bytes=7-") => ("206 Partial Content", "world!"),
+                _ => ("200 OK", "Hello, world!"),
+            };
+            write!(stream, "HTTP/1.1 {}\r\nContent-Type: application/epub+zip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                   status, body.len(), body).unwrap();
+        });
+
+        let mut settings = replay_settings("resume");
+        settings.ao3.base_url = base_url;
+        settings.ao3.fixture_mode = FixtureMode::Off;
+        let client = HttpClient::new(&mut settings);
+        let dir = env::temp_dir().join("ao3reader-downloads-resume");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Work (1).epub");
+        fs::write(dir.join("Work (1).epub.part"), "Hello, ").unwrap();
+        let mut progress = Vec::new();
+
+        // WHEN download is called
+        let res = client.download(client.url("/downloads/1/Work.epub").as_str(), &path, &mut |received, total| {
+            progress.push((received, total));
+            true
+        });
+
+        // THEN it will be resumed
+        assert!(res.is_ok());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Hello, world!");
+        assert_eq!(progress.last(), Some(&(13, Some(13))));
+    }
+}
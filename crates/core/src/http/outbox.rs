@@ -0,0 +1,287 @@
+use std::fs;
+use std::path::PathBuf;
+use chrono::{Local, NaiveDateTime};
+use serde::{Serialize, Deserialize};
+use crate::helpers::{load_json, save_json, datetime_format};
+use super::{HttpClient, Ao3Error, BookmarkForm, CommentForm};
+
+pub const OUTBOX_PATH: &str = ".outbox.json";
+
+pub type ActionResult = (u64, Result<Vec<String>, Ao3Error>);
+
+// Something to do on AO3. CSRF tokens are asked for when the action is
+// sent, since the session they belong to might be gone by then.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Ao3Action {
+    Kudos {
+        work_id: String,
+    },
+    MarkForLater {
+        work_id: String,
+    },
+    MarkAsRead {
+        work_id: String,
+    },
+    // The path of a work, series or user.
+    Subscribe {
+        path: String,
+    },
+    Unsubscribe {
+        path: String,
+    },
+    Bookmark {
+        work_id: String,
+        form: BookmarkForm,
+    },
+    EditBookmark {
+        #[serde(default)]
+        work_id: String,
+        bookmark_id: String,
+        form: BookmarkForm,
+    },
+    DeleteBookmark {
+        #[serde(default)]
+        work_id: String,
+        bookmark_id: String,
+    },
+    // Posted to a work, a chapter or a comment, for replies.
+    Comment {
+        #[serde(default)]
+        work_id: String,
+        path: String,
+        form: CommentForm,
+    },
+}
+
+impl Ao3Action {
+    pub fn run(&self, client: &HttpClient) -> Result<Vec<String>, Ao3Error> {
+        match self {
+            Ao3Action::Kudos { work_id } => client.leave_kudos(work_id),
+            Ao3Action::MarkForLater { work_id } => client.mark_for_later(work_id),
+            Ao3Action::MarkAsRead { work_id } => client.mark_as_read(work_id),
+            Ao3Action::Subscribe { path } => client.set_subscription(path, true),
+            Ao3Action::Unsubscribe { path } => client.set_subscription(path, false),
+            Ao3Action::Bookmark { work_id, form } => client.bookmark_work(work_id, form),
+            Ao3Action::EditBookmark { work_id, bookmark_id, form } => client.update_bookmark(work_id, bookmark_id, form),
+            Ao3Action::DeleteBookmark { work_id, bookmark_id } => client.delete_bookmark(work_id, bookmark_id),
+            Ao3Action::Comment { work_id, path, form } => client.post_comment(work_id, path, form),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Ao3Action::Kudos { .. } => "Kudos",
+            Ao3Action::MarkForLater { .. } => "Mark for Later",
+            Ao3Action::MarkAsRead { .. } => "Mark as Read",
+            Ao3Action::Subscribe { .. } => "Subscribe",
+            Ao3Action::Unsubscribe { .. } => "Unsubscribe",
+            Ao3Action::Bookmark { .. } | Ao3Action::EditBookmark { .. } => "Bookmark",
+            Ao3Action::DeleteBookmark { .. } => "Bookmark removal",
+            Ao3Action::Comment { .. } => "Comment",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ActionStatus {
+    Pending,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PendingAction {
+    pub id: u64,
+    pub action: Ao3Action,
+    // Title of the work, to tell the actions apart.
+    pub title: String,
+    #[serde(with = "datetime_format")]
+    pub queued: NaiveDateTime,
+    pub status: ActionStatus,
+}
+
+// AO3 actions that couldn't be sent, kept on disk until the network is back.
+pub struct Outbox {
+    path: PathBuf,
+    actions: Vec<PendingAction>,
+    replaying: bool,
+}
+
+impl Outbox {
+    pub fn load(path: PathBuf) -> Outbox {
+        let actions = if path.exists() {
+            load_json::<Vec<PendingAction>, _>(&path)
+                .map_err(|e| eprintln!("Can't load outbox: {:#}.", e))
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        Outbox { path, actions, replaying: false }
+    }
+
+    fn save(&self) {
+        if self.actions.is_empty() {
+            fs::remove_file(&self.path).ok();
+        } else if let Err(e) = save_json(&self.actions, &self.path) {
+            eprintln!("Can't save outbox: {:#}.", e);
+        }
+    }
+
+    pub fn actions(&self) -> &[PendingAction] {
+        &self.actions
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.actions.iter().filter(|pa| pa.status == ActionStatus::Pending).count()
+    }
+
+    // Returns whether the action was added: it might already be waiting.
+    pub fn push(&mut self, action: Ao3Action, title: String) -> bool {
+        if self.actions.iter().any(|pa| pa.action == action && pa.status == ActionStatus::Pending) {
+            return false;
+        }
+        let id = self.actions.iter().map(|pa| pa.id).max().unwrap_or(0) + 1;
+        self.actions.push(PendingAction {
+            id,
+            action,
+            title,
+            queued: Local::now().naive_local(),
+            status: ActionStatus::Pending,
+        });
+        self.save();
+        true
+    }
+
+    // Hands out the pending actions, unless they're already being sent.
+    pub fn start_replay(&mut self) -> Option<Vec<PendingAction>> {
+        if self.replaying || self.pending_count() == 0 {
+            return None;
+        }
+        self.replaying = true;
+        Some(self.actions.iter().filter(|pa| pa.status == ActionStatus::Pending).cloned().collect())
+    }
+
+    // Sent actions are dropped, actions that might go through later on stay
+    // pending and the others are marked as failed. Returns what to tell the user.
+    pub fn finish_replay(&mut self, results: Vec<ActionResult>) -> Vec<String> {
+        self.replaying = false;
+        let mut sent = 0;
+        let mut messages = Vec::new();
+
+        for (id, res) in results {
+            let Some(index) = self.actions.iter().position(|pa| pa.id == id) else {
+                continue;
+            };
+            match res {
+                Ok(..) => {
+                    self.actions.remove(index);
+                    sent += 1;
+                },
+                Err(e) if e.is_transient() => (),
+                Err(e) => {
+                    let pa = &mut self.actions[index];
+                    messages.push(format!("{} for {} failed: {}", pa.action.label(), pa.title, e));
+                    pa.status = ActionStatus::Failed(e.to_string());
+                },
+            }
+        }
+
+        if sent > 0 {
+            let plural = if sent > 1 { "s" } else { "" };
+            messages.insert(0, format!("Sent {} pending AO3 action{}.", sent, plural));
+        }
+
+        self.save();
+        messages
+    }
+
+    pub fn retry_failed(&mut self) {
+        for pa in &mut self.actions {
+            pa.status = ActionStatus::Pending;
+        }
+        self.save();
+    }
+
+    pub fn discard_failed(&mut self) {
+        self.actions.retain(|pa| pa.status == ActionStatus::Pending);
+        self.save();
+    }
+}
+
+// Runs the actions in order, and stops as soon as AO3 can't be reached:
+// the remaining ones wouldn't fare any better.
+pub fn replay(client: &HttpClient, actions: Vec<PendingAction>) -> Vec<ActionResult> {
+    let mut results = Vec::new();
+    for pa in actions {
+        let res = pa.action.run(client);
+        let stop = res.as_ref().is_err_and(|e| e.is_transient());
+        results.push((pa.id, res));
+        if stop {
+            break;
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn test_outbox(name: &str) -> Outbox {
+        let path = env::temp_dir().join(format!("ao3reader-outbox-{}.json", name));
+        fs::remove_file(&path).ok();
+        Outbox::load(path)
+    }
+
+    fn kudos(work_id: &str) -> Ao3Action {
+        Ao3Action::Kudos { work_id: work_id.to_string() }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_queuedActions_WHEN_theOutboxIsLoaded_THEN_theyWillStillBePending() {
+        // GIVEN queued actions
+        let mut outbox = test_outbox("reload");
+        assert!(outbox.push(kudos("1"), "First".to_string()));
+        assert!(!outbox.push(kudos("1"), "First".to_string()));
+        outbox.push(Ao3Action::MarkForLater { work_id: "2".to_string() }, "Second".to_string());
+
+        // WHEN the outbox is loaded
+        let loaded = Outbox::load(outbox.path.clone());
+
+        // THEN they will still be pending
+        assert_eq!(loaded.pending_count(), 2);
+        assert_eq!(loaded.actions()[0].action, kudos("1"));
+        assert_eq!(loaded.actions()[1].title, "Second");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aReplay_WHEN_finishReplayIsCalled_THEN_onlyTransientFailuresWillStayPending() {
+        // GIVEN a replay
+        let mut outbox = test_outbox("replay");
+        for id in ["1", "2", "3"] {
+            outbox.push(kudos(id), format!("Work {}", id));
+        }
+        let actions = outbox.start_replay().unwrap();
+        assert!(outbox.start_replay().is_none());
+
+        // WHEN finish_replay is called
+        let messages = outbox.finish_replay(vec![
+            (actions[0].id, Ok(vec!["Thank you for leaving kudos!".to_string()])),
+            (actions[1].id, Err(Ao3Error::Timeout)),
+            (actions[2].id, Err(Ao3Error::NotFound)),
+        ]);
+
+        // THEN only transient failures will stay pending
+        assert_eq!(messages[0], "Sent 1 pending AO3 action.");
+        assert!(messages[1].starts_with("Kudos for Work 3 failed"));
+        assert_eq!(outbox.actions().len(), 2);
+        assert_eq!(outbox.actions()[0].status, ActionStatus::Pending);
+        assert_eq!(outbox.actions()[1].status, ActionStatus::Failed(Ao3Error::NotFound.to_string()));
+        assert_eq!(outbox.start_replay().map(|actions| actions.len()), Some(1));
+    }
+}
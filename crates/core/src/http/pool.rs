@@ -0,0 +1,314 @@
+use std::thread;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender, Receiver};
+use scraper::Html;
+use crate::ao3_metadata::Ao3Info;
+use crate::html;
+use crate::selectors::selectors;
+use crate::view::{Event, Hub};
+use crate::view::works::IndexType;
+use crate::view::works::workindex::{fetch_index_data, fetch_pages, refetch_index, PagesRequest};
+use super::{HttpClient, Ao3Error, Freshness, DownloadFormat};
+use super::outbox::{self, Ao3Action, PendingAction};
+use super::comments;
+use super::subscriptions::{self, Subscription};
+
+const WORKERS_COUNT: usize = 2;
+// Minimum number of bytes between two progress events.
+const PROGRESS_STEP: usize = 128 * 1024;
+
+pub type FetchId = u64;
+
+type Job = Box<dyn FnOnce(&FetchContext) -> Result<Event, Ao3Error> + Send>;
+
+pub struct FetchContext {
+    pub id: FetchId,
+    pub client: HttpClient,
+    hub: Hub,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl FetchContext {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn progress(&self, text: String) {
+        if !self.is_cancelled() {
+            self.hub.send(Event::FetchProgress(self.id, text)).ok();
+        }
+    }
+}
+
+struct Task {
+    context: FetchContext,
+    job: Job,
+}
+
+// Runs network requests off the UI thread. Results are posted back
+// through the hub, tagged with the id returned by `submit`.
+pub struct FetchPool {
+    sender: Sender<Task>,
+    hub: Hub,
+    next_id: FetchId,
+    pending: Vec<(FetchId, Arc<AtomicBool>)>,
+}
+
+impl FetchPool {
+    pub fn new(hub: &Hub) -> FetchPool {
+        let (sender, receiver) = mpsc::channel::<Task>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for _ in 0..WORKERS_COUNT {
+            let receiver = receiver.clone();
+            thread::spawn(move || FetchPool::work(receiver));
+        }
+
+        FetchPool {
+            sender,
+            hub: hub.clone(),
+            next_id: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    fn work(receiver: Arc<Mutex<Receiver<Task>>>) {
+        loop {
+            let task = receiver.lock().unwrap().recv();
+            let Ok(Task { context, job }) = task else {
+                break;
+            };
+
+            if context.is_cancelled() {
+                continue;
+            }
+
+            let evt = job(&context).unwrap_or_else(|e| Event::FetchFailed(context.id, e));
+
+            if !context.is_cancelled() {
+                context.hub.send(evt).ok();
+            }
+        }
+    }
+
+    fn queue(&mut self, client: &HttpClient, job: Job) -> (FetchId, Arc<AtomicBool>) {
+        self.next_id += 1;
+        let id = self.next_id;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let context = FetchContext {
+            id,
+            client: client.clone(),
+            hub: self.hub.clone(),
+            cancelled: cancelled.clone(),
+        };
+        self.sender.send(Task { context, job }).ok();
+        (id, cancelled)
+    }
+
+    pub fn submit<F>(&mut self, client: &HttpClient, job: F) -> FetchId
+        where F: FnOnce(&FetchContext) -> Result<Event, Ao3Error> + Send + 'static {
+        let (id, cancelled) = self.queue(client, Box::new(job));
+        self.pending.push((id, cancelled));
+        id
+    }
+
+    pub fn is_pending(&self, id: FetchId) -> bool {
+        self.pending.iter().any(|(pending_id, _)| *pending_id == id)
+    }
+
+    // Returns whether the result of `id` is still wanted.
+    pub fn finish(&mut self, id: FetchId) -> bool {
+        let len = self.pending.len();
+        self.pending.retain(|(pending_id, _)| *pending_id != id);
+        self.pending.len() != len
+    }
+
+    // Returns whether anything was cancelled.
+    pub fn cancel_all(&mut self) -> bool {
+        let any = !self.pending.is_empty();
+        for (_, cancelled) in self.pending.drain(..) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+        any
+    }
+
+    // Long works with several chapters are read a chapter at a time,
+    // starting from their first page. The others are fetched whole.
+    pub fn open_work(&mut self, client: &HttpClient, work_id: String, lazy_load_words: usize) -> FetchId {
+        self.submit(client, move |ctx| {
+            let mut reported = 0;
+            let mut on_progress = |received: usize, total: Option<u64>| {
+                if received >= reported + PROGRESS_STEP {
+                    reported = received;
+                    ctx.progress(loading_text("Loading work", received, total));
+                }
+                !ctx.is_cancelled()
+            };
+            if lazy_load_words > 0 {
+                let uri = ctx.client.url(&format!("/works/{}?view_adult=true", work_id)).to_string();
+                let html = ctx.client.get_html_with_progress(&uri, Freshness::Work, &mut on_progress)?;
+                let info = Ao3Info::new_from_work(&Html::parse_document(&html), work_id.clone()).ok();
+                if info.is_none_or(|info| info.posted_chapters() < 2 || info.words > lazy_load_words) {
+                    return Ok(Event::WorkFetched(ctx.id, uri, html));
+                }
+            }
+            let uri = ctx.client.url(&format!("/works/{}?view_full_work=true&view_adult=true", work_id)).to_string();
+            let html = ctx.client.get_html_with_progress(&uri, Freshness::Work, &mut on_progress)?;
+            Ok(Event::WorkFetched(ctx.id, uri, html))
+        })
+    }
+
+    // Not tracked by `cancel_all`: the reader waits for every chapter it
+    // asked for, and ignores the chapters of other works.
+    pub fn load_chapter(&mut self, client: &HttpClient, path: String) {
+        self.queue(client, Box::new(move |ctx| {
+            let uri = ctx.client.url(&path).to_string();
+            let html = ctx.client.get_html(&uri, Freshness::Work)
+                          .map_err(|e| eprintln!("Can't fetch {}: {}", path, e))
+                          .ok();
+            Ok(Event::ChapterFetched(path, html))
+        }));
+    }
+
+    // A page of the comments of a work or chapter, laid out as HTML.
+    pub fn load_comments(&mut self, client: &HttpClient, path: String) -> FetchId {
+        self.submit(client, move |ctx| {
+            let uri = ctx.client.url(&path).to_string();
+            let html = ctx.client.get_html(&uri, Freshness::Index)?;
+            let frag = Html::parse_document(&html);
+            let title = html::scrape(&frag, &selectors().work.title)
+                              .map(|title| format!("Comments on {}", title))
+                              .unwrap_or_else(|_| "Comments".to_string());
+            let page = comments::scrape_comments(&frag, &title, &comments::post_path(&path));
+            Ok(Event::CommentsFetched(ctx.id, comments::comments_as_html(&page), page.fields))
+        })
+    }
+
+    // Tag and search indexes might redirect to a canonical tag's works.
+    pub fn load_index(&mut self, client: &HttpClient, uri: String, index_type: IndexType) -> FetchId {
+        self.submit(client, move |ctx| {
+            let index_uri = match index_type {
+                IndexType::History(..) | IndexType::Subscriptions => uri.clone(),
+                _ => ctx.client.resolve_index(&uri)?,
+            };
+            let index_type = match index_type {
+                IndexType::Search(..) if index_uri != uri => IndexType::TagWorks,
+                _ => index_type,
+            };
+            let data = fetch_index_data(&index_uri, index_type, &ctx.client)?;
+            Ok(Event::IndexFetched(ctx.id, Box::new(data)))
+        })
+    }
+
+    pub fn load_index_pages(&mut self, client: &HttpClient, request: PagesRequest) -> FetchId {
+        self.submit(client, move |ctx| {
+            let fetched = fetch_pages(&request, &ctx.client)?;
+            Ok(Event::IndexPagesFetched(ctx.id, Box::new(fetched)))
+        })
+    }
+
+    // Not tracked by `cancel_all`: the index asked for it after an action
+    // changed its list, not the user.
+    pub fn reload_index(&mut self, client: &HttpClient, request: PagesRequest) {
+        self.queue(client, Box::new(move |ctx| {
+            let fetched = refetch_index(&request, &ctx.client)?;
+            Ok(Event::IndexReloaded(Box::new(fetched)))
+        }));
+    }
+
+    // Not tracked by `cancel_all`: the dates are kept by the index, which
+    // shows them as they come.
+    pub fn load_latest_updates(&mut self, client: &HttpClient, items: Vec<Subscription>) {
+        for item in items {
+            self.queue(client, Box::new(move |ctx| {
+                let uri = ctx.client.url(&item.updates_path()).to_string();
+                let frag = ctx.client.get_parse(&uri, Freshness::Index)?;
+                let updated = subscriptions::scrape_latest_update(&frag)
+                                             .ok_or_else(|| Ao3Error::Parse("latest update".to_string()))?;
+                Ok(Event::LatestUpdateFetched(item.path, updated))
+            }));
+        }
+    }
+
+    // Interrupted downloads are resumed the next time around.
+    pub fn download(&mut self, client: &HttpClient, work_id: String, format: DownloadFormat, dir: PathBuf) -> FetchId {
+        self.submit(client, move |ctx| {
+            let mut reported = 0;
+            let prefix = format!("Downloading {}", format);
+            let path = ctx.client.download_work(&work_id, format, &dir, &mut |received, total| {
+                if received >= reported + PROGRESS_STEP {
+                    reported = received;
+                    ctx.progress(loading_text(&prefix, received, total));
+                }
+                !ctx.is_cancelled()
+            })?;
+            Ok(Event::WorkDownloaded(ctx.id, path))
+        })
+    }
+
+    pub fn login(&mut self, client: &HttpClient) -> FetchId {
+        self.submit(client, |ctx| {
+            ctx.client.renew_login()?;
+            Ok(Event::SessionUpdated(ctx.id))
+        })
+    }
+
+    pub fn logout(&mut self, client: &HttpClient) -> FetchId {
+        self.submit(client, |ctx| {
+            ctx.client.logout()?;
+            Ok(Event::SessionUpdated(ctx.id))
+        })
+    }
+
+    // Not tracked by `cancel_all`: the action changes things on AO3, and
+    // waits in the outbox when it didn't go through.
+    pub fn send_action(&mut self, client: &HttpClient, action: Ao3Action, title: String) {
+        self.queue(client, Box::new(move |ctx| {
+            let res = action.run(&ctx.client);
+            Ok(Event::ActionSent(Box::new(action), title, res))
+        }));
+    }
+
+    // Not tracked by `cancel_all`: the actions change things on AO3, and we
+    // need to know which ones went through.
+    pub fn replay_outbox(&mut self, client: &HttpClient, actions: Vec<PendingAction>) {
+        self.queue(client, Box::new(move |ctx| {
+            if !ctx.client.logged_in() {
+                ctx.client.renew_login()
+                   .map_err(|e| eprintln!("Can't log in before sending pending actions: {}", e))
+                   .ok();
+            }
+            Ok(Event::OutboxReplayed(outbox::replay(&ctx.client, actions)))
+        }));
+    }
+}
+
+pub fn loading_text(prefix: &str, received: usize, total: Option<u64>) -> String {
+    let kib = received / 1024;
+    match total {
+        Some(total) if total > 0 => format!("{}… {}%", prefix, (100 * received as u64 / total).min(100)),
+        _ => format!("{}… {} KiB", prefix, kib),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aDownloadInProgress_WHEN_loadingTextIsCalled_THEN_itWillShowTheProgress() {
+        // GIVEN a download in progress
+        let received = 512 * 1024;
+
+        // WHEN loading_text is called
+        let known = loading_text("Loading work", received, Some(2 * 1024 * 1024));
+        let unknown = loading_text("Loading work", received, None);
+
+        // THEN it will show the progress
+        assert_eq!(known, "Loading work… 25%");
+        assert_eq!(unknown, "Loading work… 512 KiB");
+    }
+}
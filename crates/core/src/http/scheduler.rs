@@ -0,0 +1,186 @@
+use std::thread;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local, Utc};
+use fxhash::FxHashMap;
+use rand_core::{RngCore, SeedableRng};
+use rand_xoshiro::Xoroshiro128Plus;
+
+const BUCKET_CAPACITY: f64 = 5.0;
+const REFILL_PER_SECOND: f64 = 1.0;
+const BACKOFF_BASE: Duration = Duration::from_secs(2);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+// Longer waits are reported to the user instead of blocking.
+pub const MAX_INLINE_WAIT: Duration = Duration::from_secs(5);
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(now: Instant) -> Bucket {
+        Bucket {
+            tokens: BUCKET_CAPACITY,
+            last_refill: now,
+            paused_until: None,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * REFILL_PER_SECOND).min(BUCKET_CAPACITY);
+        self.last_refill = now;
+    }
+
+    fn wait_time(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        let paused = self.paused_until
+                         .map(|until| until.saturating_duration_since(now))
+                         .unwrap_or_default();
+        let empty = if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / REFILL_PER_SECOND)
+        };
+        paused.max(empty)
+    }
+}
+
+// Spaces out requests with a token bucket per host.
+pub struct Scheduler {
+    buckets: Mutex<FxHashMap<String, Bucket>>,
+    rng: Mutex<Xoroshiro128Plus>,
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler {
+            buckets: Mutex::new(FxHashMap::default()),
+            rng: Mutex::new(Xoroshiro128Plus::seed_from_u64(Local::now().timestamp_subsec_nanos() as u64)),
+        }
+    }
+
+    // Blocks until a request to `host` can go out.
+    // Returns the remaining wait if it's longer than `MAX_INLINE_WAIT`.
+    pub fn acquire(&self, host: &str) -> Result<(), Duration> {
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap();
+                let now = Instant::now();
+                let bucket = buckets.entry(host.to_string())
+                                    .or_insert_with(|| Bucket::new(now));
+                let wait = bucket.wait_time(now);
+                if wait.is_zero() {
+                    bucket.tokens -= 1.0;
+                    return Ok(());
+                }
+                wait
+            };
+
+            if wait > MAX_INLINE_WAIT {
+                return Err(wait);
+            }
+
+            thread::sleep(wait);
+        }
+    }
+
+    pub fn pause(&self, host: &str, delay: Duration) {
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        let bucket = buckets.entry(host.to_string())
+                            .or_insert_with(|| Bucket::new(now));
+        let until = now + delay;
+        bucket.tokens = 0.0;
+        bucket.last_refill = until;
+        bucket.paused_until = Some(bucket.paused_until.map_or(until, |prev| prev.max(until)));
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = BACKOFF_BASE.saturating_mul(1 << attempt.min(5)).min(BACKOFF_MAX);
+        let jitter = self.rng.lock().unwrap().next_u64() % BACKOFF_BASE.as_millis() as u64;
+        delay + Duration::from_millis(jitter)
+    }
+}
+
+// `Retry-After` is either a number of seconds or an HTTP date.
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    DateTime::parse_from_rfc2822(value).ok()
+             .map(|date| (date.with_timezone(&Utc) - now).to_std().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_anEmptyBucket_WHEN_waitTimeIsCalled_THEN_itWillWaitForTheNextToken() {
+        // GIVEN an empty bucket
+        let now = Instant::now();
+        let mut bucket = Bucket::new(now);
+        bucket.tokens = 0.0;
+
+        // WHEN wait_time is called
+        let wait = bucket.wait_time(now);
+        let later = bucket.wait_time(now + Duration::from_secs(2));
+
+        // THEN it will wait for the next token
+        assert_eq!(wait, Duration::from_secs_f64(1.0 / REFILL_PER_SECOND));
+        assert_eq!(later, Duration::ZERO);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aPausedHost_WHEN_acquireIsCalled_THEN_theWaitWillBeReported() {
+        // GIVEN a paused host
+        let scheduler = Scheduler::new();
+        scheduler.pause("archiveofourown.org", Duration::from_secs(30));
+
+        // WHEN acquire is called
+        let paused = scheduler.acquire("archiveofourown.org");
+        let other = scheduler.acquire("example.org");
+
+        // THEN the wait will be reported
+        assert!(matches!(paused, Err(wait) if wait > MAX_INLINE_WAIT));
+        assert!(other.is_ok());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn WHEN_parseRetryAfterIsCalled_THEN_secondsAndDatesWillBeParsed() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT").unwrap().with_timezone(&Utc);
+
+        // WHEN parse_retry_after is called
+        let seconds = parse_retry_after("120", now);
+        let date = parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now);
+        let garbage = parse_retry_after("soon", now);
+
+        // THEN seconds and dates will be parsed
+        assert_eq!(seconds, Some(Duration::from_secs(120)));
+        assert_eq!(date, Some(Duration::from_secs(30)));
+        assert_eq!(garbage, None);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn WHEN_backoffIsCalled_THEN_theDelayWillGrowWithJitter() {
+        let scheduler = Scheduler::new();
+
+        // WHEN backoff is called
+        let first = scheduler.backoff(0);
+        let third = scheduler.backoff(2);
+        let last = scheduler.backoff(20);
+
+        // THEN the delay will grow with jitter
+        assert!(first >= BACKOFF_BASE && first < 2 * BACKOFF_BASE);
+        assert!(third >= 4 * BACKOFF_BASE && third < 5 * BACKOFF_BASE);
+        assert!(last >= BACKOFF_MAX && last < BACKOFF_MAX + BACKOFF_BASE);
+    }
+}
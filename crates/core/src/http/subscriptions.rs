@@ -0,0 +1,198 @@
+use chrono::NaiveDate;
+use scraper::{Html, Selector};
+use crate::helpers::decode_entities;
+use crate::html::form_value;
+use crate::selectors::selectors;
+
+const BLURB_DATE_FORMAT: &str = "%d %b %Y";
+const CHAPTER_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    Work,
+    Series,
+    User,
+}
+
+impl SubscriptionKind {
+    // From the path of what's subscribed to: `/works/1`, `/series/1` or `/users/name`.
+    pub fn from_path(path: &str) -> Option<SubscriptionKind> {
+        let mut parts = path.trim_start_matches('/').split('/');
+        let kind = match parts.next()? {
+            "works" => SubscriptionKind::Work,
+            "series" => SubscriptionKind::Series,
+            "users" => SubscriptionKind::User,
+            _ => return None,
+        };
+        parts.next().filter(|id| !id.is_empty())?;
+        Some(kind)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SubscriptionKind::Work => "Work",
+            SubscriptionKind::Series => "Series",
+            SubscriptionKind::User => "Author",
+        }
+    }
+}
+
+// An item of a user's subscriptions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub kind: SubscriptionKind,
+    pub name: String,
+    pub path: String,
+}
+
+impl Subscription {
+    // From an item of the subscriptions page, as kept by the index.
+    pub fn new(item: &str) -> Option<Subscription> {
+        let frag = Html::parse_fragment(item);
+        let selector = Selector::parse(&selectors().subscriptions.link).unwrap();
+        let link = frag.select(&selector).next()?;
+        let path = link.value().attr("href")?.to_string();
+        let kind = SubscriptionKind::from_path(&path)?;
+        let name = decode_entities(&link.text().collect::<String>()).trim().to_string();
+        Some(Subscription { kind, name, path })
+    }
+
+    // The page telling when it was last updated: a work's chapter index, or
+    // the works of a series or user.
+    pub fn updates_path(&self) -> String {
+        match self.kind {
+            SubscriptionKind::Work => format!("{}/navigate", self.path),
+            SubscriptionKind::Series => self.path.clone(),
+            SubscriptionKind::User => format!("{}/works", self.path),
+        }
+    }
+
+    // What to open when the item is tapped.
+    pub fn works_path(&self) -> String {
+        match self.kind {
+            SubscriptionKind::User => format!("{}/works", self.path),
+            _ => self.path.clone(),
+        }
+    }
+}
+
+// Authors are linked through their pseuds: `/users/name/pseuds/pseud`.
+pub fn user_path(location: &str) -> Option<String> {
+    let (_, rest) = location.split_once("/users/")?;
+    let name = rest.split('/').next().filter(|name| !name.is_empty())?;
+    Some(format!("/users/{}", name))
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim().trim_start_matches('(').trim_end_matches(')');
+    NaiveDate::parse_from_str(text, CHAPTER_DATE_FORMAT)
+        .or_else(|_| NaiveDate::parse_from_str(text, BLURB_DATE_FORMAT))
+        .ok()
+}
+
+// The latest date of a page given by `updates_path`: chapters and works
+// aren't always listed by date.
+pub fn scrape_latest_update(frag: &Html) -> Option<NaiveDate> {
+    let profile = selectors();
+    let chapter_dates = Selector::parse(&profile.subscriptions.chapter_dates).unwrap();
+    let works = Selector::parse(&profile.index.works).unwrap();
+    let datetime = Selector::parse(&profile.blurb.datetime).unwrap();
+    frag.select(&chapter_dates)
+        .chain(frag.select(&works).filter_map(|work| work.select(&datetime).next()))
+        .filter_map(|date| parse_date(&date.text().collect::<String>()))
+        .max()
+}
+
+// The subscribe button of a work's, series' or user's page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionForm {
+    pub action: String,
+    // The hidden fields: the token, what's subscribed to and, to
+    // unsubscribe, the method.
+    pub params: Vec<(String, String)>,
+}
+
+impl SubscriptionForm {
+    // Once subscribed, the form points to the subscription.
+    pub fn is_subscribed(&self) -> bool {
+        self.action.rsplit_once("/subscriptions/")
+            .is_some_and(|(_, id)| !id.is_empty())
+    }
+}
+
+// Only logged in users get a subscribe button, except on their own pages.
+pub fn scrape_subscription_form(frag: &Html) -> Option<SubscriptionForm> {
+    let selector = Selector::parse(&selectors().forms.subscription_form).unwrap();
+    let form = frag.select(&selector).next()?;
+    let action = form.value().attr("action")?.to_string();
+    let fields = Selector::parse(&selectors().forms.subscription_fields).unwrap();
+    let params = form.select(&fields).filter_map(|input| {
+        let name = input.value().attr("name")?;
+        let value = form_value(input).unwrap_or_default();
+        Some((name.to_string(), decode_entities(value).into_owned()))
+    }).collect();
+    Some(SubscriptionForm { action, params })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUBSCRIBED: &str = r#"<html><body><ul class="work navigation actions">
+<li class="subscribe"><form class="ajax-create-destroy" id="delete_subscription_9" action="/users/reader/subscriptions/9" method="post">
+<input type="hidden" name="_method" value="delete" /><input type="hidden" name="authenticity_token" value="fixture-token" />
+<input type="submit" value="Unsubscribe" /></form></li>
+</ul></body></html>"#;
+
+    const CHAPTER_INDEX: &str = r#"<html><body><ol class="chapter index group">
+<li><a href="/works/1/chapters/10">1. Away Team</a> <span class="datetime">(2023-11-02)</span></li>
+<li><a href="/works/1/chapters/11">2. Shore Leave</a> <span class="datetime">(2024-03-05)</span></li>
+</ol></body></html>"#;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_anItemOfTheSubscriptionsPage_WHEN_subscriptionNewIsCalled_THEN_itWillLinkToWhatIsSubscribedTo() {
+        // GIVEN an item of the subscriptions page
+        let item = r#"<dt><a href="/series/7">Shore Leave &amp; Other Stories</a> by <a rel="author" href="/users/kirk/pseuds/kirk">kirk</a></dt>"#;
+
+        // WHEN Subscription::new is called
+        let subscription = Subscription::new(item).unwrap();
+
+        // THEN it will link to what is subscribed to
+        assert_eq!(subscription, Subscription {
+            kind: SubscriptionKind::Series,
+            name: "Shore Leave & Other Stories".to_string(),
+            path: "/series/7".to_string(),
+        });
+        assert_eq!(subscription.updates_path(), "/series/7");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aSubscribedWork_WHEN_scrapeSubscriptionFormIsCalled_THEN_itWillPointToTheSubscription() {
+        // GIVEN a subscribed work
+        let frag = Html::parse_document(SUBSCRIBED);
+
+        // WHEN scrape_subscription_form is called
+        let form = scrape_subscription_form(&frag).unwrap();
+
+        // THEN it will point to the subscription
+        assert!(form.is_subscribed());
+        assert_eq!(form.action, "/users/reader/subscriptions/9");
+        assert_eq!(form.params, vec![("_method".to_string(), "delete".to_string()),
+                                     ("authenticity_token".to_string(), "fixture-token".to_string())]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aChapterIndex_WHEN_scrapeLatestUpdateIsCalled_THEN_theLastChapterDateWillBeReturned() {
+        // GIVEN a chapter index
+        let frag = Html::parse_document(CHAPTER_INDEX);
+
+        // WHEN scrape_latest_update is called
+        let date = scrape_latest_update(&frag);
+
+        // THEN the last chapter's date will be returned
+        assert_eq!(date, NaiveDate::from_ymd_opt(2024, 3, 5));
+    }
+}
@@ -27,6 +27,7 @@ pub mod gesture;
 mod ao3_metadata;
 pub mod http;
 pub mod html;
+pub mod selectors;
 
 pub use anyhow;
 pub use fxhash;
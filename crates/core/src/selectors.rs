@@ -0,0 +1,347 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use anyhow::{Error, Context};
+use lazy_static::lazy_static;
+use scraper::Selector;
+use serde::{Serialize, Deserialize};
+use toml::{Table, Value};
+use crate::ao3_metadata::TagKind;
+
+pub const SELECTORS_PATH: &str = "selectors/ao3.toml";
+pub const USER_SELECTORS_PATH: &str = "selectors/ao3-user.toml";
+
+// Used when the shipped profile is missing or broken.
+const BUILTIN_SELECTORS: &str = include_str!("../../../selectors/ao3.toml");
+
+lazy_static! {
+    static ref SELECTORS: RwLock<Arc<SelectorProfile>> = RwLock::new(Arc::new(SelectorProfile::default()));
+}
+
+// The profile the scrapers currently use.
+pub fn selectors() -> Arc<SelectorProfile> {
+    SELECTORS.read().unwrap().clone()
+}
+
+pub fn set_selectors(profile: SelectorProfile) {
+    *SELECTORS.write().unwrap() = Arc::new(profile);
+}
+
+// A selector that is known to parse.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Css(String);
+
+impl TryFrom<String> for Css {
+    type Error = String;
+
+    fn try_from(select: String) -> Result<Css, String> {
+        Selector::parse(&select).map_err(|e| format!("invalid selector {:?}: {:?}", select, e))?;
+        Ok(Css(select))
+    }
+}
+
+impl From<Css> for String {
+    fn from(css: Css) -> String {
+        css.0
+    }
+}
+
+impl Deref for Css {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SelectorProfile {
+    pub version: u32,
+    pub index: IndexSelectors,
+    pub blurb: BlurbSelectors,
+    pub work: WorkSelectors,
+    pub comments: CommentSelectors,
+    pub subscriptions: SubscriptionSelectors,
+    pub forms: FormSelectors,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct IndexSelectors {
+    pub works: Css,
+    pub heading: Css,
+    pub tag: Css,
+    pub pagination: Css,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BlurbSelectors {
+    pub datetime: Css,
+    pub title: Css,
+    pub authors: Css,
+    pub fandoms: Css,
+    pub required_tags: Css,
+    pub symbols: Css,
+    pub tags: TagSelectors,
+    pub summary: Css,
+    pub words: Css,
+    pub comments: Css,
+    pub kudos: Css,
+    pub hits: Css,
+    pub bookmarks: Css,
+    pub chapters: Css,
+    pub language: Css,
+    pub series: Css,
+    pub gifts: Css,
+    pub restricted: Css,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct WorkSelectors {
+    pub body: Css,
+    pub meta: Css,
+    pub preface: Css,
+    pub title: Css,
+    pub byline: Css,
+    pub chapter: ChapterSelectors,
+    pub downloads: Css,
+    pub notes: Css,
+    pub afterword: Css,
+    pub chapter_index: Css,
+    pub skin: Css,
+    pub authors: Css,
+    pub summary: Css,
+    pub associations: Css,
+    pub gifts: Css,
+    pub restricted: Css,
+    pub published: Css,
+    pub status: Css,
+    pub fandoms: Css,
+    pub rating: Css,
+    pub categories: Css,
+    pub tags: TagSelectors,
+    pub words: Css,
+    pub chapters: Css,
+    pub comments: Css,
+    pub kudos: Css,
+    pub hits: Css,
+    pub bookmarks: Css,
+    pub language: Css,
+    pub series: Css,
+    pub collections: Css,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ChapterSelectors {
+    pub element: Css,
+    pub title: Css,
+    pub link: Css,
+    pub summary: Css,
+    pub notes: Css,
+    pub end_notes: Css,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TagSelectors {
+    pub warnings: Css,
+    pub relationships: Css,
+    pub characters: Css,
+    pub freeforms: Css,
+}
+
+impl TagSelectors {
+    pub fn of_kind(&self, kind: TagKind) -> &Css {
+        match kind {
+            TagKind::Warning => &self.warnings,
+            TagKind::Relationship => &self.relationships,
+            TagKind::Character => &self.characters,
+            TagKind::Freeform => &self.freeforms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CommentSelectors {
+    pub threads: Css,
+    pub thread: Css,
+    pub comment: Css,
+    pub byline: Css,
+    pub author: Css,
+    pub posted: Css,
+    pub body: Css,
+    pub pagination: Css,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SubscriptionSelectors {
+    pub items: Css,
+    pub link: Css,
+    pub chapter_dates: Css,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FormSelectors {
+    pub login_token: Css,
+    pub logout_token: Css,
+    pub kudos_token: Css,
+    pub comment_token: Css,
+    pub comment_pseud: Css,
+    pub bookmark_form: Css,
+    pub subscription_form: Css,
+    pub subscription_fields: Css,
+    pub flash: Css,
+}
+
+fn load_table<P: AsRef<Path>>(path: P) -> Result<Table, Error> {
+    let s = fs::read_to_string(path.as_ref())
+               .with_context(|| format!("can't read file {}", path.as_ref().display()))?;
+    s.parse::<Table>()
+     .with_context(|| format!("can't parse TOML content from {}", path.as_ref().display()))
+}
+
+// The overrides only need to hold the keys they change.
+fn merge(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(table)) => merge(base_table, table),
+            (_, value) => { base.insert(key, value); },
+        }
+    }
+}
+
+fn version(table: &Table) -> Option<i64> {
+    table.get("version").and_then(Value::as_integer)
+}
+
+impl SelectorProfile {
+    fn from_table(table: Table) -> Result<SelectorProfile, Error> {
+        Value::Table(table).try_into()
+                           .context("can't read the selectors")
+    }
+
+    // The shipped profile, with the user's overrides on top. Broken files
+    // are reported and skipped, so that the scrapers always have a profile.
+    pub fn load<P: AsRef<Path>, Q: AsRef<Path>>(path: P, user_path: Q) -> SelectorProfile {
+        let base = load_table(&path).and_then(|table| {
+            SelectorProfile::from_table(table.clone())?;
+            Ok(table)
+        }).unwrap_or_else(|e| {
+            eprintln!("Can't load selectors: {:#}.", e);
+            BUILTIN_SELECTORS.parse().unwrap()
+        });
+        let shipped = SelectorProfile::from_table(base.clone()).unwrap();
+
+        if !user_path.as_ref().exists() {
+            return shipped;
+        }
+
+        let overrides = match load_table(&user_path) {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                eprintln!("Can't load selector overrides: {:#}.", e);
+                return shipped;
+            },
+        };
+
+        // The shipped profile has probably been fixed since.
+        if let (Some(written_for), Some(current)) = (version(&overrides), version(&base)) {
+            if written_for < current {
+                eprintln!("Ignoring {}: it was written for version {} of the selectors.",
+                          user_path.as_ref().display(), written_for);
+                return shipped;
+            }
+        }
+
+        let mut table = base;
+        merge(&mut table, overrides);
+        SelectorProfile::from_table(table)
+                        .with_context(|| format!("can't apply {}", user_path.as_ref().display()))
+                        .map_err(|e| eprintln!("Can't load selector overrides: {:#}.", e))
+                        .unwrap_or(shipped)
+    }
+}
+
+impl Default for SelectorProfile {
+    fn default() -> Self {
+        SelectorProfile::from_table(BUILTIN_SELECTORS.parse().unwrap()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn write_overrides(name: &str, content: &str) -> std::path::PathBuf {
+        let path = env::temp_dir().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_partialOverrides_WHEN_loadIsCalled_THEN_onlyTheirKeysWillChange() {
+        // GIVEN partial overrides
+        let user_path = write_overrides("ao3reader-selectors-partial.toml", r#"
+            version = 1
+            [blurb]
+            title = "h4.heading a.title"
+            [work.tags]
+            freeforms = "dd.additional.tags a"
+        "#);
+
+        // WHEN load is called
+        let profile = SelectorProfile::load("/nonexistent/ao3.toml", &user_path);
+
+        // THEN only their keys will change
+        let builtin = SelectorProfile::default();
+        assert_eq!(&*profile.blurb.title, "h4.heading a.title");
+        assert_eq!(&*profile.work.tags.freeforms, "dd.additional.tags a");
+        assert_eq!(profile.work.tags.warnings, builtin.work.tags.warnings);
+        assert_eq!(profile.index, builtin.index);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_anInvalidSelector_WHEN_loadIsCalled_THEN_theOverridesWillBeIgnored() {
+        // GIVEN an invalid selector
+        let user_path = write_overrides("ao3reader-selectors-invalid.toml", r#"
+            [index]
+            works = "li.work["
+            tag = "h2.heading a.tag-name"
+        "#);
+
+        // WHEN load is called
+        let profile = SelectorProfile::load("/nonexistent/ao3.toml", &user_path);
+
+        // THEN the overrides will be ignored
+        assert_eq!(profile, SelectorProfile::default());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_outdatedOverrides_WHEN_loadIsCalled_THEN_theyWillBeIgnored() {
+        // GIVEN outdated overrides
+        let user_path = write_overrides("ao3reader-selectors-outdated.toml", r#"
+            version = 0
+            [index]
+            works = "li.blurb"
+        "#);
+
+        // WHEN load is called
+        let profile = SelectorProfile::load("/nonexistent/ao3.toml", &user_path);
+
+        // THEN they will be ignored
+        assert_eq!(&*profile.index.works, "li.work");
+    }
+}
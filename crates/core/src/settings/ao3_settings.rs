@@ -1,22 +1,123 @@
 use serde::{Serialize, Deserialize};
-use crate::helpers::url_strip_page;
+use crate::helpers::{get_url, url_strip_page};
+use std::path::PathBuf;
+use std::collections::BTreeSet;
+use anyhow::Error;
 use url::Url;
 use crate::view::works::work::WorkView;
+use crate::http::FixtureMode;
+use crate::http::outbox::OUTBOX_PATH;
+use crate::http::comments::DRAFTS_PATH;
+use super::secrets::{Secrets, SECRETS_PATH};
+use super::INTERNAL_CARD_ROOT;
+
+pub const DEFAULT_AO3_URL: &str = "https://archiveofourown.org/";
+pub const HTTP_CACHE_PATH: &str = ".cache/http";
+pub const HTTP_FIXTURES_PATH: &str = "fixtures/http";
+pub const DOWNLOADS_DIRNAME: &str = "AO3";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct Ao3Settings {
+    // Root of the archive, so that mirrors and proxies can be used.
+    pub base_url: Url,
     pub remember_me: bool,
     pub remember_username: bool,
     pub username: Option<String>,
+    // Plaintext credentials from older versions: they're moved to the
+    // secrets file when the settings are loaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub login_cookie: Option<String>,
+    // Only the session cookie is kept when this is off.
+    pub remember_password: bool,
+    pub secrets_path: PathBuf,
+    #[serde(skip)]
+    pub secrets: Secrets,
+    // The secrets file couldn't be decrypted: it's left alone until there
+    // are new credentials to save, and the user is asked to log in again.
+    #[serde(skip)]
+    pub secrets_unreadable: bool,
     pub faves: Vec<(String, Url)>,
     pub work_display: WorkView,
-    pub screenshot_button: bool
+    // Works with more words are read a chapter at a time, 0 always
+    // fetches works whole.
+    pub lazy_load_words: usize,
+    // The works read without their creator's work skin.
+    pub skinless_works: BTreeSet<String>,
+    pub screenshot_button: bool,
+    pub cache_path: PathBuf,
+    // In bytes.
+    pub cache_max_size: u64,
+    // Records or replays AO3's responses, for tests and debugging.
+    pub fixture_mode: FixtureMode,
+    pub fixtures_path: PathBuf,
+    // AO3 actions waiting for the network.
+    pub outbox_path: PathBuf,
+    // Comments that weren't posted yet.
+    pub drafts_path: PathBuf,
+    // Where downloaded works are saved, preferably within a library.
+    pub download_path: PathBuf,
 }
 
 impl Ao3Settings {
+    // Returns whether plaintext credentials were moved to the secrets file,
+    // in which case the settings should be saved right away.
+    pub fn load_secrets(&mut self) -> bool {
+        self.secrets_unreadable = false;
+        let mut secrets = if self.secrets_path.exists() {
+            Secrets::load(&self.secrets_path)
+                    .map_err(|e| {
+                        eprintln!("Can't load secrets: {:#}.", e);
+                        self.secrets_unreadable = true;
+                    })
+                    .unwrap_or_default()
+        } else {
+            Secrets::default()
+        };
+
+        let migrating = self.password.is_some() || self.login_cookie.is_some();
+        if let Some(password) = self.password.take() {
+            secrets.password = Some(password);
+        }
+        if let Some(login_cookie) = self.login_cookie.take() {
+            secrets.login_cookie = Some(login_cookie);
+        }
+        self.secrets = secrets;
+
+        if !migrating {
+            return false;
+        }
+        if let Err(e) = self.save_secrets() {
+            eprintln!("Can't migrate credentials: {:#}.", e);
+            // Keep them where they were rather than losing them.
+            if self.remember_password {
+                self.password = self.secrets.password.clone();
+            }
+            self.login_cookie = self.secrets.login_cookie.clone();
+            return false;
+        }
+        true
+    }
+
+    pub fn save_secrets(&self) -> Result<(), Error> {
+        let mut secrets = self.secrets.clone();
+        if !self.remember_password {
+            secrets.password = None;
+        }
+        if !self.remember_me {
+            secrets.login_cookie = None;
+        }
+        if self.secrets_unreadable && secrets == Secrets::default() {
+            return Ok(());
+        }
+        secrets.save(&self.secrets_path)
+    }
+
+    pub fn url(&self, path: &str) -> Url {
+        get_url(&self.base_url, path)
+    }
 
     pub fn url_in_faves(&self, mut url: Url) -> bool {
         url_strip_page(&mut url);
@@ -42,14 +143,28 @@ impl Ao3Settings {
 impl Default for Ao3Settings {
     fn default() -> Self {
         Ao3Settings {
+            base_url: Url::parse(DEFAULT_AO3_URL).unwrap(),
             remember_me: false,
             remember_username: false,
             username: None,
             password: None,
             login_cookie: None,
+            remember_password: true,
+            secrets_path: PathBuf::from(SECRETS_PATH),
+            secrets: Secrets::default(),
+            secrets_unreadable: false,
             faves: Vec::new(),
             work_display: WorkView::Short,
-            screenshot_button: false
+            lazy_load_words: 100_000,
+            skinless_works: BTreeSet::new(),
+            screenshot_button: false,
+            cache_path: PathBuf::from(HTTP_CACHE_PATH),
+            cache_max_size: 64 << 20,
+            fixture_mode: FixtureMode::Off,
+            fixtures_path: PathBuf::from(HTTP_FIXTURES_PATH),
+            outbox_path: PathBuf::from(OUTBOX_PATH),
+            drafts_path: PathBuf::from(DRAFTS_PATH),
+            download_path: PathBuf::from(INTERNAL_CARD_ROOT).join(DOWNLOADS_DIRNAME),
         }
     }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 mod preset;
 mod ao3_settings;
+mod secrets;
 
 use std::env;
 use std::ops::Index;
@@ -16,9 +17,10 @@ use crate::device::CURRENT_DEVICE;
 use crate::library::Library;
 use crate::unit::mm_to_px;
 use self::ao3_settings::Ao3Settings;
-use crate::helpers::{load_toml};
+use crate::helpers::{load_toml, save_toml};
 
 pub use self::preset::{LightPreset, guess_frontlight};
+pub use self::secrets::{Secrets, SECRETS_PATH};
 
 pub const SETTINGS_PATH: &str = "Settings.toml";
 pub const DEFAULT_FONT_PATH: &str = "/mnt/onboard/fonts";
@@ -136,13 +138,19 @@ pub struct Settings {
 impl Settings {
     pub fn load_settings() -> Settings {
         let path = Path::new(SETTINGS_PATH);
-        let settings = if path.exists() {
+        let mut settings: Settings = if path.exists() {
             load_toml::<Settings, _>(path)
                 .map_err(|e| eprintln!("Can't open Settings.toml: {:#}.", e))
                 .unwrap()
         } else {
             Default::default()
         };
+        // The plaintext credentials are gone from the file too.
+        if settings.ao3.load_secrets() {
+            save_toml(&settings, path)
+                .map_err(|e| eprintln!("Can't save Settings.toml: {:#}.", e))
+                .ok();
+        }
         settings
     }
 
@@ -380,6 +388,7 @@ pub struct ReaderSettings {
     pub min_margin_width: i32,
     pub max_margin_width: i32,
     pub line_height: f32,
+    pub notes_display: NotesDisplay,
     pub continuous_fit_to_width: bool,
     pub ignore_document_css: bool,
     pub dithered_kinds: FxHashSet<String>,
@@ -401,6 +410,28 @@ pub struct BatterySettings {
     pub power_off: f32,
 }
 
+// How the author's notes of a work are laid out.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotesDisplay {
+    Show,
+    // Replaced by a link that opens them in an overlay.
+    Collapse,
+    Hide,
+}
+
+impl NotesDisplay {
+    pub const ALL: [NotesDisplay; 3] = [NotesDisplay::Show, NotesDisplay::Collapse, NotesDisplay::Hide];
+
+    pub fn label(&self) -> &str {
+        match self {
+            NotesDisplay::Show => "Show Notes",
+            NotesDisplay::Collapse => "Collapse Notes",
+            NotesDisplay::Hide => "Hide Notes",
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum FinishedAction {
@@ -495,6 +526,7 @@ impl Default for ReaderSettings {
             min_margin_width: DEFAULT_MARGIN_WIDTH.saturating_sub(8),
             max_margin_width: DEFAULT_MARGIN_WIDTH.saturating_add(2),
             line_height: DEFAULT_LINE_HEIGHT,
+            notes_display: NotesDisplay::Show,
             continuous_fit_to_width: true,
             ignore_document_css: false,
             dithered_kinds: ["cbz", "png", "jpg", "jpeg"].iter().map(|k| k.to_string()).collect(),
@@ -598,7 +630,6 @@ mod tests {
     use super::*;
     use std::fs;
     use reqwest::Url;
-    use crate::helpers::{save_toml};
 
     #[test]
     #[allow(non_snake_case)]
@@ -609,6 +640,8 @@ mod tests {
             ("fake fave search".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL")));
         file_settings.ao3.username = Some("testUser".to_string());
         file_settings.ao3.password = Some("superFakePass123".to_string());
+        file_settings.ao3.secrets_path = env::temp_dir().join("ao3reader-settings-secrets");
+        let _result = fs::remove_file(&file_settings.ao3.secrets_path);
         let path = Path::new(SETTINGS_PATH);
         let _result = save_toml(&file_settings, path);
 
@@ -620,7 +653,11 @@ mod tests {
         // by users in order to get anything to display on the home screen
         assert_eq!(settings.ao3.faves, file_settings.ao3.faves);
         assert_eq!(settings.ao3.username, Some("testUser".to_string()));
-        assert_eq!(settings.ao3.password, Some("superFakePass123".to_string()));
+        // The plaintext password has been moved to the secrets file
+        assert_eq!(settings.ao3.secrets.password, Some("superFakePass123".to_string()));
+        assert_eq!(settings.ao3.password, None);
+        assert_eq!(Secrets::load(&file_settings.ao3.secrets_path).unwrap().password, Some("superFakePass123".to_string()));
+        assert!(!fs::read_to_string(path).unwrap().contains("superFakePass123"));
     }
 
     #[test]
@@ -642,6 +679,62 @@ mod tests {
         assert_eq!(settings.ao3.password, default_settings.ao3.password);
     }
 
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_unreadableSecrets_WHEN_saveSecretsIsCalled_THEN_theFileIsLeftInPlace() {
+        // GIVEN unreadable secrets
+        let mut settings: Settings = Default::default();
+        settings.ao3.secrets_path = env::temp_dir().join("ao3reader-unreadable-secrets");
+        fs::write(&settings.ao3.secrets_path, "not sealed by this device").unwrap();
+        settings.ao3.load_secrets();
+
+        // WHEN save_secrets is called
+        settings.ao3.save_secrets().unwrap();
+
+        // THEN the file is left in place
+        assert!(settings.ao3.secrets_unreadable);
+        assert_eq!(fs::read_to_string(&settings.ao3.secrets_path).unwrap(), "not sealed by this device");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_rememberPasswordIsOff_WHEN_saveSecretsIsCalled_THEN_onlyTheSessionCookieIsPersisted() {
+        // GIVEN remember_password is off
+        let mut settings: Settings = Default::default();
+        settings.ao3.remember_me = true;
+        settings.ao3.remember_password = false;
+        settings.ao3.secrets_path = env::temp_dir().join("ao3reader-cookie-only-secrets");
+        settings.ao3.secrets.password = Some("superFakePass123".to_string());
+        settings.ao3.secrets.login_cookie = Some("_otwarchive_session=abc".to_string());
+
+        // WHEN save_secrets is called
+        settings.ao3.save_secrets().unwrap();
+
+        // THEN only the session cookie is persisted
+        let secrets = Secrets::load(&settings.ao3.secrets_path).unwrap();
+        assert_eq!(secrets.password, None);
+        assert_eq!(secrets.login_cookie, Some("_otwarchive_session=abc".to_string()));
+        let toml = toml::to_string(&settings).unwrap();
+        assert!(!toml.contains("superFakePass123"));
+        assert!(!toml.contains("_otwarchive_session"));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aCustomBaseUrl_WHEN_ao3UrlIsCalled_THEN_pathsResolveAgainstIt() {
+        // GIVEN a custom base URL
+        let mut settings: Settings = Default::default();
+        settings.ao3.base_url = Url::parse("https://fakeo3.org/").expect("Test URL");
+
+        // WHEN url is called
+        let relative = settings.ao3.url("/works/1?view_adult=true");
+        let absolute = settings.ao3.url("https://other.org/tags/super-fake");
+
+        // THEN paths resolve against it
+        assert_eq!(relative.as_str(), "https://fakeo3.org/works/1?view_adult=true");
+        assert_eq!(absolute.as_str(), "https://other.org/tags/super-fake");
+    }
+
     #[test]
     #[allow(non_snake_case)]
     fn WHEN_getCurrentLibraryIsCalled_THEN_theAppropriateLibraryIsCreated() {
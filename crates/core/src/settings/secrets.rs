@@ -0,0 +1,215 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use anyhow::{Error, Context, format_err};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::hkdf::{Salt, HKDF_SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Serialize, Deserialize};
+
+// Outside of the drive exposed over USB.
+pub const SECRETS_PATH: &str = "/usr/local/ao3reader/secrets";
+
+const SECRETS_SALT: &[u8] = b"ao3reader-secrets-v1";
+const SECRETS_INFO: &[u8] = b"credentials";
+const SEED_LEN: usize = 32;
+
+// Older versions derived the key from whichever of these files existed,
+// which changes with the state of the wifi module.
+const DEVICE_ID_PATHS: [&str; 3] = [
+    "/etc/machine-id",
+    "/sys/class/net/wlan0/address",
+    "/sys/class/net/eth0/address",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Secrets {
+    pub password: Option<String>,
+    pub login_cookie: Option<String>,
+}
+
+impl Secrets {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Secrets, Error> {
+        Secrets::load_from(path.as_ref(), &DEVICE_ID_PATHS)
+    }
+
+    // Secrets sealed by older versions are sealed again with a seed of
+    // their own.
+    fn load_from(path: &Path, device_id_paths: &[&str]) -> Result<Secrets, Error> {
+        let data = fs::read(path)
+                      .with_context(|| format!("can't read file {}", path.display()))?;
+        let seed_path = seed_path(path);
+        let legacy = !seed_path.exists();
+        let key = if legacy {
+            derive_key(&device_id(device_id_paths))
+        } else {
+            derive_key(&fs::read(&seed_path)
+                           .with_context(|| format!("can't read file {}", seed_path.display()))?)
+        };
+        let plain = open(&key, data)
+                        .with_context(|| format!("can't decrypt {}", path.display()))?;
+        let secrets: Secrets = serde_json::from_slice(&plain)
+                                          .with_context(|| format!("can't parse secrets from {}", path.display()))?;
+        if legacy {
+            secrets.save(path)
+                   .map_err(|e| eprintln!("Can't seal secrets again: {:#}.", e))
+                   .ok();
+        }
+        Ok(secrets)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+               .with_context(|| format!("can't create directory {}", parent.display()))?;
+        }
+
+        let plain = serde_json::to_vec(self)?;
+        let data = seal(&derive_key(&load_seed(&seed_path(path))?), plain)?;
+        write_private(path, &data)
+    }
+}
+
+// The key is derived from a random seed kept beside the secrets, outside
+// of the drive exposed over USB.
+fn seed_path(path: &Path) -> PathBuf {
+    path.with_extension("key")
+}
+
+// Created the first time secrets are saved.
+fn load_seed(path: &Path) -> Result<Vec<u8>, Error> {
+    if path.exists() {
+        return fs::read(path)
+                  .with_context(|| format!("can't read file {}", path.display()));
+    }
+    let mut seed = vec![0u8; SEED_LEN];
+    SystemRandom::new().fill(&mut seed)
+                       .map_err(|_| format_err!("can't generate a seed"))?;
+    write_private(path, &seed)?;
+    Ok(seed)
+}
+
+fn write_private(path: &Path, data: &[u8]) -> Result<(), Error> {
+    let mut file = OpenOptions::new()
+                               .write(true)
+                               .create(true)
+                               .truncate(true)
+                               .mode(0o600)
+                               .open(path)
+                               .with_context(|| format!("can't create file {}", path.display()))?;
+    file.write_all(data)
+        .with_context(|| format!("can't write file {}", path.display()))
+}
+
+fn device_id(paths: &[&str]) -> Vec<u8> {
+    let mut id = Vec::new();
+    for path in paths {
+        if let Ok(data) = fs::read(path) {
+            id.extend_from_slice(&data);
+        }
+    }
+    for name in ["PRODUCT", "MODEL_NUMBER"] {
+        id.extend_from_slice(env::var(name).unwrap_or_default().as_bytes());
+    }
+    id
+}
+
+fn derive_key(seed: &[u8]) -> LessSafeKey {
+    let prk = Salt::new(HKDF_SHA256, SECRETS_SALT).extract(seed);
+    let okm = prk.expand(&[SECRETS_INFO], &CHACHA20_POLY1305).unwrap();
+    LessSafeKey::new(UnboundKey::from(okm))
+}
+
+// The nonce is stored in front of the ciphertext.
+fn seal(key: &LessSafeKey, mut data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new().fill(&mut nonce)
+                       .map_err(|_| format_err!("can't generate a nonce"))?;
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+       .map_err(|_| format_err!("can't encrypt secrets"))?;
+    let mut sealed = nonce.to_vec();
+    sealed.append(&mut data);
+    Ok(sealed)
+}
+
+fn open(key: &LessSafeKey, mut data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    if data.len() < NONCE_LEN {
+        return Err(format_err!("the file is truncated"));
+    }
+    let mut sealed = data.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&data)
+                      .map_err(|_| format_err!("invalid nonce"))?;
+    let len = key.open_in_place(nonce, Aad::empty(), &mut sealed)
+                 .map_err(|_| format_err!("wrong key or corrupted data"))?
+                 .len();
+    sealed.truncate(len);
+    Ok(sealed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_savedSecrets_WHEN_loadIsCalled_THEN_theyWillBeDecrypted() {
+        // GIVEN saved secrets
+        let path = env::temp_dir().join("ao3reader-secrets-roundtrip");
+        let secrets = Secrets {
+            password: Some("hunter2".to_string()),
+            login_cookie: Some("_otwarchive_session=abc".to_string()),
+        };
+        secrets.save(&path).unwrap();
+
+        // WHEN load is called
+        let loaded = Secrets::load(&path).unwrap();
+
+        // THEN they will be decrypted
+        assert_eq!(loaded, secrets);
+        assert!(!fs::read_to_string(&path).unwrap_or_default().contains("hunter2"));
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_tamperedSecrets_WHEN_loadIsCalled_THEN_itWillFail() {
+        // GIVEN tampered secrets
+        let path = env::temp_dir().join("ao3reader-secrets-tampered");
+        Secrets { password: Some("hunter2".to_string()), login_cookie: None }.save(&path).unwrap();
+        let mut data = fs::read(&path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        fs::write(&path, data).unwrap();
+
+        // WHEN load is called
+        let loaded = Secrets::load(&path);
+
+        // THEN it will fail
+        assert!(loaded.is_err());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_legacySecrets_WHEN_aDeviceIdFileDisappears_THEN_theyWillStillBeDecrypted() {
+        // GIVEN legacy secrets
+        let path = env::temp_dir().join("ao3reader-secrets-legacy");
+        let wlan_path = env::temp_dir().join("ao3reader-secrets-legacy-wlan0");
+        fs::remove_file(seed_path(&path)).ok();
+        fs::write(&wlan_path, "00:11:22:33:44:55").unwrap();
+        let id_paths = [wlan_path.to_str().unwrap()];
+        let secrets = Secrets { password: Some("hunter2".to_string()), login_cookie: None };
+        let plain = serde_json::to_vec(&secrets).unwrap();
+        fs::write(&path, seal(&derive_key(&device_id(&id_paths)), plain).unwrap()).unwrap();
+        Secrets::load_from(&path, &id_paths).unwrap();
+
+        // WHEN a device id file disappears
+        fs::remove_file(&wlan_path).unwrap();
+        let loaded = Secrets::load_from(&path, &id_paths);
+
+        // THEN they will still be decrypted
+        assert_eq!(loaded.unwrap(), secrets);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::geom::{Rectangle, CornerSpec, BorderSpec};
+use crate::gesture::GestureEvent;
+use crate::http::BookmarkForm;
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, ViewId, Align};
+use super::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM, SMALL_BAR_HEIGHT, BIG_BAR_HEIGHT};
+use super::button::Button;
+use super::label::Label;
+use super::input_field::InputField;
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE};
+use crate::context::Context;
+
+const LABEL_SAVE: &str = "Save";
+const LABEL_CANCEL: &str = "Cancel";
+const LABEL_DELETE: &str = "Delete";
+const LABEL_TAGS: &str = "Tags";
+const LABEL_COLLECTIONS: &str = "Collections";
+
+// The children are the title, a label and an input field for the tags and
+// the collections, the private and rec buttons, the notes' field and the
+// bottom buttons.
+const TAGS_INDEX: usize = 2;
+const COLLECTIONS_INDEX: usize = 4;
+const PRIVATE_INDEX: usize = 5;
+const REC_INDEX: usize = 6;
+const NOTES_INDEX: usize = 7;
+
+// Edits a work's bookmark above the keyboard.
+#[derive(Clone)]
+pub struct BookmarkEditor {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    view_id: ViewId,
+    // Kept as is: the editor doesn't offer to change pseuds.
+    pseud_id: String,
+    private: bool,
+    rec: bool,
+}
+
+fn flag_label(name: &str, value: bool) -> String {
+    format!("{}: {}", name, if value { "Yes" } else { "No" })
+}
+
+impl BookmarkEditor {
+    pub fn new(title: String, form: BookmarkForm, existing: bool, context: &mut Context) -> BookmarkEditor {
+        let id = ID_FEEDER.next();
+        let dpi = CURRENT_DEVICE.dpi;
+        let (width, height) = context.display.dims;
+        let mut children = Vec::new();
+
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+        let x_height = font.x_heights.0 as i32;
+        let padding = font.em() as i32;
+        let row_height = 4 * x_height;
+        let label_width = font.plan(LABEL_TAGS, None, None).width
+                              .max(font.plan(LABEL_COLLECTIONS, None, None).width) + padding;
+        let button_width = [LABEL_SAVE, LABEL_CANCEL, LABEL_DELETE].iter()
+                                                                   .map(|label| font.plan(label, None, None).width)
+                                                                   .max().unwrap_or_default() + 2 * padding;
+
+        // The keyboard shows up above the bottom bar.
+        let keyboard_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32 + 3 * scale_by_dpi(BIG_BAR_HEIGHT, dpi) as i32;
+        let rect = rect![padding, padding,
+                         width as i32 - padding, height as i32 - keyboard_height - padding];
+
+        let mut y = rect.min.y + padding;
+        let title_label = Label::new(rect![rect.min.x + padding, y, rect.max.x - padding, y + row_height],
+                                     title, Align::Center);
+        children.push(Box::new(title_label) as Box<dyn View>);
+        y += row_height + padding;
+
+        for (label, input_id, text) in [(LABEL_TAGS, ViewId::BookmarkTagsInput, &form.tags),
+                                        (LABEL_COLLECTIONS, ViewId::BookmarkCollectionsInput, &form.collections)] {
+            let label = Label::new(rect![rect.min.x + padding, y, rect.min.x + padding + label_width, y + row_height],
+                                   label.to_string(), Align::Left(0));
+            children.push(Box::new(label) as Box<dyn View>);
+            let input_field = InputField::new(rect![rect.min.x + 2 * padding + label_width, y,
+                                                    rect.max.x - padding, y + row_height],
+                                              input_id)
+                                         .text(text, context);
+            children.push(Box::new(input_field) as Box<dyn View>);
+            y += row_height + padding;
+        }
+
+        let middle_x = (rect.min.x + rect.max.x) / 2;
+        let private_button = Button::new(rect![rect.min.x + padding, y, middle_x - padding / 2, y + row_height],
+                                         Event::Toggle(ViewId::BookmarkPrivate), flag_label("Private", form.private));
+        children.push(Box::new(private_button) as Box<dyn View>);
+        let rec_button = Button::new(rect![middle_x + padding / 2, y, rect.max.x - padding, y + row_height],
+                                     Event::Toggle(ViewId::BookmarkRec), flag_label("Rec", form.rec));
+        children.push(Box::new(rec_button) as Box<dyn View>);
+        y += row_height + padding;
+
+        let notes_field = InputField::new(rect![rect.min.x + padding, y,
+                                                rect.max.x - padding, rect.max.y - row_height - 2 * padding],
+                                          ViewId::BookmarkNotesInput)
+                                     .multi_line(true)
+                                     .placeholder("Notes")
+                                     .text(&form.notes, context);
+        children.push(Box::new(notes_field) as Box<dyn View>);
+
+        let cancel_button = Button::new(rect![rect.min.x + padding, rect.max.y - row_height - padding,
+                                              rect.min.x + padding + button_width, rect.max.y - padding],
+                                        Event::Close(ViewId::BookmarkEditor), LABEL_CANCEL.to_string());
+        children.push(Box::new(cancel_button) as Box<dyn View>);
+
+        let save_button = Button::new(rect![rect.max.x - padding - button_width, rect.max.y - row_height - padding,
+                                            rect.max.x - padding, rect.max.y - padding],
+                                      Event::Validate, LABEL_SAVE.to_string());
+        children.push(Box::new(save_button) as Box<dyn View>);
+
+        if existing {
+            let delete_button = Button::new(rect![middle_x - button_width / 2, rect.max.y - row_height - padding,
+                                                  middle_x + button_width / 2, rect.max.y - padding],
+                                            Event::DeleteBookmark, LABEL_DELETE.to_string());
+            children.push(Box::new(delete_button) as Box<dyn View>);
+        }
+
+        BookmarkEditor {
+            id,
+            rect,
+            children,
+            view_id: ViewId::BookmarkEditor,
+            pseud_id: form.pseud_id,
+            private: form.private,
+            rec: form.rec,
+        }
+    }
+
+    fn field(&self, index: usize) -> String {
+        self.children[index].downcast_ref::<InputField>()
+            .map(|input_field| input_field.value().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn form(&self) -> BookmarkForm {
+        BookmarkForm {
+            pseud_id: self.pseud_id.clone(),
+            notes: self.field(NOTES_INDEX),
+            tags: self.field(TAGS_INDEX),
+            collections: self.field(COLLECTIONS_INDEX),
+            private: self.private,
+            rec: self.rec,
+        }
+    }
+
+    fn toggle_flag(&mut self, view_id: ViewId, rq: &mut RenderQueue) {
+        let (index, label) = if view_id == ViewId::BookmarkPrivate {
+            self.private = !self.private;
+            (PRIVATE_INDEX, flag_label("Private", self.private))
+        } else {
+            self.rec = !self.rec;
+            (REC_INDEX, flag_label("Rec", self.rec))
+        };
+        let rect = *self.children[index].rect();
+        let button = Button::new(rect, Event::Toggle(view_id), label);
+        rq.add(RenderData::new(button.id(), rect, UpdateMode::Gui));
+        self.children[index] = Box::new(button) as Box<dyn View>;
+    }
+}
+
+impl View for BookmarkEditor {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Validate => {
+                bus.push_back(Event::SaveBookmark(Box::new(self.form())));
+                true
+            },
+            Event::Toggle(view_id @ (ViewId::BookmarkPrivate | ViewId::BookmarkRec)) => {
+                self.toggle_flag(view_id, rq);
+                true
+            },
+            Event::Submit(ViewId::BookmarkTagsInput, _) => {
+                hub.send(Event::Focus(Some(ViewId::BookmarkCollectionsInput))).ok();
+                true
+            },
+            Event::Submit(ViewId::BookmarkCollectionsInput, _) => {
+                hub.send(Event::Focus(Some(ViewId::BookmarkNotesInput))).ok();
+                true
+            },
+            // The keyboard lies outside of the editor.
+            Event::Gesture(GestureEvent::Tap(center)) |
+            Event::Gesture(GestureEvent::HoldFingerShort(center, _)) |
+            Event::Gesture(GestureEvent::HoldFingerLong(center, _)) => !context.kb_rect.includes(center),
+            Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
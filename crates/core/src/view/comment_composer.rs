@@ -0,0 +1,204 @@
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::Framebuffer;
+use crate::font::{Fonts, font_from_style, NORMAL_STYLE};
+use crate::geom::{Rectangle, CornerSpec, BorderSpec};
+use crate::gesture::GestureEvent;
+use crate::http::comments::CommentForm;
+use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, ViewId, Align};
+use super::{THICKNESS_LARGE, BORDER_RADIUS_MEDIUM, SMALL_BAR_HEIGHT, BIG_BAR_HEIGHT};
+use super::button::Button;
+use super::label::Label;
+use super::input_field::InputField;
+use crate::unit::scale_by_dpi;
+use crate::color::{BLACK, WHITE};
+use crate::context::Context;
+
+const LABEL_POST: &str = "Post";
+const LABEL_CANCEL: &str = "Cancel";
+const LABEL_NAME: &str = "Name";
+const LABEL_EMAIL: &str = "Email";
+
+// Writes a comment above the keyboard. Guests sign it with a name and an
+// email address.
+#[derive(Clone)]
+pub struct CommentComposer {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    view_id: ViewId,
+    // Where the comment is posted.
+    post_path: String,
+    guest: bool,
+}
+
+impl CommentComposer {
+    pub fn new(title: String, post_path: String, draft: CommentForm, guest: bool, context: &mut Context) -> CommentComposer {
+        let id = ID_FEEDER.next();
+        let dpi = CURRENT_DEVICE.dpi;
+        let (width, height) = context.display.dims;
+        let mut children = Vec::new();
+
+        let font = font_from_style(&mut context.fonts, &NORMAL_STYLE, dpi);
+        let x_height = font.x_heights.0 as i32;
+        let padding = font.em() as i32;
+        let row_height = 4 * x_height;
+        let label_width = font.plan(LABEL_NAME, None, None).width
+                              .max(font.plan(LABEL_EMAIL, None, None).width) + padding;
+        let button_width = font.plan(LABEL_CANCEL, None, None).width
+                               .max(font.plan(LABEL_POST, None, None).width) + 2 * padding;
+
+        // The keyboard shows up above the bottom bar.
+        let keyboard_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32 + 3 * scale_by_dpi(BIG_BAR_HEIGHT, dpi) as i32;
+        let rect = rect![padding, padding,
+                         width as i32 - padding, height as i32 - keyboard_height - padding];
+
+        let mut y = rect.min.y + padding;
+        let title_label = Label::new(rect![rect.min.x + padding, y, rect.max.x - padding, y + row_height],
+                                     title, Align::Center);
+        children.push(Box::new(title_label) as Box<dyn View>);
+        y += row_height + padding;
+
+        if guest {
+            for (label, input_id, text) in [(LABEL_NAME, ViewId::CommentNameInput, &draft.name),
+                                            (LABEL_EMAIL, ViewId::CommentEmailInput, &draft.email)] {
+                let label = Label::new(rect![rect.min.x + padding, y, rect.min.x + padding + label_width, y + row_height],
+                                       label.to_string(), Align::Left(0));
+                children.push(Box::new(label) as Box<dyn View>);
+                let input_field = InputField::new(rect![rect.min.x + 2 * padding + label_width, y,
+                                                        rect.max.x - padding, y + row_height],
+                                                  input_id)
+                                             .text(text, context);
+                children.push(Box::new(input_field) as Box<dyn View>);
+                y += row_height + padding;
+            }
+        }
+
+        let content_field = InputField::new(rect![rect.min.x + padding, y,
+                                                  rect.max.x - padding, rect.max.y - row_height - 2 * padding],
+                                            ViewId::CommentInput)
+                                       .multi_line(true)
+                                       .placeholder("Comment")
+                                       .text(&draft.content, context);
+        children.push(Box::new(content_field) as Box<dyn View>);
+
+        let cancel_button = Button::new(rect![rect.min.x + padding, rect.max.y - row_height - padding,
+                                              rect.min.x + padding + button_width, rect.max.y - padding],
+                                        Event::Close(ViewId::CommentComposer), LABEL_CANCEL.to_string());
+        children.push(Box::new(cancel_button) as Box<dyn View>);
+
+        let post_button = Button::new(rect![rect.max.x - padding - button_width, rect.max.y - row_height - padding,
+                                            rect.max.x - padding, rect.max.y - padding],
+                                      Event::Validate, LABEL_POST.to_string());
+        children.push(Box::new(post_button) as Box<dyn View>);
+
+        CommentComposer {
+            id,
+            rect,
+            children,
+            view_id: ViewId::CommentComposer,
+            post_path,
+            guest,
+        }
+    }
+
+    pub fn post_path(&self) -> &str {
+        &self.post_path
+    }
+
+    // The children are the title, a label and an input field for each part
+    // of a guest's signature, the comment's field and the buttons.
+    fn field(&self, index: usize) -> String {
+        self.children.get(index)
+            .and_then(|child| child.downcast_ref::<InputField>())
+            .map(|input_field| input_field.value().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn form(&self) -> CommentForm {
+        if self.guest {
+            CommentForm {
+                name: self.field(2),
+                email: self.field(4),
+                content: self.field(5),
+                .. Default::default()
+            }
+        } else {
+            CommentForm {
+                content: self.field(1),
+                .. Default::default()
+            }
+        }
+    }
+
+    pub fn first_input(&self) -> ViewId {
+        if self.guest && self.field(2).is_empty() {
+            ViewId::CommentNameInput
+        } else {
+            ViewId::CommentInput
+        }
+    }
+}
+
+impl View for CommentComposer {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Validate => {
+                bus.push_back(Event::PostComment(self.post_path.clone(), Box::new(self.form())));
+                true
+            },
+            Event::Submit(ViewId::CommentNameInput, _) => {
+                hub.send(Event::Focus(Some(ViewId::CommentEmailInput))).ok();
+                true
+            },
+            Event::Submit(ViewId::CommentEmailInput, _) => {
+                hub.send(Event::Focus(Some(ViewId::CommentInput))).ok();
+                true
+            },
+            // The keyboard lies outside of the composer.
+            Event::Gesture(GestureEvent::Tap(center)) |
+            Event::Gesture(GestureEvent::HoldFingerShort(center, _)) |
+            Event::Gesture(GestureEvent::HoldFingerLong(center, _)) => !context.kb_rect.includes(center),
+            Event::Gesture(..) => true,
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, _fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let border_radius = scale_by_dpi(BORDER_RADIUS_MEDIUM, dpi) as i32;
+        let border_thickness = scale_by_dpi(THICKNESS_LARGE, dpi) as u16;
+        fb.draw_rounded_rectangle_with_border(&self.rect,
+                                              &CornerSpec::Uniform(border_radius),
+                                              &BorderSpec { thickness: border_thickness,
+                                                            color: BLACK },
+                                              &WHITE);
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
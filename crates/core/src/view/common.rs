@@ -7,6 +7,8 @@ use crate::metadata::{ReaderInfo, TextAlign};
 use crate::framebuffer::UpdateMode;
 use crate::font::family_names;
 use crate::geom::{Point, Rectangle};
+use crate::ao3_metadata::Ao3Info;
+use crate::http::subscriptions::{SubscriptionKind, user_path};
 use super::{View, RenderQueue, RenderData, ViewId, EntryId, EntryKind};
 use super::menu::{Menu, MenuKind};
 use super::notification::Notification;
@@ -49,6 +51,25 @@ pub fn overlapping_rectangle(view: &dyn View) -> Rectangle {
     rect
 }
 
+// Shows or updates the notification pinned while something is loading.
+pub fn show_loading(view: &mut dyn View, text: String, rq: &mut RenderQueue, context: &mut Context) {
+    if let Some(index) = locate_by_id(view, ViewId::LoadingNotif) {
+        if let Some(notif) = view.child_mut(index).downcast_mut::<Notification>() {
+            notif.update_text(text, rq, context);
+        }
+    } else {
+        let notif = Notification::pinned(ViewId::LoadingNotif, text, rq, context);
+        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+    }
+}
+
+pub fn hide_loading(view: &mut dyn View, rq: &mut RenderQueue) {
+    if let Some(index) = locate_by_id(view, ViewId::LoadingNotif) {
+        rq.add(RenderData::expose(overlapping_rectangle(view.child(index)), UpdateMode::Gui));
+        view.children_mut().remove(index);
+    }
+}
+
 // Transfer the notifications from the view1 to the view2.
 pub fn transfer_notifications(view1: &mut dyn View, view2: &mut dyn View, rq: &mut RenderQueue, context: &mut Context) {
     for index in (0..view1.len()).rev() {
@@ -142,10 +163,15 @@ pub fn toggle_main_menu(view: &mut dyn View, rect: Rectangle, enable: Option<boo
                             EntryKind::SubMenu("Margin Width".to_string(), margin_width_entries),
                             EntryKind::SubMenu("Font Family".to_string(), font_family_entries)];
 
+        let outbox = vec![EntryKind::Command("Show".to_string(), EntryId::ShowOutbox),
+                          EntryKind::Command("Retry Failed".to_string(), EntryId::RetryOutbox),
+                          EntryKind::Command("Discard Failed".to_string(), EntryId::DiscardOutbox)];
+
         let mut entries = vec![EntryKind::Command("About".to_string(),
                                                   EntryId::About),
                                EntryKind::Command("System Info".to_string(),
                                                   EntryId::SystemInfo),
+                               EntryKind::SubMenu("Pending Actions".to_string(), outbox),
                                EntryKind::Separator,
                                EntryKind::SubMenu("Display Settings".to_string(), reader_set),
                                EntryKind::CheckBox("Invert Colors".to_string(),
@@ -169,7 +195,7 @@ pub fn toggle_main_menu(view: &mut dyn View, rect: Rectangle, enable: Option<boo
                 EntryKind::RadioButton(ButtonScheme::Natural.to_string(), EntryId::SetButtonScheme(ButtonScheme::Natural), button_scheme == ButtonScheme::Natural),
                 EntryKind::RadioButton(ButtonScheme::Inverted.to_string(), EntryId::SetButtonScheme(ButtonScheme::Inverted), button_scheme == ButtonScheme::Inverted),
             ];
-            entries.insert(5, EntryKind::SubMenu("Button Scheme".to_string(), button_schemes));
+            entries.insert(6, EntryKind::SubMenu("Button Scheme".to_string(), button_schemes));
         }
 
         if CURRENT_DEVICE.has_gyroscope() {
@@ -181,7 +207,7 @@ pub fn toggle_main_menu(view: &mut dyn View, rect: Rectangle, enable: Option<boo
                 EntryKind::RadioButton("Landscape".to_string(), EntryId::SetRotationLock(Some(RotationLock::Landscape)), rotation_lock == Some(RotationLock::Landscape)),
                 EntryKind::RadioButton("Ignore".to_string(), EntryId::SetRotationLock(Some(RotationLock::Current)), rotation_lock == Some(RotationLock::Current)),
             ];
-            entries.insert(5, EntryKind::SubMenu("Gyroscope".to_string(), gyro));
+            entries.insert(6, EntryKind::SubMenu("Gyroscope".to_string(), gyro));
         }
 
         let main_menu = Menu::new(rect, ViewId::MainMenu, MenuKind::DropDown, entries, context);
@@ -292,3 +318,30 @@ pub fn toggle_keyboard_layout_menu(view: &mut dyn View, rect: Rectangle, enable:
         view.children_mut().push(Box::new(keyboard_layout_menu) as Box<dyn View>);
     }
 }
+
+// What can be subscribed to from a work: itself, its series and its authors.
+pub fn subscription_entries(info: &Ao3Info) -> Vec<EntryKind> {
+    let mut targets = vec![(SubscriptionKind::Work, format!("/works/{}", info.id), info.title.clone())];
+    for part in &info.series {
+        if SubscriptionKind::from_path(&part.series.location) == Some(SubscriptionKind::Series) {
+            targets.push((SubscriptionKind::Series, part.series.location.clone(), part.series.title.clone()));
+        }
+    }
+    for author in &info.authors {
+        if let Some(path) = user_path(&author.location) {
+            targets.push((SubscriptionKind::User, path, author.title.clone()));
+        }
+    }
+
+    let entries = |subscribe: bool| targets.iter().map(|(kind, path, name)| {
+        let id = if subscribe {
+            EntryId::Subscribe(path.clone(), name.clone())
+        } else {
+            EntryId::Unsubscribe(path.clone(), name.clone())
+        };
+        EntryKind::Command(format!("{}: {}", kind.label(), name), id)
+    }).collect::<Vec<EntryKind>>();
+
+    vec![EntryKind::SubMenu("Subscribe To".to_string(), entries(true)),
+         EntryKind::SubMenu("Unsubscribe From".to_string(), entries(false))]
+}
@@ -52,7 +52,7 @@ impl Home {
 
 
     pub fn new(rect: Rectangle, rq: &mut RenderQueue,
-               format: String, fonts: &mut Fonts, battery: &mut Box<dyn Battery>, frontlight: bool, logged_in: bool, faves: &Vec<(String, Url)>) -> Home {
+               format: String, fonts: &mut Fonts, battery: &mut Box<dyn Battery>, frontlight: bool, logged_in: bool, username: Option<&str>, faves: &[(String, Url)]) -> Home {
         let mut home = Home::new_empty(rect);
 
         home.create_background();
@@ -60,14 +60,17 @@ impl Home {
         home.create_top_bar(format, fonts, battery, frontlight);
         let top_bar = &home.children[rlocate::<TopBar>(&home).unwrap()];
 
-        // TODO add login/logged in section
-
         let mut top_pos = top_bar.rect().height() as i32;
 
+        home.create_login_status(top_pos, logged_in, username);
+        top_pos = home.children[home.children.len() - 1].rect().max.y;
+
         // Link to 'Marked for Later' view
         if logged_in {
             home.create_marked_for_later(top_pos);
             top_pos = home.children[home.children.len() - 1].rect().max.y;
+            home.create_subscriptions(top_pos);
+            top_pos = home.children[home.children.len() - 1].rect().max.y;
         }
 
         // TODO - make this actually the bottom bar after refactoring search to not be
@@ -119,6 +122,17 @@ impl Home {
         self.children.push(Box::new(bottom_bar) as Box<dyn View>);
     }
 
+    fn create_login_status(&mut self, top_pos: i32, logged_in: bool, username: Option<&str>) {
+        let (title, event) = match (logged_in, username) {
+            (true, Some(username)) => (format!("Log out ({})", username), Event::Logout),
+            (true, None) => ("Log out".to_string(), Event::Logout),
+            (false, _) => ("Log in".to_string(), Event::Login),
+        };
+        let login_status = Fave::new(self.rect, top_pos, title, event);
+
+        self.children.push(Box::new(login_status) as Box<dyn View>);
+    }
+
     fn create_marked_for_later(&mut self, top_pos: i32) {
         let marked_for_later = Fave::new(
             self.rect, top_pos,
@@ -128,6 +142,15 @@ impl Home {
         self.children.push(Box::new(marked_for_later) as Box<dyn View>);
     }
 
+    fn create_subscriptions(&mut self, top_pos: i32) {
+        let subscriptions = Fave::new(
+            self.rect, top_pos,
+            "Subscriptions".to_string(),
+            Event::LoadSubscriptions);
+
+        self.children.push(Box::new(subscriptions) as Box<dyn View>);
+    }
+
     fn create_fav_search(&mut self, fave: (String, Url), top_pos: i32) {
         let fave = Fave::new(
             self.rect, top_pos,
@@ -43,6 +43,32 @@ fn WHEN_createMarkedForLaterIsCalled_THEN_aMarkedForLaterLabelIsAddedToChildren(
     let _label = home.child_mut(0).downcast_mut::<Fave>().unwrap();
 }
 
+#[test]
+#[allow(non_snake_case)]
+#[coverage(off)]
+fn WHEN_createSubscriptionsIsCalled_THEN_aSubscriptionsLabelIsAddedToChildren() {
+    // WHEN create_subscriptions is called
+    let mut home = Home::new_empty(rect![0, 0, 600, 800]);
+    home.create_subscriptions(5);
+    // THEN a subscriptions label is added to children
+    assert_eq!(home.children.len(), 1);
+    assert_eq!(home.children[0].rect(), &rect![0, 5, 600, 62]);
+    let _label = home.child_mut(0).downcast_mut::<Fave>().unwrap();
+}
+
+#[test]
+#[allow(non_snake_case)]
+#[coverage(off)]
+fn WHEN_createLoginStatusIsCalled_THEN_aLoginStatusLabelIsAddedToChildren() {
+    // WHEN create_login_status is called
+    let mut home = Home::new_empty(rect![0, 0, 600, 800]);
+    home.create_login_status(5, true, Some("reader"));
+    // THEN a login status label is added to children
+    assert_eq!(home.children.len(), 1);
+    assert_eq!(home.children[0].rect(), &rect![0, 5, 600, 62]);
+    let _label = home.child_mut(0).downcast_mut::<Fave>().unwrap();
+}
+
 #[test]
 #[allow(non_snake_case)]
 #[coverage(off)]
@@ -59,19 +85,19 @@ fn WHEN_createFaveSearchIsCalled_THEN_aFaveLabelIsAddedToChildren() {
 #[test]
 #[allow(non_snake_case)]
 #[coverage(off)]
-fn GIVEN_loggedInUser_WHEN_homeNewIsCalled_THEN_aHomePageWithTheStandardChildrenPlusMarkedForLaterIsCreated() {
+fn GIVEN_loggedInUser_WHEN_homeNewIsCalled_THEN_aHomePageWithTheStandardChildrenPlusMarkedForLaterAndSubscriptionsIsCreated() {
     // WHEN Home::new() is called
     let mut battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let mut rq = RenderQueue::new();
     let home = Home::new(rect![0, 0, 600, 800], &mut rq, "%H:%M".to_string(), &mut Fonts::load_with_prefix("../../").unwrap(),
-                                &mut battery, true, true, &vec![("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
+                                &mut battery, true, true, Some("reader"), &[("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
 
-    // THEN a home with the standard children plus a marked for later fave is called
+    // THEN a home with the standard children plus marked for later and subscriptions faves is created
     assert_eq!(locate::<Filler>(&home).unwrap(), 0);
     assert_eq!(locate::<TopBar>(&home).unwrap(), 1);
-    assert_eq!(locate::<Fave>(&home).unwrap(), 2); // marked for later
-    assert_eq!(rlocate::<Fave>(&home).unwrap(), 3); // test fave
-    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 5);
+    assert_eq!(locate::<Fave>(&home).unwrap(), 2); // login status
+    assert_eq!(rlocate::<Fave>(&home).unwrap(), 5); // test fave
+    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 7);
 }
 
 #[test]
@@ -81,16 +107,16 @@ fn WHEN_openSearchBarIsCalled_THEN_aSearchBarAndAKeyboardAreCreated() {
     let mut battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let mut rq = RenderQueue::new();
     let mut home = Home::new(rect![0, 0, 600, 800], &mut rq, "%H:%M".to_string(), &mut Fonts::load_with_prefix("../../").unwrap(),
-                                &mut battery, true, true, &vec![("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
+                                &mut battery, true, true, Some("reader"), &[("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
     let mut keyboard_layouts = BTreeMap::new();
     keyboard_layouts.insert("test_keyboard".to_string(), Layout::default());
     // WHEN open_search_bar() is called
     home.open_search_bar(&keyboard_layouts, "test_keyboard".to_string(), &mut rq);
     // THEN a search bar and keyboard are created
     // Ignore all the normal children before the search bar
-    assert_eq!(locate::<SearchBar>(&home).unwrap(), 4);
-    assert_eq!(locate::<Keyboard>(&home).unwrap(), 5);
-    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 7);
+    assert_eq!(locate::<SearchBar>(&home).unwrap(), 6);
+    assert_eq!(locate::<Keyboard>(&home).unwrap(), 7);
+    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 9);
 }
 
 #[test]
@@ -101,7 +127,7 @@ fn GIVEN_anOpenSearchBar_WHEN_closeSearchBarIsCalled_THEN_noSearchBarExists() {
     let mut battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let mut rq = RenderQueue::new();
     let mut home = Home::new(rect![0, 0, 600, 800], &mut rq, "%H:%M".to_string(), &mut Fonts::load_with_prefix("../../").unwrap(),
-                                &mut battery, true, true, &vec![("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
+                                &mut battery, true, true, Some("reader"), &[("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
     let mut keyboard_layouts = BTreeMap::new();
     keyboard_layouts.insert("test_keyboard".to_string(), Layout::default());
     home.open_search_bar(&keyboard_layouts, "test_keyboard".to_string(), &mut rq);
@@ -111,7 +137,7 @@ fn GIVEN_anOpenSearchBar_WHEN_closeSearchBarIsCalled_THEN_noSearchBarExists() {
     // Ignore all the normal children before the search bar
     assert_eq!(locate::<SearchBar>(&home), None);
     assert_eq!(locate::<Keyboard>(&home), None);
-    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 5);
+    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 7);
 }
 
 #[test]
@@ -121,7 +147,7 @@ fn WHEN_handleSearchEventsIsCalledWithEventToggleSearchBar_THEN_aSearchBarAndAKe
     let mut battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let mut rq = RenderQueue::new();
     let mut home = Home::new(rect![0, 0, 600, 800], &mut rq, "%H:%M".to_string(), &mut Fonts::load_with_prefix("../../").unwrap(),
-                                &mut battery, true, true, &vec![("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
+                                &mut battery, true, true, Some("reader"), &[("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
     let mut keyboard_layouts = BTreeMap::new();
     keyboard_layouts.insert("test_keyboard".to_string(), Layout::default());
     let (tx, _rx) = mpsc::channel();
@@ -129,9 +155,9 @@ fn WHEN_handleSearchEventsIsCalledWithEventToggleSearchBar_THEN_aSearchBarAndAKe
     home.handle_search_events(&Event::Toggle(ViewId::SearchBar), &keyboard_layouts, "test_keyboard".to_string(), &mut rq, &tx);
     // THEN a search bar and keyboard are created
     // Ignore all the normal children before the search bar
-    assert_eq!(locate::<SearchBar>(&home).unwrap(), 4);
-    assert_eq!(locate::<Keyboard>(&home).unwrap(), 5);
-    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 7);
+    assert_eq!(locate::<SearchBar>(&home).unwrap(), 6);
+    assert_eq!(locate::<Keyboard>(&home).unwrap(), 7);
+    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 9);
 }
 
 #[test]
@@ -142,7 +168,7 @@ fn GIVEN_anOpenSearchBar_WHEN_handleSearchEventsIsCalledWithEventToggleSearchBar
     let mut battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let mut rq = RenderQueue::new();
     let mut home = Home::new(rect![0, 0, 600, 800], &mut rq, "%H:%M".to_string(), &mut Fonts::load_with_prefix("../../").unwrap(),
-                                &mut battery, true, true, &vec![("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
+                                &mut battery, true, true, Some("reader"), &[("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
     let mut keyboard_layouts = BTreeMap::new();
     keyboard_layouts.insert("test_keyboard".to_string(), Layout::default());
     home.open_search_bar(&keyboard_layouts, "test_keyboard".to_string(), &mut rq);
@@ -153,7 +179,7 @@ fn GIVEN_anOpenSearchBar_WHEN_handleSearchEventsIsCalledWithEventToggleSearchBar
     // Ignore all the normal children before the search bar
     assert_eq!(locate::<SearchBar>(&home), None);
     assert_eq!(locate::<Keyboard>(&home), None);
-    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 5);
+    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 7);
 }
 
 #[test]
@@ -164,7 +190,7 @@ fn GIVEN_anOpenSearchBar_WHEN_handleSearchEventsIsCalledWithEventCloseSearchBar_
     let mut battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let mut rq = RenderQueue::new();
     let mut home = Home::new(rect![0, 0, 600, 800], &mut rq, "%H:%M".to_string(), &mut Fonts::load_with_prefix("../../").unwrap(),
-                                &mut battery, true, true, &vec![("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
+                                &mut battery, true, true, Some("reader"), &[("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
     let mut keyboard_layouts = BTreeMap::new();
     keyboard_layouts.insert("test_keyboard".to_string(), Layout::default());
     home.open_search_bar(&keyboard_layouts, "test_keyboard".to_string(), &mut rq);
@@ -175,7 +201,7 @@ fn GIVEN_anOpenSearchBar_WHEN_handleSearchEventsIsCalledWithEventCloseSearchBar_
     // Ignore all the normal children before the search bar
     assert_eq!(locate::<SearchBar>(&home), None);
     assert_eq!(locate::<Keyboard>(&home), None);
-    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 5);
+    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 7);
 }
 
 #[test]
@@ -186,7 +212,7 @@ fn GIVEN_anOpenSearchBar_WHEN_handleSearchEventsIsCalledWithEventSubmitSiteTextS
     let mut battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let mut rq = RenderQueue::new();
     let mut home = Home::new(rect![0, 0, 600, 800], &mut rq, "%H:%M".to_string(), &mut Fonts::load_with_prefix("../../").unwrap(),
-                                &mut battery, true, true, &vec![("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
+                                &mut battery, true, true, Some("reader"), &[("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
     let mut keyboard_layouts = BTreeMap::new();
     keyboard_layouts.insert("test_keyboard".to_string(), Layout::default());
     home.open_search_bar(&keyboard_layouts, "test_keyboard".to_string(), &mut rq);
@@ -199,7 +225,7 @@ fn GIVEN_anOpenSearchBar_WHEN_handleSearchEventsIsCalledWithEventSubmitSiteTextS
     // Ignore all the normal children before the search bar
     assert_eq!(locate::<SearchBar>(&home), None);
     assert_eq!(locate::<Keyboard>(&home), None);
-    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 5);
+    assert_eq!(rlocate::<BottomBar>(&home).unwrap(), 7);
     // AND an Event::LoadSearch was sent
     match rx.recv() {
         Ok(Event::LoadSearch(search_text)) => assert_eq!(search_text, "fake_search".to_string()),
@@ -216,7 +242,7 @@ fn GIVEN_anOpenSearchBar_WHEN_handleSearchEventsIsCalledWithEventSubmitSiteTextS
     let mut battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let mut rq = RenderQueue::new();
     let mut home = Home::new(rect![0, 0, 600, 800], &mut rq, "%H:%M".to_string(), &mut Fonts::load_with_prefix("../../").unwrap(),
-                                &mut battery, true, true, &vec![("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
+                                &mut battery, true, true, Some("reader"), &[("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
     let mut keyboard_layouts = BTreeMap::new();
     keyboard_layouts.insert("test_keyboard".to_string(), Layout::default());
     home.open_search_bar(&keyboard_layouts, "test_keyboard".to_string(), &mut rq);
@@ -241,7 +267,7 @@ fn GIVEN_anOpenSearchBar_WHEN_handleSearchEventsIsCalledWithEventFocus_THEN_focu
     let mut battery = Box::new(FakeBattery::new()) as Box<dyn Battery>;
     let mut rq = RenderQueue::new();
     let mut home = Home::new(rect![0, 0, 600, 800], &mut rq, "%H:%M".to_string(), &mut Fonts::load_with_prefix("../../").unwrap(),
-                                &mut battery, true, true, &vec![("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
+                                &mut battery, true, true, Some("reader"), &[("Test Fave".to_string(), Url::parse("https://fakeo3.org/tags/super-fake").expect("Test URL"))]);
     let mut keyboard_layouts = BTreeMap::new();
     keyboard_layouts.insert("test_keyboard".to_string(), Layout::default());
     home.open_search_bar(&keyboard_layouts, "test_keyboard".to_string(), &mut rq);
@@ -25,7 +25,7 @@ lazy_static! {
                      "return", "shift", "combine", "alternate", "delete-backward", "delete-forward",
                      "move-backward", "move-backward-short", "move-forward", "move-forward-short",
                      "close",  "check_mark-small", "check_mark", "check_mark-large", "bullet",
-                     "arrow-left", "arrow-right", "angle-down", "angle-up", "angle-down-grey",
+                     "arrow-left", "arrow-right", "arrow-down", "angle-down", "angle-up", "angle-down-grey",
                      "angle-up-grey", "crop", "toc", "toc-grey", "font_family",
                      "font_size", "line_height", "align-justify", "align-left", "align-right",
                      "align-center", "margin", "plug", "enclosed_menu", "contrast", "gray",
@@ -33,7 +33,7 @@ lazy_static! {
                      "explicit", "mature", "teen", "general",
                      "complete", "wip", "blank",
                      "other", "femslash", "slash", "het", "multi", "gen",
-                     "warning", "chosenotto", "external"].iter().cloned() {
+                     "warning", "chosenotto", "external", "notes", "comments"].iter().cloned() {
             let path = dir.join(&format!("{}.svg", name));
             let doc = PdfOpener::new().and_then(|o| o.open(path)).unwrap();
             let pixmap = doc.page(0).and_then(|p| p.pixmap(scale)).unwrap();
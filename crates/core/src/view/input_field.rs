@@ -3,7 +3,7 @@ use crate::framebuffer::{Framebuffer, UpdateMode};
 use super::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData, KeyboardEvent, ViewId, EntryId, TextKind};
 use super::THICKNESS_MEDIUM;
 use crate::gesture::GestureEvent;
-use crate::font::{Fonts, font_from_style, NORMAL_STYLE, FONT_SIZES};
+use crate::font::{Fonts, Font, RenderPlan, font_from_style, NORMAL_STYLE, FONT_SIZES};
 use crate::geom::{Rectangle, Point, LinearDir, BorderSpec, halves};
 use crate::color::{TEXT_NORMAL, BLACK};
 use crate::context::Context;
@@ -21,6 +21,7 @@ pub struct InputField {
     cursor: usize,
     border: bool,
     focused: bool,
+    multi_line: bool,
 }
 
 fn closest_char_boundary(text: &str, index: usize, dir: LinearDir) -> Option<usize> {
@@ -90,9 +91,17 @@ impl InputField {
             cursor: 0,
             border: true,
             focused: false,
+            multi_line: false,
         }
     }
 
+    // The return key starts a new line, and the text wraps within the
+    // field instead of scrolling sideways.
+    pub fn multi_line(mut self, multi_line: bool) -> InputField {
+        self.multi_line = multi_line;
+        self
+    }
+
     pub fn border(mut self, border: bool) -> InputField {
         self.border = border;
         self
@@ -121,6 +130,10 @@ impl InputField {
         }
     }
 
+    pub fn value(&self) -> &str {
+        &self.text
+    }
+
     pub fn text_before_cursor(&self) -> &str {
         &self.text[..self.cursor]
     }
@@ -183,12 +196,58 @@ impl InputField {
         }
     }
 
+    // The lines of a multi-line field, with the index of their first char.
+    fn wrapped_lines(&self, font: &mut Font, max_width: i32) -> Vec<(usize, RenderPlan)> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for paragraph in self.text.split('\n') {
+            let mut plan = font.plan(paragraph, None, Some(&["-liga".to_string()]));
+            let mut line_start = start;
+            while plan.width > max_width {
+                let (index, width) = font.cut_point(&plan, max_width);
+                if index == 0 {
+                    break;
+                }
+                let next_plan = plan.split_off(index, width);
+                lines.push((line_start, plan));
+                line_start += index;
+                plan = next_plan;
+            }
+            lines.push((line_start, plan));
+            start += paragraph.chars().count() + 1;
+        }
+        lines
+    }
+
+    // The line the cursor is on, and the first line shown: the lines
+    // scroll to keep the cursor in view.
+    fn cursor_line(&self, lines: &[(usize, RenderPlan)], visible_lines: usize) -> (usize, usize) {
+        let index = char_position(&self.text, self.cursor).unwrap_or_else(|| self.text.chars().count());
+        let line = lines.iter().rposition(|(start, _)| *start <= index).unwrap_or(0);
+        (line, (line + 1).saturating_sub(visible_lines.max(1)))
+    }
+
+    fn multi_line_index_from_position(&self, position: Point, font: &mut Font) -> usize {
+        let padding = font.em() as i32;
+        let line_height = font.line_height();
+        let max_width = self.rect.width().saturating_sub(2 * padding as u32) as i32;
+        let visible_lines = ((self.rect.height() as i32 - padding) / line_height) as usize;
+        let lines = self.wrapped_lines(font, max_width);
+        let (_, first_line) = self.cursor_line(&lines, visible_lines);
+        let row = ((position.y - self.rect.min.y - padding / 2).max(0) / line_height) as usize;
+        let (start, plan) = &lines[(first_line + row).min(lines.len() - 1)];
+        start + plan.index_from_advance(position.x - self.rect.min.x - padding)
+    }
+
     fn index_from_position(&self, position: Point, fonts: &mut Fonts) -> usize {
         if self.text.is_empty() {
             return 0;
         }
         let dpi = CURRENT_DEVICE.dpi;
         let font = font_from_style(fonts, &NORMAL_STYLE, dpi);
+        if self.multi_line {
+            return self.multi_line_index_from_position(position, font);
+        }
         let padding = font.em() as i32;
         let max_width = self.rect.width().saturating_sub(2 * padding as u32) as i32;
         let mut plan = font.plan(&self.text, None, Some(&["-liga".to_string()]));
@@ -198,6 +257,53 @@ impl InputField {
     }
 }
 
+impl InputField {
+    fn render_lines(&self, fb: &mut dyn Framebuffer, font: &mut Font, thickness: i32) {
+        let dpi = CURRENT_DEVICE.dpi;
+        let padding = font.em() as i32;
+        let line_height = font.line_height();
+        let max_width = self.rect.width().saturating_sub(2 * padding as u32) as i32;
+        let visible_lines = ((self.rect.height() as i32 - padding) / line_height) as usize;
+        let top = self.rect.min.y + padding / 2;
+        let ascender = font.ascender();
+
+        if self.text.is_empty() {
+            let plan = font.plan(&self.placeholder, Some(max_width), None);
+            font.render(fb, TEXT_NORMAL[2], &plan, pt!(self.rect.min.x + padding, top + ascender));
+        }
+
+        let lines = self.wrapped_lines(font, max_width);
+        let (cursor_line, first_line) = self.cursor_line(&lines, visible_lines);
+
+        for (row, (_, plan)) in lines.iter().skip(first_line).take(visible_lines).enumerate() {
+            let pt = pt!(self.rect.min.x + padding, top + row as i32 * line_height + ascender);
+            font.render(fb, TEXT_NORMAL[1], plan, pt);
+        }
+
+        if !self.focused {
+            return;
+        }
+
+        let index = char_position(&self.text, self.cursor).unwrap_or_else(|| self.text.chars().count());
+        let (start, plan) = &lines[cursor_line];
+        let dx = plan.total_advance(index - start);
+        let y_min = top + (cursor_line - first_line) as i32 * line_height;
+
+        fb.draw_rectangle(&rect![self.rect.min.x + padding + dx,
+                                 y_min,
+                                 self.rect.min.x + padding + dx + thickness,
+                                 y_min + line_height],
+                          BLACK);
+
+        if !self.partial.is_empty() {
+            font.set_size(FONT_SIZES[0], dpi);
+            let plan = font.plan(&self.partial, None, None);
+            let pt = pt!(self.rect.min.x + padding + dx + 3 * thickness, y_min + line_height);
+            font.render(fb, TEXT_NORMAL[1], &plan, pt);
+        }
+    }
+}
+
 impl View for InputField {
     fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
         match *evt {
@@ -262,6 +368,10 @@ impl View for InputField {
                             TextKind::Extremum => self.extremum_delete(dir),
                         }
                     },
+                    KeyboardEvent::Submit if self.multi_line => {
+                        self.text.insert(self.cursor, '\n');
+                        self.cursor += 1;
+                    },
                     KeyboardEvent::Submit => {
                         println!("Submitting for {:?}", self.view_id);
                         bus.push_back(Event::Submit(self.view_id, self.text.clone()));
@@ -303,6 +413,11 @@ impl View for InputField {
                                       &BorderSpec { thickness: thickness as u16, color: BLACK });
         }
 
+        if self.multi_line {
+            self.render_lines(fb, font, thickness);
+            return;
+        }
+
         let (mut plan, foreground) = if self.text.is_empty() {
             (font.plan(&self.placeholder, Some(max_width), None),
              TEXT_NORMAL[2])
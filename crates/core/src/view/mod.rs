@@ -21,6 +21,8 @@ pub mod slider;
 pub mod input_field;
 pub mod page_label;
 pub mod named_input;
+pub mod comment_composer;
+pub mod bookmark_editor;
 pub mod labeled_icon;
 pub mod bottom_bar;
 pub mod top_bar;
@@ -47,6 +49,7 @@ pub mod textlabel;
 pub mod text;
 pub mod works;
 pub mod tag;
+pub mod rich_text;
 //pub mod htmlview;
 
 use std::ops::{Deref, DerefMut};
@@ -57,12 +60,13 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::collections::VecDeque;
 use std::fmt::{self, Debug};
 use fxhash::FxHashMap;
+use chrono::NaiveDate;
 use downcast_rs::{Downcast, impl_downcast};
 use dyn_clone::{DynClone, clone_trait_object};
 use url::Url;
 use crate::font::Fonts;
 use crate::document::{Location, TextLocation};
-use crate::settings::{ButtonScheme, FirstColumn, SecondColumn, RotationLock};
+use crate::settings::{ButtonScheme, FirstColumn, SecondColumn, RotationLock, NotesDisplay};
 use crate::metadata::{Info, ZoomMode, ScrollMode, SortMethod, TextAlign, SimpleStatus, PageScheme, Margin};
 use crate::geom::{LinearDir, CycleDir, Rectangle, Boundary};
 use crate::framebuffer::{Framebuffer, UpdateMode};
@@ -71,6 +75,14 @@ use crate::gesture::GestureEvent;
 use self::key::KeyKind;
 use self::works::HistoryView;
 use crate::ao3_metadata::Ao3Info;
+use crate::http::Ao3Error;
+use crate::http::pool::FetchId;
+use crate::http::outbox::{Ao3Action, ActionResult};
+use crate::http::DownloadFormat;
+use crate::http::comments::{CommentFields, CommentForm};
+use crate::http::BookmarkForm;
+use crate::http::subscriptions::Subscription;
+use self::works::workindex::{IndexData, PagesRequest, FetchedPages};
 use crate::context::Context;
 
 // Border thicknesses in pixels, at 300 DPI.
@@ -392,6 +404,10 @@ pub enum Event {
 
     // Ao3Reader-specific Events
     ToggleAboutWork(Ao3Info),
+    // The work's id.
+    ToggleWorkMenu(Rectangle, String),
+    // The path of what's subscribed to.
+    ToggleSubscriptionMenu(Rectangle, String),
     ShowOverlay(String),
     ToggleAbout,
     ToggleToc,
@@ -401,9 +417,41 @@ pub enum Event {
     GoToTag(String),
     LoadIndex(String),
     LoadHistory(HistoryView),
+    LoadSubscriptions,
     OpenWork(String),
     LoadSearch(String),
     SubmitInput(ViewId),
+    Login,
+    Logout,
+    FetchProgress(FetchId, String),
+    FetchFailed(FetchId, Ao3Error),
+    WorkFetched(FetchId, String, String),
+    // The path of a chapter of a work read a chapter at a time.
+    FetchChapter(String),
+    // The chapter's page, if it could be fetched.
+    ChapterFetched(String, Option<String>),
+    // A page of comments, as HTML, and its comment form's fields.
+    CommentsFetched(FetchId, String, Option<CommentFields>),
+    PostComment(String, Box<CommentForm>),
+    SaveBookmark(Box<BookmarkForm>),
+    DeleteBookmark,
+    // A work was added to or removed from the Marked for Later list.
+    MarkedForLaterChanged,
+    SubscriptionsChanged,
+    FetchLatestUpdates(Vec<Subscription>),
+    // The subscription's path and when it was last updated.
+    LatestUpdateFetched(String, NaiveDate),
+    IndexFetched(FetchId, Box<IndexData>),
+    LoadIndexPages(Box<PagesRequest>),
+    IndexPagesFetched(FetchId, Box<FetchedPages>),
+    // An index whose list changed on AO3.
+    ReloadIndex(Box<PagesRequest>),
+    IndexReloaded(Box<FetchedPages>),
+    // The action, its title and what AO3 answered.
+    ActionSent(Box<Ao3Action>, String, Result<Vec<String>, Ao3Error>),
+    SessionUpdated(FetchId),
+    OutboxReplayed(Vec<ActionResult>),
+    WorkDownloaded(FetchId, PathBuf),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -439,6 +487,22 @@ pub enum ViewId {
     ContrastExponentMenu,
     ContrastGrayMenu,
     LineHeightMenu,
+    DownloadMenu,
+    NotesMenu,
+    CommentsMenu,
+    BookmarkMenu,
+    WorkMenu,
+    SubscriptionMenu,
+    CommentComposer,
+    CommentNameInput,
+    CommentEmailInput,
+    CommentInput,
+    BookmarkEditor,
+    BookmarkTagsInput,
+    BookmarkCollectionsInput,
+    BookmarkNotesInput,
+    BookmarkPrivate,
+    BookmarkRec,
     DirectoryMenu,
     BookMenu,
     LibraryMenu,
@@ -473,9 +537,11 @@ pub enum ViewId {
     TopBottomBars,
     TableOfContents,
     MessageNotif(Id),
+    LoadingNotif,
     SubMenu(u8),
     Overlay,
     AboutOverlay,
+    NotesOverlay,
     ChapterList
 }
 
@@ -549,6 +615,21 @@ pub enum EntryKind {
 pub enum EntryId {
     About,
     SystemInfo,
+    ShowOutbox,
+    Download(String, DownloadFormat),
+    LoadComments(String),
+    ComposeComment(String),
+    // The work's id.
+    EditBookmark(String),
+    AboutWork(String),
+    // The work's id and title.
+    MarkForLater(String, String),
+    MarkAsRead(String, String),
+    // The path of a work, series or user, and its name.
+    Subscribe(String, String),
+    Unsubscribe(String, String),
+    RetryOutbox,
+    DiscardOutbox,
     LoadLibrary(usize),
     Load(PathBuf),
     Flush,
@@ -596,6 +677,7 @@ pub enum EntryId {
     SetTextAlign(TextAlign),
     SetMarginWidth(i32),
     SetLineHeight(i32),
+    SetNotesDisplay(NotesDisplay),
     SetContrastExponent(i32),
     SetContrastGray(i32),
     SetRotationLock(Option<RotationLock>),
@@ -606,6 +688,7 @@ pub enum EntryId {
     ToggleFuzzy,
     ToggleInverted,
     ToggleDithered,
+    ToggleWorkSkin,
     ToggleWifi,
     Rotate(i8),
     Launch(AppCmd),
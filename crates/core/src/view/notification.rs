@@ -30,13 +30,39 @@ impl Notification {
         let id = ID_FEEDER.next();
         let view_id = ViewId::MessageNotif(id);
         let hub2 = hub.clone();
-        let index = context.notification_index;
 
         thread::spawn(move || {
             thread::sleep(NOTIFICATION_CLOSE_DELAY);
             hub2.send(Event::Close(view_id)).ok();
         });
 
+        Notification::build(id, view_id, text, rq, context)
+    }
+
+    // Stays onscreen until its owner closes it.
+    pub fn pinned(view_id: ViewId, text: String, rq: &mut RenderQueue, context: &mut Context) -> Notification {
+        Notification::build(ID_FEEDER.next(), view_id, text, rq, context)
+    }
+
+    fn build(id: Id, view_id: ViewId, text: String, rq: &mut RenderQueue, context: &mut Context) -> Notification {
+        let index = context.notification_index;
+        let (rect, max_width) = Notification::layout(&text, index, context);
+
+        rq.add(RenderData::new(id, rect, UpdateMode::Gui));
+        context.notification_index = index.wrapping_add(1);
+
+        Notification {
+            id,
+            rect,
+            children: Vec::new(),
+            text,
+            max_width,
+            index,
+            view_id,
+        }
+    }
+
+    fn layout(text: &str, index: u8, context: &mut Context) -> (Rectangle, i32) {
         let dpi = CURRENT_DEVICE.dpi;
         let (width, _) = context.display.dims;
         let small_height = scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32;
@@ -46,7 +72,7 @@ impl Notification {
         let padding = font.em() as i32;
 
         let max_message_width = width as i32 - 5 * padding;
-        let plan = font.plan(&text, Some(max_message_width), None);
+        let plan = font.plan(text, Some(max_message_width), None);
 
         let dialog_width = plan.width + 3 * padding;
         let dialog_height = 7 * x_height;
@@ -59,21 +85,18 @@ impl Notification {
         };
         let dy = small_height + padding + (index % 3) as i32 * (dialog_height + padding);
 
-        let rect = rect![dx, dy,
-                         dx + dialog_width, dy + dialog_height];
-
-        rq.add(RenderData::new(id, rect, UpdateMode::Gui));
-        context.notification_index = index.wrapping_add(1);
+        (rect![dx, dy, dx + dialog_width, dy + dialog_height], max_message_width)
+    }
 
-        Notification {
-            id,
-            rect,
-            children: Vec::new(),
-            text,
-            max_width: max_message_width,
-            index,
-            view_id,
+    pub fn update_text(&mut self, text: String, rq: &mut RenderQueue, context: &mut Context) {
+        let (rect, max_width) = Notification::layout(&text, self.index, context);
+        if rect != self.rect {
+            rq.add(RenderData::expose(self.rect, UpdateMode::Gui));
         }
+        self.rect = rect;
+        self.max_width = max_width;
+        self.text = text;
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
     }
 }
 
@@ -1,5 +1,5 @@
 use super::Overlay;
-use crate::ao3_metadata::Ao3Info;
+use crate::ao3_metadata::{Ao3Info, TagKind};
 use crate::context::Context;
 use crate::device::CURRENT_DEVICE;
 use crate::font::{font_from_style, Fonts};
@@ -8,10 +8,16 @@ use crate::framebuffer::{Framebuffer, UpdateMode};
 use crate::geom::{CycleDir, Rectangle};
 use crate::unit::scale_by_dpi;
 use crate::view::tag::{Tag, TagInfo};
+use crate::view::rich_text::RichText;
+use crate::html::HtmlText;
 use crate::view::SMALL_PADDING;
 use crate::view::{
-    Bus, Event, Hub, Id, RenderData, RenderQueue, View, ViewId, ID_FEEDER,
+    Bus, Event, EntryId, Hub, Id, RenderData, RenderQueue, View, ViewId, ID_FEEDER,
 };
+use crate::http::DownloadFormat;
+use chrono::NaiveDate;
+
+const DATE_FORMAT: &str = "%d %b %Y";
 
 #[derive(Clone)]
 pub struct About {
@@ -19,7 +25,7 @@ pub struct About {
     children: Vec<Box<dyn View>>,
     id: Id,
     view_id: ViewId,
-    pages: Vec<Vec<Tag>>,
+    pages: Vec<Vec<Box<dyn View>>>,
     current_page: usize,
     max_pages: usize,
 }
@@ -31,6 +37,18 @@ pub struct PageInfo {
     pub elements: Vec<Tag>,
 }
 
+enum AboutItem {
+    Tag(Box<TagInfo>),
+    // Rich text, e.g. the summary, always on its own lines.
+    Text(Vec<HtmlText>),
+}
+
+impl From<TagInfo> for AboutItem {
+    fn from(info: TagInfo) -> AboutItem {
+        AboutItem::Tag(Box::new(info))
+    }
+}
+
 impl About {
     pub fn new(info: Ao3Info, context: &mut Context) -> About {
         let id = ID_FEEDER.next();
@@ -48,26 +66,91 @@ impl About {
         // Generate our list of elements to iterate over
         let mut items = Vec::new();
 
-        items.push(TagInfo::new(info.title, None, BOLD_TITLE));
-        items.push(TagInfo::new("    by".to_string(), None, LABEL_STYLE));
+        items.push(TagInfo::new(info.title, None, BOLD_TITLE).into());
+        items.push(TagInfo::new("    by".to_string(), None, LABEL_STYLE).into());
         for author in info.authors {
             let temp = author.clone();
-            items.push(TagInfo::new(temp.title, Some(temp.location), LABEL_STYLE));
+            items.push(TagInfo::new(temp.title, Some(temp.location), LABEL_STYLE).into());
+        }
+        if !info.gifts.is_empty() {
+            items.push(TagInfo::new("    for".to_string(), None, LABEL_STYLE).into());
+            for recipient in &info.gifts {
+                items.push(TagInfo::new(recipient.title.clone(), Some(recipient.location.clone()), LABEL_STYLE).into());
+            }
+        }
+        if info.restricted {
+            items.push(TagInfo::new("Restricted to archive users".to_string(), None, LABEL_STYLE).into());
         }
-        items.push(TagInfo::new("Fandoms:".to_string(), None, BOLD_STYLE));
+        items.push(TagInfo::new("Fandoms:".to_string(), None, BOLD_STYLE).into());
         for fandom in info.fandoms {
             let temp = fandom.clone();
-            items.push(TagInfo::new(temp.title, Some(temp.location), ABOUT_STYLE));
+            items.push(TagInfo::new(temp.title, Some(temp.location), ABOUT_STYLE).into());
         }
 
-        items.push(TagInfo::new("Tags:".to_string(), None, BOLD_STYLE));
-        for tag in info.tags {
-            let temp = tag.clone();
-            items.push(TagInfo::new(temp.title, Some(temp.location), ABOUT_STYLE));
+        for kind in TagKind::ALL {
+            let tags = info.tags.of_kind(kind);
+            if tags.is_empty() {
+                continue;
+            }
+            items.push(TagInfo::new(format!("{}:", kind.label()), None, BOLD_STYLE).into());
+            for tag in tags {
+                items.push(TagInfo::new(tag.title.clone(), Some(tag.location.clone()), ABOUT_STYLE).into());
+            }
         }
 
-        items.push(TagInfo::new("Summary:".to_string(), None, BOLD_STYLE));
-        items.push(TagInfo::new(info.summary.clone(), None, LABEL_STYLE));
+        if !info.language.is_empty() {
+            items.push(TagInfo::new(format!("Language: {}", info.language), None, LABEL_STYLE).into());
+        }
+
+        if !info.series.is_empty() {
+            items.push(TagInfo::new("Series:".to_string(), None, BOLD_STYLE).into());
+            for position in &info.series {
+                let text = format!("Part {} of {}", position.part, position.series.title);
+                items.push(TagInfo::new(text, Some(position.series.location.clone()), ABOUT_STYLE).into());
+            }
+        }
+
+        if !info.collections.is_empty() {
+            items.push(TagInfo::new("Collections:".to_string(), None, BOLD_STYLE).into());
+            for collection in &info.collections {
+                items.push(TagInfo::new(collection.title.clone(), Some(collection.location.clone()), ABOUT_STYLE).into());
+            }
+        }
+
+        if !info.inspirations.is_empty() {
+            items.push(TagInfo::new("Inspired by:".to_string(), None, BOLD_STYLE).into());
+            for work in &info.inspirations {
+                items.push(TagInfo::new(work.title.clone(), Some(work.location.clone()), ABOUT_STYLE).into());
+            }
+        }
+
+        let mut dates = Vec::new();
+        if info.published != NaiveDate::MIN {
+            dates.push(format!("Published: {}", info.published.format(DATE_FORMAT)));
+        }
+        if info.updated != NaiveDate::MIN && info.updated != info.published {
+            dates.push(format!("Updated: {}", info.updated.format(DATE_FORMAT)));
+        }
+        if !dates.is_empty() {
+            items.push(TagInfo::new(dates.join("    "), None, LABEL_STYLE).into());
+        }
+
+        items.push(TagInfo::new("Summary:".to_string(), None, BOLD_STYLE).into());
+        items.push(AboutItem::Text(info.summary.clone()));
+
+        if !info.id.is_empty() && info.id != "0" && context.client.logged_in() {
+            let event = Event::Select(EntryId::EditBookmark(info.id.clone()));
+            items.push(TagInfo::new("Bookmark:".to_string(), None, BOLD_STYLE).into());
+            items.push(TagInfo::new("Add or edit".to_string(), None, ABOUT_STYLE).event(Some(event)).into());
+        }
+
+        if !info.id.is_empty() && info.id != "0" {
+            items.push(TagInfo::new("Download:".to_string(), None, BOLD_STYLE).into());
+            for format in DownloadFormat::ALL {
+                let event = Event::Select(EntryId::Download(info.id.clone(), format));
+                items.push(TagInfo::new(format.label().to_string(), None, ABOUT_STYLE).event(Some(event)).into());
+            }
+        }
 
         // Actually generate the items
         let padding = scale_by_dpi(SMALL_PADDING, dpi) as i32;
@@ -80,8 +163,30 @@ impl About {
         let mut pages = Vec::new();
 
         for item in items {
+            let item = match item {
+                AboutItem::Tag(info) => *info,
+                AboutItem::Text(text) => {
+                    if start_x != offset {
+                        start_y += line_height;
+                        start_x = offset;
+                    }
+                    let text_rect = rect![offset, start_y, offset + width, i32::MAX];
+                    let mut text = RichText::new(text_rect, &text, LABEL_STYLE, line_height, &mut context.fonts);
+                    while text.rect().max.y > rect.max.y {
+                        let next = text.split(rect.max.y, rect.min.y);
+                        elements.push(Box::new(text) as Box<dyn View>);
+                        pages.push(elements);
+                        elements = Vec::new();
+                        text = next;
+                    }
+                    start_y = text.rect().max.y;
+                    elements.push(Box::new(text) as Box<dyn View>);
+                    continue;
+                },
+            };
+
             // tags without locations are labels, and start on a new line always.
-            let label = item.location.is_none();
+            let label = item.is_label();
             if label && start_x != offset {
                 start_y += line_height;
                 start_x = offset;
@@ -109,7 +214,7 @@ impl About {
                     // we need to split a tag
                     let new_tag = tag.split(rect.max.y, height);
 
-                    elements.push(tag);
+                    elements.push(Box::new(tag) as Box<dyn View>);
                     pages.push(elements);
 
                     // start a new page and reset line counts
@@ -127,7 +232,7 @@ impl About {
                     break;
                 }
             }
-                elements.push(tag);
+                elements.push(Box::new(tag) as Box<dyn View>);
 
         }
         if elements.len() > 0 {
@@ -154,9 +259,7 @@ impl About {
         self.children_mut().drain(1..); // Remove old items, but not the overlay reference
 
         let page = self.pages[self.current_page].clone();
-        for item in page {
-            self.children_mut().push(Box::new(item) as Box<dyn View>);
-        }
+        self.children_mut().extend(page);
     }
 }
 
@@ -187,7 +290,8 @@ impl View for About {
                 rq.add(RenderData::new(self.id, *self.rect(), UpdateMode::Gui));
                 true
             }
-            Event::LoadIndex(..) => {
+            Event::LoadIndex(..) | Event::OpenWork(..) |
+            Event::Select(EntryId::Download(..)) | Event::Select(EntryId::EditBookmark(..)) => {
                 hub.send(Event::Close(self.view_id)).ok();
                 false
             }
@@ -1,6 +1,7 @@
 pub mod chapters;
 pub mod works;
 pub mod about;
+pub mod notes;
 
 use std::thread;
 use crate::device::CURRENT_DEVICE;
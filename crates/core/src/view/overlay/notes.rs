@@ -0,0 +1,113 @@
+use super::Overlay;
+use crate::context::Context;
+use crate::device::CURRENT_DEVICE;
+use crate::font::{font_from_style, Fonts, LABEL_STYLE};
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::geom::{CycleDir, Rectangle};
+use crate::html::HtmlText;
+use crate::unit::scale_by_dpi;
+use crate::view::rich_text::RichText;
+use crate::view::SMALL_PADDING;
+use crate::view::{Bus, Event, Hub, Id, RenderData, RenderQueue, View, ViewId, ID_FEEDER};
+
+// The author's notes, when they're collapsed within the work.
+#[derive(Clone)]
+pub struct Notes {
+    overlay: Overlay,
+    children: Vec<Box<dyn View>>,
+    id: Id,
+    view_id: ViewId,
+    pages: Vec<Vec<Box<dyn View>>>,
+    current_page: usize,
+}
+
+impl Notes {
+    pub fn new(notes: Vec<HtmlText>, context: &mut Context) -> Notes {
+        let id = ID_FEEDER.next();
+        let mut overlay = Overlay::new(ViewId::NotesOverlay, context);
+
+        let dpi = CURRENT_DEVICE.dpi;
+        let rect = overlay.msg_rect();
+        let font = font_from_style(&mut context.fonts, &LABEL_STYLE, dpi);
+        let line_height = font.line_height() * 3 / 2;
+        let padding = scale_by_dpi(SMALL_PADDING, dpi) as i32;
+
+        let text_rect = rect![rect.min.x + padding, rect.min.y, rect.max.x - padding, i32::MAX];
+        let mut text = RichText::new(text_rect, &notes, LABEL_STYLE, line_height, &mut context.fonts);
+        let mut pages = Vec::new();
+        while text.rect().max.y > rect.max.y {
+            let next = text.split(rect.max.y, rect.min.y);
+            pages.push(vec![Box::new(text) as Box<dyn View>]);
+            text = next;
+        }
+        pages.push(vec![Box::new(text) as Box<dyn View>]);
+        overlay.set_max(pages.len());
+
+        let children = vec![Box::new(overlay.clone()) as Box<dyn View>];
+
+        Notes {
+            overlay,
+            children,
+            id,
+            view_id: ViewId::NotesOverlay,
+            pages,
+            current_page: 0,
+        }
+    }
+
+    pub fn update_page(&mut self) {
+        self.children.drain(1..);
+        let page = self.pages[self.current_page].clone();
+        self.children.extend(page);
+    }
+}
+
+impl View for Notes {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Page(ref dir) => {
+                match dir {
+                    CycleDir::Next => if self.current_page < self.pages.len() - 1 { self.current_page += 1 },
+                    CycleDir::Previous => if self.current_page > 0 { self.current_page -= 1 },
+                }
+                self.update_page();
+                rq.add(RenderData::new(self.id, *self.rect(), UpdateMode::Gui));
+                true
+            },
+            Event::LoadIndex(..) | Event::OpenWork(..) => {
+                hub.send(Event::Close(self.view_id)).ok();
+                false
+            },
+            Event::Gesture(..) => true,
+            _ => self.overlay.handle_event(evt, hub, bus, rq, context),
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, rect: Rectangle, fonts: &mut Fonts) {
+        self.overlay.render(fb, rect, fonts);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        self.overlay.rect()
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        self.overlay.rect_mut()
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn view_id(&self) -> Option<ViewId> {
+        Some(self.view_id)
+    }
+}
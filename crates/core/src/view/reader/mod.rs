@@ -18,10 +18,7 @@ use chrono::Local;
 use regex::Regex;
 use septem::prelude::*;
 use septem::{Roman, Digit};
-use serde_json::{Value, Map};
-use serde::Deserialize;
 use rand_core::RngCore;
-use reqwest::StatusCode;
 use crate::input::{DeviceEvent, FingerStatus, ButtonCode, ButtonStatus};
 use crate::framebuffer::{Framebuffer, UpdateMode, Pixmap};
 use crate::view::{View, Event, AppCmd, Hub, Bus, RenderQueue, RenderData};
@@ -38,7 +35,7 @@ use self::tool_bar::ToolBar;
 use self::bottom_bar::BottomBar;
 use self::results_bar::ResultsBar;
 use crate::view::common::{locate, rlocate, locate_by_id};
-use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu};
+use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu, subscription_entries};
 use crate::view::filler::Filler;
 use crate::view::named_input::NamedInput;
 use crate::view::search_bar::SearchBar;
@@ -48,18 +45,26 @@ use crate::view::menu_entry::MenuEntry;
 use crate::view::notification::Notification;
 // use crate::view::overlay::Overlay;
 use crate::view::overlay::chapters::Chapters;
+use crate::view::overlay::notes::Notes;
 // use crate::view::overlay::works::WorksOverlay;
 // use crate::view::works::work::{Work, WorkView};
 // use crate::settings::{guess_frontlight, FinishedAction, SouthEastCornerAction};
 use crate::settings::{guess_frontlight, FinishedAction, SouthEastCornerAction, BottomRightGestureAction, SouthStripAction, WestStripAction, EastStripAction};
 use crate::settings::{DEFAULT_FONT_FAMILY, DEFAULT_TEXT_ALIGN, DEFAULT_LINE_HEIGHT, DEFAULT_MARGIN_WIDTH};
-use crate::settings::{HYPHEN_PENALTY, STRETCH_TOLERANCE};
+use crate::settings::{HYPHEN_PENALTY, STRETCH_TOLERANCE, NotesDisplay};
 use crate::frontlight::LightLevels;
 use crate::gesture::GestureEvent;
 use crate::document::{Document, open, Location, TextLocation, BoundedText, Neighbors, BYTES_PER_PAGE};
 use crate::document::{TocEntry, SimpleTocEntry, TocLocation, toc_as_html, annotations_as_html, bookmarks_as_html};
 use crate::document::html::HtmlDocument;
 use crate::document::ao3::Ao3Document;
+use crate::http::{Ao3Error, Freshness, DownloadFormat};
+use crate::http::outbox::Ao3Action;
+use crate::http::comments::{comments_path, post_path, CommentFields, CommentForm, COMMENTS_SCHEME, COMMENT_SCHEME};
+use crate::view::comment_composer::CommentComposer;
+use crate::view::bookmark_editor::BookmarkEditor;
+use crate::http::bookmarks::BookmarkFields;
+use crate::http::BookmarkForm;
 use crate::metadata::{Info, FileInfo, ReaderInfo, Annotation, TextAlign, ZoomMode, ScrollMode, PageScheme};
 use crate::metadata::{Margin, CroppingMargins, make_query};
 use crate::metadata::{DEFAULT_CONTRAST_EXPONENT, DEFAULT_CONTRAST_GRAY};
@@ -105,6 +110,13 @@ pub struct Reader {
     finished: bool,
     has_chapters: bool,
     kudos: Option<String>,
+    // Formats AO3 offers the work in.
+    downloads: Vec<DownloadFormat>,
+    // Whether the creator's work skin is applied, for works that have one.
+    work_skin: Option<bool>,
+    // Missing when comments can't be posted from the page.
+    comment_fields: Option<CommentFields>,
+    bookmark_fields: Option<BookmarkFields>,
 }
 
 #[derive(Debug, Clone)]
@@ -115,11 +127,6 @@ struct ViewPort {
     margin_width: i32,
 }
 
-#[derive(Deserialize)]
-struct KudosRes {
-    errors: Map<String, Value>
-}
-
 
 impl Default for ViewPort {
     fn default() -> Self {
@@ -372,6 +379,9 @@ impl Reader {
             let reflowable = doc.is_reflowable();
             let has_chapters = doc.has_chapters();
             let kudos = doc.kudos_token();
+            let comment_fields = doc.comment_fields();
+            let bookmark_fields = doc.bookmark_fields();
+            let downloads = doc.download_links().into_iter().map(|(format, _)| format).collect();
 
             hub.send(Event::Update(UpdateMode::Partial)).ok();
 
@@ -405,6 +415,10 @@ impl Reader {
                 finished: false,
                 has_chapters,
                 kudos,
+                downloads,
+                work_skin: None,
+                comment_fields,
+                bookmark_fields,
             })
         })
     }
@@ -475,11 +489,15 @@ impl Reader {
             reflowable: true,
             finished: false,
             has_chapters: false,
-            kudos: None
+            kudos: None,
+            downloads: Vec::new(),
+            work_skin: None,
+            comment_fields: None,
+            bookmark_fields: None,
         }
     }
 
-    pub fn from_ao3(rect: Rectangle, html: &str, link_uri: Option<&str>, hub: &Hub, context: &mut Context) -> Reader {
+    pub fn from_ao3(rect: Rectangle, html: &str, link_uri: Option<&str>, hub: &Hub, context: &mut Context) -> Result<Reader, Ao3Error> {
         let id = ID_FEEDER.next();
 
         let mut info = Info {
@@ -491,7 +509,7 @@ impl Reader {
             .. Default::default()
         };
 
-        let mut doc = Ao3Document::new_from_memory(html, link_uri);
+        let mut doc = Ao3Document::new_from_memory(html, link_uri)?;
         let (width, height) = context.display.dims;
         let font_size = context.settings.reader.font_size;
 
@@ -499,6 +517,12 @@ impl Reader {
         doc.set_line_height(context.settings.reader.line_height);
         doc.set_font_size(context.settings.reader.font_size);
         doc.set_text_align(context.settings.reader.text_align);
+        doc.set_notes_display(context.settings.reader.notes_display);
+
+        let work_skin = doc.has_work_skin().then(|| !context.settings.ao3.skinless_works.contains(&doc.work_id()));
+        if work_skin == Some(false) {
+            doc.set_ignore_document_css(true);
+        }
 
         let font_family = &context.settings.reader.font_family;
 
@@ -518,6 +542,9 @@ impl Reader {
         info.title = doc.title().unwrap_or_default();
         let has_chapters = doc.has_chapters();
         let kudos = doc.kudos_token();
+        let comment_fields = doc.comment_fields();
+        let bookmark_fields = doc.bookmark_fields();
+        let downloads = doc.download_links().into_iter().map(|(format, _)| format).collect();
 
         let mut current_page = 0;
         if let Some(link_uri) = link_uri {
@@ -533,7 +560,7 @@ impl Reader {
 
         hub.send(Event::Update(UpdateMode::Partial)).ok();
 
-        Reader {
+        Ok(Reader {
             id,
             rect,
             children: Vec::new(),
@@ -562,8 +589,17 @@ impl Reader {
             reflowable: true,
             finished: false,
             has_chapters,
-            kudos
-        }
+            kudos,
+            downloads,
+            work_skin,
+            comment_fields,
+            bookmark_fields,
+        })
+    }
+
+    // For pages that aren't AO3's, like the comments.
+    pub fn set_comment_fields(&mut self, comment_fields: Option<CommentFields>) {
+        self.comment_fields = comment_fields;
     }
 
     fn load_pixmap(&mut self, location: usize) {
@@ -1158,6 +1194,7 @@ impl Reader {
             }
         });
 
+        self.request_chapters(hub);
         self.chunks.clear();
         let mut location = self.current_page;
         let smw = self.view_port.margin_width;
@@ -1468,6 +1505,7 @@ impl Reader {
                 Some(_token) => true,
                 None => false
             };
+            let has_comments = !self.doc.lock().unwrap().work_id().is_empty();
 
             let tool_bar = ToolBar::new(rect![self.rect.min.x,
                                               sp_rect.max.y,
@@ -1477,7 +1515,9 @@ impl Reader {
                                         self.info.reader.as_ref(),
                                         &context.settings.reader,
                                         self.has_chapters,
-                                    has_kudos);
+                                    has_kudos,
+                                    has_comments,
+                                    !self.downloads.is_empty());
             self.children.insert(2, Box::new(tool_bar) as Box<dyn View>);
 
             let separator = Filler::new(sp_rect, BLACK);
@@ -1683,6 +1723,7 @@ impl Reader {
                     Some(_token) => true,
                     None => false
                 };
+                let has_comments = !self.doc.lock().unwrap().work_id().is_empty();
 
                 let tool_bar = ToolBar::new(rect![self.rect.min.x,
                                                   self.rect.max.y - (small_height + tb_height) as i32 + big_thickness,
@@ -1692,7 +1733,9 @@ impl Reader {
                                             self.info.reader.as_ref(),
                                             &context.settings.reader, 
                                             self.has_chapters,
-                                        has_kudos);
+                                        has_kudos,
+                                        has_comments,
+                                        !self.downloads.is_empty());
                 self.children.insert(index, Box::new(tool_bar) as Box<dyn View>);
                 index += 1;
             }
@@ -1980,6 +2023,12 @@ impl Reader {
                                              EntryId::ToggleDithered,
                                              context.fb.dithered()));
 
+            if let Some(work_skin) = self.work_skin {
+                entries.push(EntryKind::CheckBox("Work Skin".to_string(),
+                                                 EntryId::ToggleWorkSkin,
+                                                 work_skin));
+            }
+
             let mut title_menu = Menu::new(rect, ViewId::TitleMenu, MenuKind::DropDown, entries, context);
             title_menu.child_mut(1)
                       .downcast_mut::<MenuEntry>().unwrap()
@@ -2101,6 +2150,297 @@ impl Reader {
         }
     }
 
+    fn toggle_download_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::DownloadMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let work_id = self.doc.lock().unwrap().work_id();
+            let entries = self.downloads.iter().map(|format| {
+                EntryKind::Command(format.label().to_string(),
+                                   EntryId::Download(work_id.clone(), *format))
+            }).collect();
+            let download_menu = Menu::new(rect, ViewId::DownloadMenu, MenuKind::DropDown, entries, context);
+            rq.add(RenderData::new(download_menu.id(), *download_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(download_menu) as Box<dyn View>);
+        }
+    }
+
+    fn toggle_comments_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::CommentsMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let (work_id, chapter_path) = {
+                let doc = self.doc.lock().unwrap();
+                let chapter_path = if doc.has_chapters() { doc.chapter_path(self.current_page) } else { None };
+                (doc.work_id(), chapter_path)
+            };
+            let work_path = format!("/works/{}", work_id);
+            let mut entries = Vec::new();
+            if let Some(path) = chapter_path.as_ref() {
+                entries.push(EntryKind::Command("Chapter Comments".to_string(),
+                                                EntryId::LoadComments(comments_path(path))));
+            }
+            entries.push(EntryKind::Command("Work Comments".to_string(),
+                                            EntryId::LoadComments(comments_path(&work_path))));
+            if self.comment_fields.is_some() {
+                entries.push(EntryKind::Separator);
+                if let Some(path) = chapter_path.as_ref() {
+                    entries.push(EntryKind::Command("Comment on Chapter".to_string(),
+                                                    EntryId::ComposeComment(post_path(path))));
+                }
+                entries.push(EntryKind::Command("Comment on Work".to_string(),
+                                                EntryId::ComposeComment(post_path(&work_path))));
+            }
+            let comments_menu = Menu::new(rect, ViewId::CommentsMenu, MenuKind::DropDown, entries, context);
+            rq.add(RenderData::new(comments_menu.id(), *comments_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(comments_menu) as Box<dyn View>);
+        }
+    }
+
+    fn toggle_bookmark_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::BookmarkMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let (work_id, title, mut info) = {
+                let doc = self.doc.lock().unwrap();
+                (doc.work_id(), doc.title().unwrap_or_default(), doc.ao3_meta())
+            };
+            let existing = self.bookmark_fields.as_ref().is_some_and(|fields| fields.bookmark_id.is_some());
+            let label = if existing { "Edit Bookmark" } else { "Add Bookmark" };
+            let mut entries = vec![EntryKind::Command(label.to_string(),
+                                                      EntryId::EditBookmark(work_id.clone()))];
+            if context.client.logged_in() && !work_id.is_empty() {
+                entries.push(EntryKind::Separator);
+                entries.push(EntryKind::Command("Mark for Later".to_string(),
+                                                EntryId::MarkForLater(work_id.clone(), title.clone())));
+                entries.push(EntryKind::Command("Mark as Read".to_string(),
+                                                EntryId::MarkAsRead(work_id.clone(), title.clone())));
+                entries.push(EntryKind::Separator);
+                info.id = work_id;
+                if info.title.is_empty() {
+                    info.title = title;
+                }
+                entries.extend(subscription_entries(&info));
+            }
+            let bookmark_menu = Menu::new(rect, ViewId::BookmarkMenu, MenuKind::DropDown, entries, context);
+            rq.add(RenderData::new(bookmark_menu.id(), *bookmark_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(bookmark_menu) as Box<dyn View>);
+        }
+    }
+
+    // Closing the composer keeps what was written for next time.
+    fn toggle_comment_composer(&mut self, post_path: Option<String>, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::CommentComposer) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            if let Some(composer) = self.child(index).downcast_ref::<CommentComposer>() {
+                context.comment_drafts.set(composer.post_path(), composer.form());
+            }
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+
+            if matches!(self.focus, Some(ViewId::CommentNameInput | ViewId::CommentEmailInput | ViewId::CommentInput)) {
+                self.toggle_keyboard(false, None, hub, rq, context);
+            }
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let (Some(post_path), Some(fields)) = (post_path, self.comment_fields.as_ref()) else {
+                return;
+            };
+            let guest = fields.pseud_id.is_empty();
+            let draft = context.comment_drafts.get(&post_path).cloned().unwrap_or_else(|| {
+                let (name, email) = context.comment_drafts.signature().unwrap_or_default();
+                CommentForm { name: name.to_string(), email: email.to_string(), .. Default::default() }
+            });
+            let title = if post_path.starts_with("/comments/") { "Reply" } else { "Comment" };
+            let composer = CommentComposer::new(title.to_string(), post_path, draft, guest, context);
+            hub.send(Event::Focus(Some(composer.first_input()))).ok();
+            rq.add(RenderData::new(composer.id(), *composer.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(composer) as Box<dyn View>);
+        }
+    }
+
+    // Comments that can't be posted right away wait in the outbox.
+    fn post_comment(&mut self, post_path: &str, form: &CommentForm, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let Some(fields) = self.comment_fields.as_ref() else {
+            return;
+        };
+        if form.is_empty() {
+            hub.send(Event::Notify("Your comment is empty.".to_string())).ok();
+            return;
+        }
+
+        let (work_id, title) = {
+            let doc = self.doc.lock().unwrap();
+            (doc.work_id(), doc.title().unwrap_or_default())
+        };
+        let action = Ao3Action::Comment {
+            work_id,
+            path: post_path.to_string(),
+            form: CommentForm { pseud_id: fields.pseud_id.clone(), .. form.clone() },
+        };
+        let res = if context.online {
+            action.run(&context.client)
+        } else {
+            Err(Ao3Error::Network("offline".to_string()))
+        };
+        match res {
+            Ok(messages) => {
+                for msg in messages {
+                    hub.send(Event::Notify(msg)).ok();
+                }
+            },
+            Err(e) if e.is_transient() => {
+                context.outbox.push(action, title);
+                hub.send(Event::Notify("Your comment will be posted once AO3 can be reached.".to_string())).ok();
+            },
+            Err(e) => {
+                hub.send(Event::Notify(format!("Sorry, we were unable to post your comment. {}", e))).ok();
+                return;
+            }
+        }
+
+        self.toggle_comment_composer(None, Some(false), hub, rq, context);
+        context.comment_drafts.remove(post_path);
+    }
+
+    fn toggle_bookmark_editor(&mut self, enable: Option<bool>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::BookmarkEditor) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+
+            if matches!(self.focus, Some(ViewId::BookmarkTagsInput | ViewId::BookmarkCollectionsInput | ViewId::BookmarkNotesInput)) {
+                self.toggle_keyboard(false, None, hub, rq, context);
+            }
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let Some(fields) = self.bookmark_fields.as_ref() else {
+                let msg = if context.client.logged_in() {
+                    "Only works can be bookmarked."
+                } else {
+                    "Log in to bookmark works."
+                };
+                hub.send(Event::Notify(msg.to_string())).ok();
+                return;
+            };
+            let existing = fields.bookmark_id.is_some();
+            let title = if existing { "Edit Bookmark" } else { "Add Bookmark" };
+            let editor = BookmarkEditor::new(title.to_string(), fields.form.clone(), existing, context);
+            rq.add(RenderData::new(editor.id(), *editor.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(editor) as Box<dyn View>);
+        }
+    }
+
+    // Saves the bookmark when `form` is given, deletes it otherwise.
+    fn update_bookmark(&mut self, form: Option<&BookmarkForm>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let Some(fields) = self.bookmark_fields.as_mut() else {
+            return;
+        };
+
+        let (work_id, title) = {
+            let doc = self.doc.lock().unwrap();
+            (doc.work_id(), doc.title().unwrap_or_default())
+        };
+        let action = match (form, fields.bookmark_id.clone()) {
+            (Some(form), None) => Ao3Action::Bookmark { work_id, form: form.clone() },
+            (Some(form), Some(bookmark_id)) => Ao3Action::EditBookmark { work_id, bookmark_id, form: form.clone() },
+            (None, Some(bookmark_id)) => Ao3Action::DeleteBookmark { work_id, bookmark_id },
+            (None, None) => return,
+        };
+        let res = if context.online {
+            action.run(&context.client)
+        } else {
+            Err(Ao3Error::Network("offline".to_string()))
+        };
+        match res {
+            Ok(messages) => {
+                for msg in messages {
+                    hub.send(Event::Notify(msg)).ok();
+                }
+            },
+            Err(e) if e.is_transient() => {
+                context.outbox.push(action, title);
+                hub.send(Event::Notify("Your bookmark will be saved once AO3 can be reached.".to_string())).ok();
+            },
+            Err(e) => {
+                hub.send(Event::Notify(format!("Sorry, we were unable to save your bookmark. {}", e))).ok();
+                return;
+            }
+        }
+
+        if let Some(form) = form {
+            fields.form = form.clone();
+        } else {
+            fields.bookmark_id = None;
+            fields.form = BookmarkForm { pseud_id: fields.form.pseud_id.clone(), .. Default::default() };
+        }
+        self.toggle_bookmark_editor(Some(false), hub, rq, context);
+    }
+
+    fn toggle_notes_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::NotesMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let notes_display = context.settings.reader.notes_display;
+            let entries = NotesDisplay::ALL.iter().map(|display| {
+                EntryKind::RadioButton(display.label().to_string(),
+                                       EntryId::SetNotesDisplay(*display),
+                                       *display == notes_display)
+            }).collect();
+            let notes_menu = Menu::new(rect, ViewId::NotesMenu, MenuKind::DropDown, entries, context);
+            rq.add(RenderData::new(notes_menu.id(), *notes_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(notes_menu) as Box<dyn View>);
+        }
+    }
+
     fn toggle_contrast_exponent_menu(&mut self, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
         if let Some(index) = locate_by_id(self, ViewId::ContrastExponentMenu) {
             if let Some(true) = enable {
@@ -2337,6 +2677,96 @@ impl Reader {
         self.update_bottom_bar(rq);
     }
 
+    fn set_notes_display(&mut self, notes_display: NotesDisplay, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        if Arc::strong_count(&self.doc) > 1 {
+            return;
+        }
+
+        context.settings.reader.notes_display = notes_display;
+
+        {
+            let mut doc = self.doc.lock().unwrap();
+            doc.set_notes_display(notes_display);
+
+            if self.synthetic {
+                let current_page = self.current_page.min(doc.pages_count() - 1);
+                if let Some(location) =  doc.resolve_location(Location::Exact(current_page)) {
+                    self.current_page = location;
+                }
+            } else {
+                self.pages_count = doc.pages_count();
+                self.current_page = self.current_page.min(self.pages_count - 1);
+            }
+        }
+
+        self.cache.clear();
+        self.text.clear();
+        self.update(None, hub, rq, context);
+        self.update_tool_bar(rq, context);
+        self.update_bottom_bar(rq);
+    }
+
+    // Remembered for each work.
+    fn toggle_work_skin(&mut self, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+        let Some(work_skin) = self.work_skin else {
+            return;
+        };
+
+        if Arc::strong_count(&self.doc) > 1 {
+            return;
+        }
+
+        {
+            let mut doc = self.doc.lock().unwrap();
+            doc.set_ignore_document_css(work_skin);
+
+            let work_id = doc.work_id();
+            if work_skin {
+                context.settings.ao3.skinless_works.insert(work_id);
+            } else {
+                context.settings.ao3.skinless_works.remove(&work_id);
+            }
+
+            if let Some(location) = doc.resolve_location(Location::Exact(self.current_page)) {
+                self.current_page = location;
+            }
+        }
+
+        self.work_skin = Some(!work_skin);
+        self.cache.clear();
+        self.text.clear();
+        self.update(None, hub, rq, context);
+        self.update_bottom_bar(rq);
+    }
+
+    // For documents read a chapter at a time.
+    fn request_chapters(&mut self, hub: &Hub) {
+        if let Ok(mut doc) = self.doc.lock() {
+            for path in doc.request_chapters(self.current_page) {
+                hub.send(Event::FetchChapter(path)).ok();
+            }
+        }
+    }
+
+    fn load_chapter(&mut self, path: &str, html: Option<&str>, hub: &Hub, rq: &mut RenderQueue, context: &Context) {
+        let range = self.doc.lock().ok().and_then(|mut doc| doc.load_chapter(path, html));
+        let Some(range) = range else {
+            return;
+        };
+
+        self.cache.retain(|location, _| !range.contains(location));
+        self.text.retain(|location, _| !range.contains(location));
+
+        // Replaces the chapter's title with its text.
+        if self.chunks.iter().any(|chunk| range.contains(&chunk.location)) {
+            if let Some(location) = self.doc.lock().ok().and_then(|mut doc| doc.resolve_location(Location::Exact(self.current_page))) {
+                self.current_page = location;
+            }
+            self.update(None, hub, rq, context);
+            self.update_bottom_bar(rq);
+        }
+    }
+
     fn set_font_family(&mut self, font_family: &str, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
         if Arc::strong_count(&self.doc) > 1 {
             return;
@@ -2707,6 +3137,12 @@ impl Reader {
             s.running.store(false, AtomicOrdering::Relaxed);
         }
 
+        let composer = locate_by_id(self, ViewId::CommentComposer)
+                           .and_then(|index| self.child(index).downcast_ref::<CommentComposer>());
+        if let Some(composer) = composer {
+            context.comment_drafts.set(composer.post_path(), composer.form());
+        }
+
         if self.ephemeral {
             return;
         }
@@ -2824,51 +3260,32 @@ impl View for Reader {
             //     true
             // },
             Event::Kudos => {
-                if let Some(token) = &self.kudos {
-                    let doc = self.doc.lock().unwrap();
-                    let params = [ 
-                                ("authenticity_token", token), 
-                                ("kudo[commentable_id]", &doc.work_id()),
-                                ("kudo[commentable_type]", &"Work".to_string())];
-                    let res = context.client.post("https://archiveofourown.org/kudos.js")
-                        .form(&params)
-                        .send();
-    
+                if self.kudos.is_some() {
+                    let (work_id, title) = {
+                        let doc = self.doc.lock().unwrap();
+                        (doc.work_id(), doc.title().unwrap_or_default())
+                    };
+                    let action = Ao3Action::Kudos { work_id };
+                    let res = if context.online {
+                        action.run(&context.client)
+                    } else {
+                        Err(Ao3Error::Network("offline".to_string()))
+                    };
                     match res {
-                        Ok(r) => {
-                            match r.status() {
-                                StatusCode::CREATED => {hub.send(Event::Notify("Thank you for leaving kudos!".to_string())).ok();},
-                                StatusCode::UNPROCESSABLE_ENTITY => {
-                                    let data = r.json::<KudosRes>();
-                                    match data {
-                                        Ok(json) => {
-                                            for val in json.errors.values() {
-                                                match val {
-                                                    Value::Array(items) => {
-                                                        for item in items {
-                                                        hub.send(Event::Notify(item.to_string())).ok();
-                                                        }
-                                                    },
-                                                    _ => {hub.send(Event::Notify(val.to_string())).ok();}
-                                                }
-                                                
-                                            }
-                                        },
-                                        Err(e) => {
-                                            println!("{}", e);
-                                            hub.send(Event::Notify("Sorry, we were unable to save your kudos".to_string())).ok();
-                                        }
-                                    }
-                                    
-                                },
-                                _ => {hub.send(Event::Notify("Sorry, we were unable to save your kudos".to_string())).ok();} 
-                            };
+                        Ok(messages) => {
+                            for msg in messages {
+                                hub.send(Event::Notify(msg)).ok();
+                            }
+                        },
+                        Err(e) if e.is_transient() => {
+                            context.outbox.push(action, title);
+                            hub.send(Event::Notify("Your kudos will be sent once AO3 can be reached.".to_string())).ok();
                         },
                         Err(e) => {
                             println!("{}", e);
-                            hub.send(Event::Notify("Sorry, we were unable to save your kudos".to_string())).ok();
+                            hub.send(Event::Notify(format!("Sorry, we were unable to save your kudos. {}", e))).ok();
                         }
-                    };
+                    }
                 }
 
                 true
@@ -3249,7 +3666,17 @@ impl View for Reader {
                     let pdf_page = Regex::new(r"^#page=(\d+).*$").unwrap();
                     let _djvu_page = Regex::new(r"^#([+-])?(\d+)$").unwrap();
                     let toc_page = Regex::new(r"^@(.+)$").unwrap();
-                    if let Some(caps) = toc_page.captures(&link.text) {
+                    let notes = self.doc.lock().ok().and_then(|doc| doc.notes(&link.text));
+                    if let Some(notes) = notes {
+                        let mut notes_overlay = Notes::new(notes, context);
+                        notes_overlay.update_page();
+                        rq.add(RenderData::new(notes_overlay.id(), *notes_overlay.rect(), UpdateMode::Gui));
+                        self.children.push(Box::new(notes_overlay) as Box<dyn View>);
+                    } else if let Some(path) = link.text.strip_prefix(COMMENTS_SCHEME) {
+                        hub.send(Event::Select(EntryId::LoadComments(path.to_string()))).ok();
+                    } else if let Some(path) = link.text.strip_prefix(COMMENT_SCHEME) {
+                        hub.send(Event::Select(EntryId::ComposeComment(path.to_string()))).ok();
+                    } else if let Some(caps) = toc_page.captures(&link.text) {
                         let loc_opt = if caps[1].chars().all(|c| c.is_digit(10)) {
                             caps[1].parse::<usize>()
                                    .map(Location::Exact)
@@ -3271,19 +3698,10 @@ impl View for Reader {
                         if !context.settings.wifi {
                             hub.send(Event::SetWifi(true)).ok();
                         }
-                        let html = context.client.get(&uri).send();
-                        match html {
-                        Ok(r) => {
-                            let text = r.text();
-                            match text {
-                                Ok(t) => hub.send(Event::OpenHtml(t, Some(uri))).ok(),
-                                Err(e) => hub.send(Event::Notify(format!("There was an error in the response body of {}:\n{}", uri, e))).ok(),
-                            };
-                        },
-                        Err(e) => {
-                            hub.send(Event::Notify(format!("{}", e))).ok();
-                        }
-                    }
+                        match context.client.get_html(&uri, Freshness::Index) {
+                            Ok(t) => hub.send(Event::OpenHtml(t, Some(uri))).ok(),
+                            Err(e) => hub.send(Event::Notify(e.to_string())).ok(),
+                        };
 
                     } else {
                         let mut doc = self.doc.lock().unwrap();
@@ -3487,6 +3905,10 @@ impl View for Reader {
                 self.update(Some(mode), hub, rq, context);
                 true
             },
+            Event::ChapterFetched(ref path, ref html) => {
+                self.load_chapter(path, html.as_deref(), hub, rq, context);
+                true
+            },
             Event::LoadPixmap(location) => {
                 self.load_pixmap(location);
                 true
@@ -3687,6 +4109,18 @@ impl View for Reader {
                 self.toggle_margin_width_menu(rect, None, rq, context);
                 true
             },
+            Event::ToggleNear(ViewId::DownloadMenu, rect) => {
+                self.toggle_download_menu(rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::CommentsMenu, rect) => {
+                self.toggle_comments_menu(rect, None, rq, context);
+                true
+            },
+            Event::ToggleNear(ViewId::NotesMenu, rect) => {
+                self.toggle_notes_menu(rect, None, rq, context);
+                true
+            },
             Event::ToggleNear(ViewId::LineHeightMenu, rect) => {
                 self.toggle_line_height_menu(rect, None, rq, context);
                 true
@@ -3734,6 +4168,42 @@ impl View for Reader {
                 }
                 false
             },
+            Event::ToggleNear(ViewId::BookmarkMenu, rect) => {
+                self.toggle_bookmark_menu(rect, None, rq, context);
+                true
+            },
+            Event::Select(EntryId::EditBookmark(..)) | Event::Show(ViewId::BookmarkEditor) => {
+                self.toggle_bookmark_editor(Some(true), hub, rq, context);
+                true
+            },
+            Event::Close(ViewId::BookmarkEditor) => {
+                self.toggle_bookmark_editor(Some(false), hub, rq, context);
+                true
+            },
+            Event::SaveBookmark(ref form) => {
+                self.update_bookmark(Some(form), hub, rq, context);
+                true
+            },
+            Event::DeleteBookmark => {
+                self.update_bookmark(None, hub, rq, context);
+                true
+            },
+            Event::Close(ViewId::CommentComposer) => {
+                self.toggle_comment_composer(None, Some(false), hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ComposeComment(ref post_path)) => {
+                if self.comment_fields.is_some() {
+                    self.toggle_comment_composer(Some(post_path.clone()), Some(true), hub, rq, context);
+                } else {
+                    hub.send(Event::Notify("Comments can't be posted from this page.".to_string())).ok();
+                }
+                true
+            },
+            Event::PostComment(ref post_path, ref form) => {
+                self.post_comment(post_path, form, hub, rq, context);
+                true
+            },
             Event::Close(ViewId::EditNote) => {
                 self.toggle_edit_note(None, Some(false), hub, rq, context);
                 if let Some(rect) = self.selection_rect() {
@@ -4007,6 +4477,14 @@ impl View for Reader {
                 self.set_text_align(text_align, hub, rq, context);
                 true
             },
+            Event::Select(EntryId::SetNotesDisplay(notes_display)) => {
+                self.set_notes_display(notes_display, hub, rq, context);
+                true
+            },
+            Event::Select(EntryId::ToggleWorkSkin) => {
+                self.toggle_work_skin(hub, rq, context);
+                true
+            },
             Event::Select(EntryId::SetFontSize(v)) => {
                 let font_size = context.settings.reader.font_size;
                 let font_size = font_size - 1.0 + v as f32 / 10.0;
@@ -24,6 +24,7 @@ pub struct ToolBar {
 }
 
 impl ToolBar {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         rect: Rectangle,
         _reflowable: bool,
@@ -31,6 +32,8 @@ impl ToolBar {
         _reader_settings: &ReaderSettings,
         has_chapters: bool,
         has_kudos: bool,
+        has_comments: bool,
+        has_downloads: bool,
     ) -> ToolBar {
         let id = ID_FEEDER.next();
         let mut children = Vec::new();
@@ -51,7 +54,7 @@ impl ToolBar {
             children.push(Box::new(toc_icon) as Box<dyn View>);
         }
 
-        let remaining_width = rect.width() as i32 - 4 * side;
+        let remaining_width = rect.width() as i32 - 7 * side;
 
         // About Work
         let about_work_rect = rect![
@@ -80,21 +83,72 @@ impl ToolBar {
             children.push(Box::new(kudos_filler) as Box<dyn View>);
         }
 
-
+        // Comments
+        let comments_rect = rect![
+            remaining_width + 2 * side + rect.min.x,
+            rect.min.y,
+            remaining_width + 3 * side + rect.min.x,
+            rect.max.y
+        ];
+        if has_comments {
+            let comments_icon = Icon::new(
+                "comments",
+                comments_rect,
+                Event::ToggleNear(ViewId::CommentsMenu, comments_rect),
+            );
+            children.push(Box::new(comments_icon) as Box<dyn View>);
+        } else {
+            let comments_filler = Filler::new(comments_rect, WHITE);
+            children.push(Box::new(comments_filler) as Box<dyn View>);
+        }
 
         // Bookmark
+        let bookmark_rect = rect![
+            remaining_width + 3 * side + rect.min.x,
+            rect.min.y,
+            remaining_width + 4 * side + rect.min.x,
+            rect.max.y
+        ];
         let bookmark_icon = Icon::new(
             "bookmark",
-            rect![
-                remaining_width + 2 * side + rect.min.x,
-                rect.min.y,
-                remaining_width + 3 * side + rect.min.x,
-                rect.max.y
-            ],
-            Event::Show(ViewId::LineHeightMenu),
+            bookmark_rect,
+            Event::ToggleNear(ViewId::BookmarkMenu, bookmark_rect),
         );
         children.push(Box::new(bookmark_icon) as Box<dyn View>);
 
+        // Download
+        let download_rect = rect![
+            remaining_width + 4 * side + rect.min.x,
+            rect.min.y,
+            remaining_width + 5 * side + rect.min.x,
+            rect.max.y
+        ];
+        if has_downloads {
+            let download_icon = Icon::new(
+                "arrow-down",
+                download_rect,
+                Event::ToggleNear(ViewId::DownloadMenu, download_rect),
+            );
+            children.push(Box::new(download_icon) as Box<dyn View>);
+        } else {
+            let download_filler = Filler::new(download_rect, WHITE);
+            children.push(Box::new(download_filler) as Box<dyn View>);
+        }
+
+        // Notes
+        let notes_rect = rect![
+            remaining_width + 5 * side + rect.min.x,
+            rect.min.y,
+            remaining_width + 6 * side + rect.min.x,
+            rect.max.y
+        ];
+        let notes_icon = Icon::new(
+            "notes",
+            notes_rect,
+            Event::ToggleNear(ViewId::NotesMenu, notes_rect),
+        );
+        children.push(Box::new(notes_icon) as Box<dyn View>);
+
         let search_icon = Icon::new(
             "search",
             rect![
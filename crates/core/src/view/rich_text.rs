@@ -0,0 +1,293 @@
+use regex::Regex;
+use url::Url;
+use super::{Bus, Event, Hub, Id, RenderQueue, View, ID_FEEDER, THICKNESS_SMALL};
+use crate::color::TEXT_NORMAL;
+use crate::context::Context;
+use crate::device::CURRENT_DEVICE;
+use crate::font::{font_from_style, Fonts, Style, Variant};
+use crate::framebuffer::Framebuffer;
+use crate::geom::{Point, Rectangle};
+use crate::gesture::GestureEvent;
+use crate::html::{Format, HtmlText, Link};
+use crate::unit::scale_by_dpi;
+
+// A piece of text drawn on a single line, with a single style.
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub text: String,
+    pub format: Option<Format>,
+    // Start of the baseline.
+    pub origin: Point,
+    pub rect: Rectangle,
+    pub link: Option<Link>,
+}
+
+fn run_style(style: &Style, format: Option<Format>) -> Style {
+    match format {
+        None => style.clone(),
+        Some(Format::Bold) => style.with_variant(Variant::BOLD),
+        Some(Format::Italic) => style.with_variant(Variant::ITALIC),
+        Some(Format::BoldItalic) => style.with_variant(Variant::BOLDITALIC),
+    }
+}
+
+// Lays out `items` in `rect`, wrapping between words. The lines that
+// don't fit are left out.
+pub fn layout(items: &[HtmlText], rect: Rectangle, style: &Style, line_height: i32, fonts: &mut Fonts) -> Vec<Run> {
+    let dpi = CURRENT_DEVICE.dpi;
+    let (indent, x_height) = {
+        let font = font_from_style(fonts, style, dpi);
+        (font.em() as i32, font.x_heights.0 as i32)
+    };
+    let mut runs: Vec<Run> = Vec::new();
+    let mut left = rect.min.x;
+    let mut x = left;
+    let mut top = rect.min.y;
+    let mut spaced = false;
+
+    for item in items {
+        let (text, format, link) = match item {
+            HtmlText::Break | HtmlText::Blockquote => {
+                left = rect.min.x + if *item == HtmlText::Blockquote { indent } else { 0 };
+                x = left;
+                top += line_height;
+                spaced = false;
+                continue;
+            },
+            HtmlText::Text(text) => (text.as_str(), None, None),
+            HtmlText::FormatText(text, format) => (text.as_str(), Some(*format), None),
+            HtmlText::LinkText(link) => (link.title.as_str(), None, Some(link)),
+        };
+
+        let font = font_from_style(fonts, &run_style(style, format), dpi);
+        let space = font.plan(" ", None, None).width;
+        spaced |= text.starts_with(char::is_whitespace);
+
+        for word in text.split_whitespace() {
+            let width = font.plan(word, None, None).width;
+            let mut gap = if spaced && x > left { space } else { 0 };
+            if x + gap + width > rect.max.x && x > left {
+                x = left;
+                top += line_height;
+                gap = 0;
+            }
+            if top > rect.max.y - line_height {
+                return runs;
+            }
+
+            let end = (x + gap + width).min(rect.max.x);
+            match runs.last_mut() {
+                Some(last) if last.rect.min.y == top && last.format == format && last.link.as_ref() == link => {
+                    if gap > 0 {
+                        last.text.push(' ');
+                    }
+                    last.text.push_str(word);
+                    last.rect.max.x = end;
+                },
+                _ => {
+                    runs.push(Run {
+                        text: word.to_string(),
+                        format,
+                        origin: pt!(x + gap, top + (line_height + x_height) / 2),
+                        rect: rect![x + gap, top, end, top + line_height],
+                        link: link.cloned(),
+                    });
+                },
+            }
+            x += gap + width;
+            spaced = true;
+        }
+
+        spaced = text.ends_with(char::is_whitespace);
+    }
+
+    runs
+}
+
+pub fn render_runs(fb: &mut dyn Framebuffer, runs: &[Run], style: &Style, color: u8, fonts: &mut Fonts) {
+    let dpi = CURRENT_DEVICE.dpi;
+    let thickness = scale_by_dpi(THICKNESS_SMALL, dpi) as i32;
+
+    for run in runs {
+        let font = font_from_style(fonts, &run_style(style, run.format), dpi);
+        let mut plan = font.plan(&run.text, None, None);
+        let max_width = run.rect.width() as i32;
+        if plan.width > max_width {
+            font.crop_right(&mut plan, max_width);
+        }
+        font.render(fb, color, &plan, run.origin);
+
+        // Links are underlined.
+        if run.link.is_some() {
+            let y = run.origin.y + 2 * thickness;
+            fb.draw_rectangle(&rect![run.origin.x, y, run.origin.x + plan.width, y + thickness], color);
+        }
+    }
+}
+
+pub fn link_at(runs: &[Run], pt: Point) -> Option<&Link> {
+    runs.iter()
+        .find(|run| run.link.is_some() && run.rect.includes(pt))
+        .and_then(|run| run.link.as_ref())
+}
+
+// Works are opened and other pages of the archive at `base_url` are loaded
+// as indexes. Links to other sites can't be followed.
+pub fn link_event(link: &Link, base_url: &Url) -> Option<Event> {
+    let mut url = base_url.join(&link.location).ok()?;
+    let host = |url: &Url| url.host_str().map(|host| host.trim_start_matches("www.").to_string());
+    if host(&url) != host(base_url) {
+        return None;
+    }
+    // The same archive, whatever the scheme or subdomain the link went for.
+    url.set_scheme(base_url.scheme()).ok()?;
+    url.set_host(base_url.host_str()).ok()?;
+    url.set_port(base_url.port()).ok()?;
+    let relative = base_url.make_relative(&url).filter(|relative| !relative.starts_with(".."))?;
+    let path = format!("/{}", relative);
+    let work_re = Regex::new(r"^/works/(\d+)(?:/chapters/\d+)?/?(?:[?#].*)?$").unwrap();
+    match work_re.captures(&path) {
+        Some(caps) => Some(Event::OpenWork(caps[1].to_string())),
+        None => Some(Event::LoadIndex(path)),
+    }
+}
+
+// Laid out text that might span several pages of an overlay.
+#[derive(Clone)]
+pub struct RichText {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    runs: Vec<Run>,
+    style: Style,
+}
+
+impl RichText {
+    // The height of `rect` is adjusted to the text.
+    pub fn new(rect: Rectangle, items: &[HtmlText], style: Style, line_height: i32, fonts: &mut Fonts) -> RichText {
+        let runs = layout(items, rect, &style, line_height, fonts);
+        let mut text = RichText {
+            id: ID_FEEDER.next(),
+            rect,
+            children: Vec::new(),
+            runs,
+            style,
+        };
+        text.fit();
+        text
+    }
+
+    fn fit(&mut self) {
+        if let Some(first) = self.runs.first() {
+            self.rect.min.y = first.rect.min.y;
+        }
+        self.rect.max.y = self.runs.iter().map(|run| run.rect.max.y).max().unwrap_or(self.rect.min.y);
+    }
+
+    // Moves the lines that end below `max_y` to a new text starting at `top`.
+    pub fn split(&mut self, max_y: i32, top: i32) -> RichText {
+        let index = self.runs.iter().position(|run| run.rect.max.y > max_y).unwrap_or(self.runs.len());
+        let mut runs = self.runs.split_off(index);
+        let offset = runs.first().map_or(0, |run| run.rect.min.y - top);
+        for run in &mut runs {
+            run.origin.y -= offset;
+            run.rect.min.y -= offset;
+            run.rect.max.y -= offset;
+        }
+        self.fit();
+
+        let mut text = RichText {
+            id: ID_FEEDER.next(),
+            rect: rect![self.rect.min.x, top, self.rect.max.x, top],
+            children: Vec::new(),
+            runs,
+            style: self.style.clone(),
+        };
+        text.fit();
+        text
+    }
+}
+
+impl View for RichText {
+    fn handle_event(&mut self, evt: &Event, _hub: &Hub, bus: &mut Bus, _rq: &mut RenderQueue, context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                let base_url = &context.settings.ao3.base_url;
+                if let Some(event) = link_at(&self.runs, center).and_then(|link| link_event(link, base_url)) {
+                    bus.push_back(event);
+                    true
+                } else {
+                    false
+                }
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        render_runs(fb, &self.runs, &self.style, TEXT_NORMAL[1], fonts);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_summaryLinks_WHEN_linkEventIsCalled_THEN_worksWillBeOpenedAndOtherPagesLoaded() {
+        // GIVEN summary links
+        let link = |location: &str| Link { title: "link".to_string(), location: location.to_string() };
+        let base_url = Url::parse("https://archiveofourown.org/").unwrap();
+
+        // WHEN link_event is called
+        let work = link_event(&link("https://www.archiveofourown.org/works/123"), &base_url);
+        let chapter = link_event(&link("/works/123/chapters/456"), &base_url);
+        let tag = link_event(&link("/tags/Fluff/works"), &base_url);
+        let home = link_event(&link("https://archiveofourown.org"), &base_url);
+        let external = link_event(&link("https://example.org/works/123"), &base_url);
+
+        // THEN works will be opened and other pages loaded
+        assert!(matches!(work, Some(Event::OpenWork(ref id)) if id == "123"));
+        assert!(matches!(chapter, Some(Event::OpenWork(ref id)) if id == "123"));
+        assert!(matches!(tag, Some(Event::LoadIndex(ref path)) if path == "/tags/Fluff/works"));
+        assert!(matches!(home, Some(Event::LoadIndex(ref path)) if path == "/"));
+        assert!(external.is_none());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn GIVEN_aMirror_WHEN_linkEventIsCalled_THEN_onlyItsLinksWillBeFollowed() {
+        // GIVEN a mirror
+        let link = |location: &str| Link { title: "link".to_string(), location: location.to_string() };
+        let base_url = Url::parse("https://ao3.example.org/").unwrap();
+
+        // WHEN link_event is called
+        let work = link_event(&link("https://ao3.example.org/works/123"), &base_url);
+        let archive = link_event(&link("https://archiveofourown.org/works/123"), &base_url);
+
+        // THEN only its links will be followed
+        assert!(matches!(work, Some(Event::OpenWork(ref id)) if id == "123"));
+        assert!(archive.is_none());
+    }
+}
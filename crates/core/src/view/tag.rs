@@ -14,6 +14,8 @@ pub struct TagInfo {
     pub text: String,
     pub location: Option<String>,
     pub style: Style,
+    // Sent instead of loading the location when tapped.
+    pub event: Option<Event>,
 }
 
 #[derive(Clone, Debug)]
@@ -28,8 +30,18 @@ impl TagInfo {
             text,
             location,
             style,
+            event: None,
         }
     }
+
+    pub fn event(mut self, event: Option<Event>) -> TagInfo {
+        self.event = event;
+        self
+    }
+
+    pub fn is_label(&self) -> bool {
+        self.location.is_none() && self.event.is_none()
+    }
 }
 
 impl TagElement {
@@ -86,7 +98,7 @@ impl Tag {
         let mut has_loc = false;
         let mut pt;
 
-        if let Some(_loc) = info.location.clone() {
+        if !info.is_label() {
             has_loc = true;
         }
 
@@ -247,7 +259,11 @@ impl View for Tag {
             Event::Gesture(GestureEvent::Tap(center)) if self.in_rects(center) => {
                 rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
                 self.active = true;
-                bus.push_back(Event::LoadIndex(self.info.location.clone().unwrap()));
+                let event = self.info.event.clone()
+                                .or_else(|| self.info.location.clone().map(Event::LoadIndex));
+                if let Some(event) = event {
+                    bus.push_back(event);
+                }
                 true
             }
             _ => false,
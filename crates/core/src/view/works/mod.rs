@@ -2,24 +2,25 @@ mod title_bar;
 mod works_label;
 pub mod work;
 pub mod workindex;
+pub mod subscription;
 mod bottom_bar;
 
 use rand_core::RngCore;
-use anyhow::Error;
 use crate::framebuffer::{Framebuffer, UpdateMode};
 use crate::view::{View, Event, Hub, Bus, RenderQueue, RenderData};
-use crate::view::{Id, ID_FEEDER, ViewId, EntryId};
+use crate::view::{Id, ID_FEEDER, ViewId, EntryId, EntryKind};
 use crate::view::{SMALL_BAR_HEIGHT, BIG_BAR_HEIGHT, THICKNESS_MEDIUM};
 use crate::view::common::{toggle_main_menu, toggle_battery_menu, toggle_clock_menu};
-use crate::view::common::{locate, rlocate, locate_by_id};
+use crate::view::common::{locate, rlocate, locate_by_id, subscription_entries};
 use crate::view::filler::Filler;
 use crate::view::keyboard::Keyboard;
 use crate::view::named_input::NamedInput;
-use crate::view::menu::Menu;
+use crate::view::menu::{Menu, MenuKind};
 use crate::view::notification::Notification;
 use crate::view::search_bar::SearchBar;
 use super::top_bar::TopBar;
-use self::workindex::WorkIndex;
+use self::workindex::{WorkIndex, IndexData, FetchedPages};
+use self::subscription::SubscriptionItem;
 use self::bottom_bar::BottomBar;
 use crate::gesture::GestureEvent;
 use crate::geom::{Rectangle, halves};
@@ -31,17 +32,18 @@ use crate::context::Context;
 
 pub const TRASH_DIRNAME: &str = ".trash";
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HistoryView {
     Full,
     MarkedForLater
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IndexType {
     TagWorks,
     History(HistoryView),
     Search(String),
+    Subscriptions,
 }
 
 
@@ -60,7 +62,7 @@ pub struct Works {
 }
 
 impl Works {
-    pub fn new(rect: Rectangle, index_url: String, hub: &Hub, rq: &mut RenderQueue, context: &mut Context, index_type: IndexType) -> Result<Works, Error> {
+    pub fn new(rect: Rectangle, index_data: IndexData, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) -> Works {
         let id = ID_FEEDER.next();
         let dpi = CURRENT_DEVICE.dpi;
         let mut children = Vec::new();
@@ -78,15 +80,14 @@ impl Works {
 
         let y_start = rect.min.y + small_height + big_thickness;
 
-        let mut workindex = WorkIndex::new(rect![rect.min.x, y_start,
-                                         rect.max.x, rect.max.y],
-                                   false,
-                                   index_url,
-                                   hub,
-                                   context,
-                                index_type.clone());
+        let mut workindex = WorkIndex::from_data(rect![rect.min.x, y_start,
+                                               rect.max.x, rect.max.y],
+                                         false,
+                                         index_data,
+                                         hub,
+                                         context);
 
-        workindex.get_works(context, &mut RenderQueue::new());
+        workindex.get_works(hub, &mut RenderQueue::new());
 
         let current_page = workindex.current_page;
         let pages_count = workindex.max_page;
@@ -99,7 +100,7 @@ impl Works {
 
         rq.add(RenderData::new(id, rect, UpdateMode::Full));
 
-        Ok(Works {
+        Works {
             id,
             rect,
             children,
@@ -111,7 +112,7 @@ impl Works {
             works_lines,
             // index_type,
             query: None
-        })
+        }
     }
 
     // NOTE: This function assumes that the workindex wasn't resized.
@@ -138,7 +139,7 @@ impl Works {
         self.update_shelf(false, hub, rq, context);
     }
 
-    fn update_shelf(&mut self, _was_resized: bool, _hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
+    fn update_shelf(&mut self, _was_resized: bool, hub: &Hub, rq: &mut RenderQueue, _context: &mut Context) {
         let dpi = CURRENT_DEVICE.dpi;
         let big_height = scale_by_dpi(BIG_BAR_HEIGHT, dpi) as i32;
         let thickness = scale_by_dpi(THICKNESS_MEDIUM, dpi) as i32;
@@ -167,7 +168,7 @@ impl Works {
         // }
 
         workindex.set_page(self.current_page);
-        workindex.get_works(context, rq);
+        workindex.get_works(hub, rq);
     }
 
     // fn update_top_bar(&mut self, search_visible: bool, rq: &mut RenderQueue) {
@@ -190,6 +191,97 @@ impl Works {
         }
     }
 
+    fn toggle_work_menu(&mut self, work_id: &str, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::WorkMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let workindex = self.children[self.shelf_index].downcast_ref::<WorkIndex>().unwrap();
+            let Some(info) = workindex.work_info(work_id) else {
+                return;
+            };
+
+            let mut entries = vec![EntryKind::Command("About This Work".to_string(),
+                                                      EntryId::AboutWork(info.id.clone()))];
+            // Deleted works don't have an id.
+            if context.client.logged_in() && info.id != "0" {
+                entries.push(EntryKind::Separator);
+                entries.push(EntryKind::Command("Mark for Later".to_string(),
+                                                EntryId::MarkForLater(info.id.clone(), info.title.clone())));
+                entries.push(EntryKind::Command("Mark as Read".to_string(),
+                                                EntryId::MarkAsRead(info.id.clone(), info.title.clone())));
+                entries.push(EntryKind::Separator);
+                entries.extend(subscription_entries(info));
+            }
+
+            let work_menu = Menu::new(rect, ViewId::WorkMenu, MenuKind::Contextual, entries, context);
+            rq.add(RenderData::new(work_menu.id(), *work_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(work_menu) as Box<dyn View>);
+        }
+    }
+
+    fn toggle_subscription_menu(&mut self, path: &str, rect: Rectangle, enable: Option<bool>, rq: &mut RenderQueue, context: &mut Context) {
+        if let Some(index) = locate_by_id(self, ViewId::SubscriptionMenu) {
+            if let Some(true) = enable {
+                return;
+            }
+
+            rq.add(RenderData::expose(*self.child(index).rect(), UpdateMode::Gui));
+            self.children.remove(index);
+        } else {
+            if let Some(false) = enable {
+                return;
+            }
+
+            let workindex = self.children[self.shelf_index].downcast_ref::<WorkIndex>().unwrap();
+            let Some(item) = workindex.children().iter()
+                                      .filter_map(|child| child.downcast_ref::<SubscriptionItem>())
+                                      .find(|item| item.subscription.path == path) else {
+                return;
+            };
+
+            let subscription = &item.subscription;
+            let entries = vec![EntryKind::Command("Unsubscribe".to_string(),
+                                                  EntryId::Unsubscribe(subscription.path.clone(), subscription.name.clone()))];
+
+            let subscription_menu = Menu::new(rect, ViewId::SubscriptionMenu, MenuKind::Contextual, entries, context);
+            rq.add(RenderData::new(subscription_menu.id(), *subscription_menu.rect(), UpdateMode::Gui));
+            self.children.push(Box::new(subscription_menu) as Box<dyn View>);
+        }
+    }
+
+    // Only the lists showing what changed get fetched again.
+    fn refresh_index(&mut self, index_type: IndexType, hub: &Hub, context: &mut Context) {
+        let workindex = self.children[self.shelf_index].as_mut().downcast_mut::<WorkIndex>().unwrap();
+        if workindex.index_type != index_type {
+            return;
+        }
+
+        let request = workindex.invalidate(context);
+        hub.send(Event::ReloadIndex(Box::new(request))).ok();
+    }
+
+    // Takes the pages fetched for its index.
+    pub fn take_pages(&mut self, fetched: &FetchedPages, hub: &Hub, rq: &mut RenderQueue) {
+        let workindex = self.children[self.shelf_index].as_mut().downcast_mut::<WorkIndex>().unwrap();
+        if workindex.id() != fetched.id {
+            return;
+        }
+
+        workindex.take_pages(fetched, hub, rq);
+        self.current_page = workindex.current_page;
+        self.pages_count = workindex.max_page;
+        self.works_count = workindex.max_works;
+    }
+
     fn toggle_keyboard(&mut self, enable: bool, update: bool, id: Option<ViewId>, hub: &Hub, rq: &mut RenderQueue, context: &mut Context) {
         let dpi = CURRENT_DEVICE.dpi;
         let (small_height, big_height) = (scale_by_dpi(SMALL_BAR_HEIGHT, dpi) as i32,
@@ -411,7 +503,7 @@ impl Works {
 }
 
 impl View for Works {
-    fn handle_event(&mut self, evt: &Event, hub: &Hub, _bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
         match *evt {
             Event::Gesture(GestureEvent::Swipe { dir, start: _, end: _, .. }) => {
                 match dir {
@@ -462,6 +554,34 @@ impl View for Works {
                 hub.send(Event::SubmitInput(ViewId::SiteTextSearchInput)).ok();
                 true
             },
+            Event::ToggleWorkMenu(rect, ref work_id) => {
+                self.toggle_work_menu(work_id, rect, None, rq, context);
+                true
+            },
+            Event::Select(EntryId::AboutWork(ref work_id)) => {
+                let workindex = self.children[self.shelf_index].downcast_ref::<WorkIndex>().unwrap();
+                if let Some(info) = workindex.work_info(work_id) {
+                    bus.push_back(Event::ToggleAboutWork(info.clone()));
+                }
+                true
+            },
+            Event::MarkedForLaterChanged => {
+                self.refresh_index(IndexType::History(HistoryView::MarkedForLater), hub, context);
+                true
+            },
+            Event::ToggleSubscriptionMenu(rect, ref path) => {
+                self.toggle_subscription_menu(path, rect, None, rq, context);
+                true
+            },
+            Event::SubscriptionsChanged => {
+                self.refresh_index(IndexType::Subscriptions, hub, context);
+                true
+            },
+            Event::LatestUpdateFetched(ref path, updated) => {
+                let workindex = self.children[self.shelf_index].as_mut().downcast_mut::<WorkIndex>().unwrap();
+                workindex.set_latest_update(path, updated, rq);
+                true
+            },
             Event::Close(ViewId::MainMenu) => {
                 toggle_main_menu(self, Rectangle::default(), Some(false), rq, context);
                 true
@@ -483,14 +603,14 @@ impl View for Works {
             Event::Submit(ViewId::GoToPageInput, ref text) => {
                 let workindex = self.children[self.shelf_index].as_mut().downcast_mut::<WorkIndex>().unwrap();
                 if text == "(" {
-                    workindex.go_to_page(0, hub, rq, context);
+                    workindex.go_to_page(0, hub, rq);
                 } else if text == ")" {
-                    workindex.go_to_page(self.pages_count.saturating_sub(1), hub, rq, context);
+                    workindex.go_to_page(self.pages_count.saturating_sub(1), hub, rq);
                 } else if text == "_" {
                     let index = (context.rng.next_u64() % self.pages_count as u64) as usize;
-                    workindex.go_to_page(index, hub, rq, context);
+                    workindex.go_to_page(index, hub, rq);
                 } else if let Ok(index) = text.parse::<usize>() {
-                    workindex.go_to_page(index.saturating_sub(1), hub, rq, context);
+                    workindex.go_to_page(index.saturating_sub(1), hub, rq);
                 }
                 true
             },
@@ -513,33 +633,8 @@ impl View for Works {
                 true
             },
             Event::GoToTag(ref loc) => {
-
-                let shelf_index = self.shelf_index.clone();
-                let prev_workindex = self.child(shelf_index).downcast_ref::<WorkIndex>().unwrap();
-                let rect = prev_workindex.rect().clone();
-
-                let mut workindex = WorkIndex::new(rect,
-                            false,
-                            loc.clone(),
-                            hub,
-                            context,
-                        IndexType::TagWorks);
-
-
-                let current_page = workindex.current_page;
-                let pages_count = workindex.max_page;
-
-                self.pages_count = pages_count;
-                self.current_page = current_page;
-                self.works_count = workindex.max_works;
-                self.works_lines = workindex.max_lines;
-
-                workindex.get_works(context, rq);
-
-                self.children_mut().push(Box::new(workindex) as Box<dyn View>);
-                self.children_mut().swap_remove(shelf_index);
-                self.update_bottom_bar(rq);
-                false
+                hub.send(Event::LoadIndex(loc.clone())).ok();
+                true
             },
             Event::ToggleFrontlight => {
                 if let Some(index) = locate::<TopBar>(self) {
@@ -0,0 +1,138 @@
+use chrono::NaiveDate;
+use crate::device::CURRENT_DEVICE;
+use crate::framebuffer::{Framebuffer, UpdateMode};
+use crate::view::{View, Event, Hub, Bus, Id, ID_FEEDER, RenderQueue, RenderData};
+use crate::font::{MD_KIND, MD_AUTHOR, WORK_LARGE};
+use crate::color::{TEXT_NORMAL, TEXT_INVERTED_HARD};
+use crate::gesture::GestureEvent;
+use crate::http::subscriptions::{Subscription, SubscriptionKind};
+use crate::font::{Fonts, font_from_style};
+use crate::geom::Rectangle;
+use crate::context::Context;
+
+// A work, series or user from the subscriptions, with its latest update
+// once it's known.
+#[derive(Clone)]
+pub struct SubscriptionItem {
+    id: Id,
+    rect: Rectangle,
+    children: Vec<Box<dyn View>>,
+    pub subscription: Subscription,
+    pub updated: Option<NaiveDate>,
+    active: bool,
+}
+
+impl SubscriptionItem {
+    pub fn new(rect: Rectangle, subscription: Subscription, updated: Option<NaiveDate>) -> SubscriptionItem {
+        SubscriptionItem {
+            id: ID_FEEDER.next(),
+            rect,
+            children: vec![],
+            subscription,
+            updated,
+            active: false,
+        }
+    }
+
+    pub fn set_updated(&mut self, updated: NaiveDate, rq: &mut RenderQueue) {
+        self.updated = Some(updated);
+        rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+    }
+}
+
+impl View for SubscriptionItem {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, _context: &mut Context) -> bool {
+        match *evt {
+            Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                self.active = true;
+                rq.add(RenderData::new(self.id, self.rect, UpdateMode::Gui));
+                let event = match self.subscription.kind {
+                    SubscriptionKind::Work => {
+                        let work_id = self.subscription.path.trim_start_matches("/works/");
+                        Event::OpenWork(work_id.to_string())
+                    },
+                    _ => Event::LoadIndex(self.subscription.works_path()),
+                };
+                hub.send(event).ok();
+                self.active = false;
+                true
+            },
+            Event::Gesture(GestureEvent::HoldFingerShort(center, ..)) if self.rect.includes(center) => {
+                let pt = pt!(center.x, self.rect.center().y);
+                bus.push_back(Event::ToggleSubscriptionMenu(Rectangle::from_point(pt), self.subscription.path.clone()));
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut dyn Framebuffer, _rect: Rectangle, fonts: &mut Fonts) {
+        let dpi = CURRENT_DEVICE.dpi;
+
+        let scheme = if self.active {
+            TEXT_INVERTED_HARD
+        } else {
+            TEXT_NORMAL
+        };
+
+        fb.draw_rectangle(&self.rect, scheme[0]);
+
+        let (x_height, padding) = {
+            let font = font_from_style(fonts, &MD_AUTHOR, dpi);
+            (font.x_heights.0 as i32, font.em() as i32)
+        };
+        let small_baseline = {
+            let font = font_from_style(fonts, &MD_KIND, dpi);
+            font.line_height()
+        };
+        let date_width = 8 * x_height;
+        let width = self.rect.width() as i32 - date_width - 3 * padding;
+        let center_y = self.rect.min.y + self.rect.height() as i32 / 2;
+
+        // Name
+        {
+            let font = font_from_style(fonts, &WORK_LARGE, dpi);
+            let mut plan = font.plan(&self.subscription.name, None, None);
+            font.crop_right(&mut plan, width);
+            let pt = pt!(self.rect.min.x + padding, center_y + x_height);
+            font.render(fb, scheme[1], &plan, pt);
+        }
+
+        // Kind
+        {
+            let font = font_from_style(fonts, &MD_KIND, dpi);
+            let plan = font.plan(self.subscription.kind.label(), None, None);
+            let pt = pt!(self.rect.max.x - padding - plan.width, center_y);
+            font.render(fb, scheme[1], &plan, pt);
+        }
+
+        // Latest update
+        if let Some(updated) = self.updated {
+            let date = format!("{}", updated.format("%d %b %Y"));
+            let font = font_from_style(fonts, &MD_KIND, dpi);
+            let plan = font.plan(&date, None, None);
+            let pt = pt!(self.rect.max.x - padding - plan.width, center_y + small_baseline);
+            font.render(fb, scheme[1], &plan, pt);
+        }
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<dyn View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<dyn View>> {
+        &mut self.children
+    }
+
+    fn id(&self) -> Id {
+        self.id
+    }
+}
@@ -8,7 +8,8 @@ use crate::ao3_metadata::Ao3Info;
 use crate::font::{Fonts, font_from_style};
 use crate::geom::{Rectangle, halves};
 use crate::context::Context;
-use crate::html::list_to_str;
+use crate::html::{list_to_str, HtmlText};
+use crate::view::rich_text::{self, Run};
 use serde::{Serialize, Deserialize};
 
 const SIZE_BASE: f32 = 1000.0;
@@ -40,7 +41,15 @@ pub enum WorkView {
 
 impl Work {
     pub fn new(rect: Rectangle, data: String, _index: usize, preview: bool, length: WorkView) -> Work {
-        let info = Ao3Info::new(data);
+        // Broken blurbs tell which selectors failed, in short blurbs too.
+        let info = Ao3Info::new(data).unwrap_or_else(|e| {
+            eprintln!("Can't parse work blurb: {}.", e);
+            Ao3Info {
+                title: format!("Unreadable blurb ({})", e),
+                summary: vec![HtmlText::Text(format!("This blurb couldn't be read: {}.", e))],
+                .. Ao3Info::default()
+            }
+        });
         Work {
             id: ID_FEEDER.next(),
             rect,
@@ -53,10 +62,46 @@ impl Work {
     }
 }
 
+impl Work {
+    // The summary takes the bottom third of long blurbs, left of the stats.
+    fn summary_runs(&self, fonts: &mut Fonts) -> Vec<Run> {
+        let dpi = CURRENT_DEVICE.dpi;
+        let (x_height, padding) = {
+            let font = font_from_style(fonts, &MD_AUTHOR, dpi);
+            (font.x_heights.0 as i32, font.em() as i32)
+        };
+        let line_height = {
+            let font = font_from_style(fonts, &MD_YEAR, dpi);
+            (font.line_height() / 4) * 3
+        };
+        let height = self.rect.height() as i32;
+        let (small_half_padding, _big_half_padding) = halves(padding);
+        let mut width = self.rect.width() as i32 - 8 * x_height - padding - small_half_padding;
+        let mut start_x = self.rect.min.x + padding;
+        if self.preview {
+            width -= height;
+            start_x = height;
+        }
+        let rect = rect![start_x, self.rect.max.y - height / 3,
+                         start_x + width, self.rect.max.y - small_half_padding];
+        rich_text::layout(&self.info.summary, rect, &WORK_SMALL, line_height, fonts)
+    }
+}
+
 impl View for Work {
-    fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, _context: &mut Context) -> bool {
+    fn handle_event(&mut self, evt: &Event, hub: &Hub, bus: &mut Bus, rq: &mut RenderQueue, context: &mut Context) -> bool {
         match *evt {
             Event::Gesture(GestureEvent::Tap(center)) if self.rect.includes(center) => {
+                if let WorkView::Long = self.length {
+                    let runs = self.summary_runs(&mut context.fonts);
+                    let base_url = &context.settings.ao3.base_url;
+                    if let Some(event) = rich_text::link_at(&runs, center)
+                                                   .and_then(|link| rich_text::link_event(link, base_url)) {
+                        bus.push_back(event);
+                        return true;
+                    }
+                }
+
                 self.active = true;
                 let id = &self.info.id;
                 // FIXME: 0 id means we couldn't scrape one, either because of error or because
@@ -70,7 +115,8 @@ impl View for Work {
                 true
             },
             Event::Gesture(GestureEvent::HoldFingerShort(center, ..)) if self.rect.includes(center) => {
-                bus.push_back(Event::ToggleAboutWork(self.info.clone()));
+                let pt = pt!(center.x, self.rect.center().y);
+                bus.push_back(Event::ToggleWorkMenu(Rectangle::from_point(pt), self.info.id.clone()));
                 true
             },
             _ => false,
@@ -178,11 +224,12 @@ impl View for Work {
         // Tags
         match self.length {
             WorkView::Long => {
-            let tags = list_to_str(&self.info.tags, ", ");
+            let tags = self.info.tags.descriptive().map(|tag| tag.title.as_str()).collect::<Vec<_>>().join(", ");
             let font = font_from_style(fonts, &WORK_SMALL, dpi);
             let mut plan = font.plan(&tags, None, None);
             let mut tag_lines = 1;
-            let max_lines = (self.rect.max.y - start_y) / small_baseline;
+            let summary_top = self.rect.max.y - height / 3;
+            let max_lines = (summary_top - start_y) / small_baseline;
 
             while tag_lines < max_lines {
                 if plan.width > width {
@@ -208,6 +255,9 @@ impl View for Work {
                 start_y);
             font.render(fb, scheme[1], &plan, pt);
 
+            // Summary
+            let runs = self.summary_runs(fonts);
+            rich_text::render_runs(fb, &runs, &WORK_SMALL, scheme[1], fonts);
         },
         WorkView::Short => {}
     }
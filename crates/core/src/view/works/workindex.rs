@@ -10,19 +10,24 @@ use crate::view::{ THICKNESS_MEDIUM, BIG_BAR_HEIGHT, SMALL_BAR_HEIGHT };
 use crate::input::{ DeviceEvent, ButtonCode, ButtonStatus };
 use crate::unit::scale_by_dpi;
 use crate::context::Context;
+use crate::http::{Ao3Error, Freshness, HttpClient};
 use super::work::{ Work, WorkView };
+use super::subscription::SubscriptionItem;
 use super::{ HistoryView, IndexType };
 use crate::view::filler::Filler;
 use crate::html::{ scrape_many, scrape, scrape_many_outer };
-use crate::ao3_metadata::str_to_usize;
+use crate::selectors::selectors;
+use crate::ao3_metadata::{Ao3Info, str_to_usize};
+use crate::http::subscriptions::Subscription;
+use chrono::NaiveDate;
 use regex::Regex;
-use crate::helpers::{ ceil, get_url, update_url };
+use crate::helpers::{ ceil, update_url };
 use fxhash::FxHashMap;
 use url::Url;
 use super::bottom_bar::BottomBar;
 use super::title_bar::TitleBar;
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum PageStatus {
     Clean,
     Dirty,
@@ -43,23 +48,72 @@ pub struct WorkIndex {
     internal_max: usize,
     pub max_works: Option<usize>,
     pub url: Url,
+    // Where the first page was fetched from, before any page number was added.
+    source_url: Url,
+    pub index_type: IndexType,
     pub title: String,
+    // The latest updates of subscriptions, by path, as they get fetched.
+    dates: FxHashMap<String, NaiveDate>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct IndexPage {
     pub works: Vec<String>,
     pub status: PageStatus,
 }
 
+// Everything needed to build an index without touching the network.
+#[derive(Debug, Clone)]
+pub struct IndexData {
+    pub url: Url,
+    pub index_type: IndexType,
+    pub first_page: IndexPage,
+    pub internal_max: usize,
+    pub max_works: Option<usize>,
+    pub title: String,
+    pub last_page_len: usize,
+}
+
+// What an index needs fetched again to show one of its display pages.
+#[derive(Debug, Clone)]
+pub struct PagesRequest {
+    // The index asking for them.
+    pub id: Id,
+    pub url: Url,
+    pub index_type: IndexType,
+    pub display_page: usize,
+    pub max_lines: usize,
+    // The AO3 pages missing. Reloads work them out once they know the
+    // new counts.
+    pub pages: Vec<usize>,
+}
+
+// The AO3 pages fetched for an index, and the counts they came with.
+#[derive(Debug, Clone)]
+pub struct FetchedPages {
+    pub id: Id,
+    pub display_page: usize,
+    pub pages: Vec<(usize, IndexPage)>,
+    pub internal_max: usize,
+    pub max_works: Option<usize>,
+    // Only known when the whole index was fetched again.
+    pub last_page_len: Option<usize>,
+}
+
 const AO3_WORKS_PER_PAGE: usize = 20;
 
-pub fn fetch_index(url: &Url, context: &Context) -> (IndexPage, usize, Option<usize>, String) {
-    let data = context.client.get_parse(url.as_str());
-    let works = scrape_many_outer(&data, "li.work");
-    let max_works_data = scrape(&data, "h2.heading");
-    let title = scrape(&data, "h2.heading a.tag");
-    let max_page_data = scrape_many(&data, ".pagination li a");
+pub fn fetch_index(url: &Url, index_type: &IndexType, client: &HttpClient) -> Result<(IndexPage, usize, Option<usize>, String), Ao3Error> {
+    let data = client.get_parse(url.as_str(), Freshness::Index)?;
+    let profile = selectors();
+    let select = &profile.index;
+    let works = match index_type {
+        IndexType::Subscriptions => scrape_many_outer(&data, &profile.subscriptions.items),
+        _ => scrape_many_outer(&data, &select.works),
+    };
+    let max_works_data = scrape(&data, &select.heading)?;
+    // Only tag indexes link to their tag.
+    let title = scrape(&data, &select.tag).unwrap_or_default();
+    let max_page_data = scrape_many(&data, &select.pagination);
     let mut max_page_text = "1";
     if max_page_data.len() >= 2 {
         max_page_text = &max_page_data[max_page_data.len() - 2];
@@ -72,17 +126,111 @@ pub fn fetch_index(url: &Url, context: &Context) -> (IndexPage, usize, Option<us
         max_works = Some(str_to_usize(caps[1].to_string()));
     }
 
-    (IndexPage { works, status: PageStatus::Clean }, max_page, max_works, title)
+    Ok((IndexPage { works, status: PageStatus::Clean }, max_page, max_works, title))
+}
+
+pub fn fetch_index_data(source_url: &str, index_type: IndexType, client: &HttpClient) -> Result<IndexData, Ao3Error> {
+    let url = client.url(source_url);
+    let (first_page, internal_max, max_works, title) = fetch_index(&url, &index_type, client)?;
+    // Without a works count, the length of the last page tells us how many there are.
+    let last_page_len = if max_works.is_none() && internal_max > 1 {
+        let mut last_page_url = url.clone();
+        update_url(&mut last_page_url, vec![("page", &internal_max.to_string())]);
+        fetch_index(&last_page_url, &index_type, client)?.0.works.len()
+    } else {
+        first_page.works.len()
+    };
+
+    Ok(IndexData {
+        url,
+        index_type,
+        first_page,
+        internal_max,
+        max_works,
+        title,
+        last_page_len,
+    })
+}
+
+// Fetches the index again, with the pages of the display page it was on.
+pub fn refetch_index(request: &PagesRequest, client: &HttpClient) -> Result<FetchedPages, Ao3Error> {
+    let data = fetch_index_data(request.url.as_str(), request.index_type.clone(), client)?;
+    let max_page = max_page(data.max_works, data.internal_max, data.last_page_len, request.max_lines).max(1);
+    let display_page = request.display_page.min(max_page - 1);
+    let (start_page, end_page) = remote_pages(display_page, request.max_lines, data.internal_max);
+
+    let mut pages = vec![(1, data.first_page)];
+    for index in (start_page..=end_page).filter(|index| *index != 1) {
+        let (page, ..) = fetch_index(&page_url(&data.url, index), &data.index_type, client)?;
+        pages.push((index, page));
+    }
+
+    Ok(FetchedPages {
+        id: request.id,
+        display_page,
+        pages,
+        internal_max: data.internal_max,
+        max_works: data.max_works,
+        last_page_len: Some(data.last_page_len),
+    })
+}
+
+// Fetches the AO3 pages an index is missing to show a display page.
+pub fn fetch_pages(request: &PagesRequest, client: &HttpClient) -> Result<FetchedPages, Ao3Error> {
+    let mut pages = Vec::new();
+    let mut counts = (1, None);
+    for &index in &request.pages {
+        let (page, internal_max, max_works, _title) = fetch_index(&page_url(&request.url, index), &request.index_type, client)?;
+        pages.push((index, page));
+        counts = (internal_max, max_works);
+    }
+
+    Ok(FetchedPages {
+        id: request.id,
+        display_page: request.display_page,
+        pages,
+        internal_max: counts.0,
+        max_works: counts.1,
+        last_page_len: None,
+    })
+}
+
+fn page_url(url: &Url, index: usize) -> Url {
+    let mut url = url.clone();
+    update_url(&mut url, vec![("page", &index.to_string())]);
+    url
+}
+
+// The first and last AO3 pages holding the works of a display page.
+fn remote_pages(display_page: usize, max_lines: usize, internal_max: usize) -> (usize, usize) {
+    let start = max_lines * display_page;
+    let end = start + max_lines;
+    let start_page = start / AO3_WORKS_PER_PAGE + 1; // remote pages aren't 0-indexed
+    let end_page = if start % AO3_WORKS_PER_PAGE + max_lines > AO3_WORKS_PER_PAGE {
+        end / AO3_WORKS_PER_PAGE + 1
+    } else {
+        start_page
+    };
+    (start_page, end_page.min(internal_max))
+}
+
+// If we have a known number of max works, we can know exactly how many
+// display pages we have. If not, we have to guess off the max number of pages
+// on AO3, which may make our display page count slightly too high.
+fn max_page(max_works: Option<usize>, internal_max: usize, last_page_len: usize, max_lines: usize) -> usize {
+    match max_works {
+        Some(max) => ceil(max, max_lines),
+        None => ceil(((internal_max - 1) * AO3_WORKS_PER_PAGE) + last_page_len, max_lines),
+    }
 }
 
 impl WorkIndex {
-    pub fn new(
+    pub fn from_data(
         rect: Rectangle,
         thumbnail_previews: bool,
-        source_url: String,
+        data: IndexData,
         hub: &Hub,
-        context: &Context,
-        index_type: IndexType
+        context: &Context
     ) -> WorkIndex {
         let dpi = CURRENT_DEVICE.dpi;
         let big_height = scale_by_dpi(BIG_BAR_HEIGHT, dpi) as i32;
@@ -94,11 +242,11 @@ impl WorkIndex {
         let height = (rect.height() as i32) - (2 * small_height + 2 * small_thickness);
         let work_height = match work_display {
             WorkView::Short => big_height,
-            WorkView::Long => 2 * big_height,
+            WorkView::Long => 3 * big_height,
         };
 
         let max_lines = (height / work_height) as usize;
-        let url = get_url(&source_url);
+        let IndexData { url, index_type, first_page: index_data, internal_max, max_works, title: index_title, last_page_len } = data;
         let works_rect = rect![
             rect.min.x,
             rect.min.y + small_height + small_thickness,
@@ -106,25 +254,14 @@ impl WorkIndex {
             rect.max.y - small_height - small_thickness
         ];
 
-        let (index_data, internal_max, max_works, index_title) = fetch_index(&url, context);
-        let title = match index_type {
+        let title = match &index_type {
             IndexType::TagWorks => index_title,
             IndexType::History(HistoryView::MarkedForLater) => "Marked For Later".to_string(),
             IndexType::History(HistoryView::Full) => "History".to_string(),
-            IndexType::Search(ref query) => format!("Search: {}", query)
-        };
-        // If we have a known number of max works, we can know exactly how many
-        // display pages we have. If not, we have to guess off the max number of pages
-        // on AO3, which may make our display page count slightly too high.
-        let max_page = match max_works {
-            Some(max) => ceil(max, max_lines),
-            None => {
-                let mut last_page_url = url.clone();
-                update_url(&mut last_page_url, vec![("page", &internal_max.to_string())]);
-                let (index, _max_pages, _max_works, _title) = fetch_index(&last_page_url, context);
-                ceil(((internal_max - 1) * AO3_WORKS_PER_PAGE) + index.works.len(), max_lines)
-            },
+            IndexType::Search(query) => format!("Search: {}", query),
+            IndexType::Subscriptions => "Subscriptions".to_string(),
         };
+        let max_page = max_page(max_works, internal_max, last_page_len, max_lines);
 
         let mut pages = FxHashMap::default();
         pages.insert(1, index_data);
@@ -163,10 +300,13 @@ impl WorkIndex {
             current_page: 0,
             max_page,
             internal_max,
+            source_url: url.clone(),
             url,
+            index_type,
             max_works,
             pages,
-            title
+            title,
+            dates: FxHashMap::default(),
         }
     }
 
@@ -174,20 +314,35 @@ impl WorkIndex {
         self.thumbnail_previews = thumbnail_previews;
     }
 
-    pub fn get_works(&mut self, context: &Context, rq: &mut RenderQueue) {
-        let start = self.max_lines * self.current_page;
-        let end = start + self.max_lines;
+    pub fn get_works(&mut self, hub: &Hub, rq: &mut RenderQueue) {
+        self.show_page(self.current_page, hub, rq);
+    }
 
-        let start_page = start / 20 + 1; // remote pages aren't 0-indexed
-        let mut offset = start % 20;
-        let mut offset_end = offset + self.max_lines;
-        let mut end_page = if offset + self.max_lines > 20 { end / 20 + 1 } else { start_page };
-        if end_page > self.internal_max {
-            end_page = self.internal_max;
+    // The AO3 pages aren't fetched here: the ones missing are asked for,
+    // and the page is shown once they're taken.
+    fn show_page(&mut self, index: usize, hub: &Hub, rq: &mut RenderQueue) {
+        let (start_page, end_page) = remote_pages(index, self.max_lines, self.internal_max);
+        let missing: Vec<usize> = (start_page..=end_page)
+            .filter(|page| !matches!(self.pages.get(page), Some(IndexPage { status: PageStatus::Clean, .. })))
+            .collect();
+        if !missing.is_empty() {
+            hub.send(Event::LoadIndexPages(Box::new(PagesRequest {
+                id: self.id,
+                url: self.source_url.clone(),
+                index_type: self.index_type.clone(),
+                display_page: index,
+                max_lines: self.max_lines,
+                pages: missing,
+            }))).ok();
+            return;
         }
 
+        self.current_page = index;
+        let mut offset = (self.max_lines * index) % AO3_WORKS_PER_PAGE;
+        let mut offset_end = offset + self.max_lines;
+
         let works = if start_page == end_page {
-            let page = self.get_page(start_page, context);
+            let page = &self.pages[&start_page].works;
             if offset_end > page.len() {
                 offset_end = page.len();
             }
@@ -204,7 +359,7 @@ impl WorkIndex {
             let mut temp = Vec::new();
 
             for i in start_page..end_page + 1 {
-                let page = self.get_page(i, context);
+                let page = &self.pages[&i].works;
                 if i == start_page {
                     temp.append(&mut page[offset..].to_vec());
                 } else {
@@ -212,53 +367,82 @@ impl WorkIndex {
                 }
             }
 
-            temp[..self.max_lines].to_vec()
+            // The last pages can be short, e.g. after works were removed from the list.
+            temp.truncate(self.max_lines);
+            temp
         };
         self.update(&works, rq);
+        self.fetch_latest_updates(hub);
     }
 
-    pub fn get_page(&mut self, page: usize, context: &Context) -> Vec<String> {
-        let index_page = self.pages.get(&page);
-
-        let this_page = match index_page {
-            Some(page_data) => {
-                match page_data.status {
-                    PageStatus::Clean => { page_data.clone() }
-                    PageStatus::Dirty => {
-                        update_url(&mut self.url, vec![("page", &page.to_string())]);
-                        let (index, max_pages, max_works, _title) = fetch_index(&self.url, context);
-                        if self.max_works != max_works {
-                            self.mark_dirty(page);
-                            self.internal_max = max_pages;
-                            self.max_works = max_works;
-                        }
-                        self.pages.insert(page, index.clone());
-                        index
-                    }
-                }
-            }
-            None => {
-                update_url(&mut self.url, vec![("page", &page.to_string())]);
-                let (index, max_pages, max_works, _title) = fetch_index(&self.url, context);
-                if self.max_works != max_works {
-                    self.mark_dirty(page);
-                    self.internal_max = max_pages;
-                    self.max_works = max_works;
-                }
-                self.pages.insert(page, index.clone());
-                index
-            }
-        };
+    // The subscriptions page doesn't tell when things were updated.
+    fn fetch_latest_updates(&self, hub: &Hub) {
+        let pending: Vec<Subscription> = self.children.iter()
+            .filter_map(|child| child.downcast_ref::<SubscriptionItem>())
+            .filter(|item| item.updated.is_none())
+            .map(|item| item.subscription.clone())
+            .collect();
+        if !pending.is_empty() {
+            hub.send(Event::FetchLatestUpdates(pending)).ok();
+        }
+    }
+
+    pub fn set_latest_update(&mut self, path: &str, updated: NaiveDate, rq: &mut RenderQueue) {
+        self.dates.insert(path.to_string(), updated);
+        if let Some(item) = self.children.iter_mut()
+                                .filter_map(|child| child.downcast_mut::<SubscriptionItem>())
+                                .find(|item| item.subscription.path == path) {
+            item.set_updated(updated, rq);
+        }
+    }
 
-        this_page.works
+    pub fn work_info(&self, id: &str) -> Option<&Ao3Info> {
+        self.children.iter()
+            .filter_map(|child| child.downcast_ref::<Work>())
+            .map(|work| &work.info)
+            .find(|info| info.id == id)
     }
 
-    pub fn mark_dirty(&mut self, ignore: usize) {
+    // Forgets the cached pages once the list changed on AO3, and tells
+    // what to fetch again.
+    pub fn invalidate(&mut self, context: &Context) -> PagesRequest {
+        context.client.invalidate(self.source_url.as_str());
         for (index, page) in self.pages.iter_mut() {
-            page.status = if *index == ignore { PageStatus::Clean } else { PageStatus::Dirty };
+            context.client.invalidate(page_url(&self.url, *index).as_str());
+            page.status = PageStatus::Dirty;
+        }
+        PagesRequest {
+            id: self.id,
+            url: self.source_url.clone(),
+            index_type: self.index_type.clone(),
+            display_page: self.current_page,
+            max_lines: self.max_lines,
+            pages: Vec::new(),
         }
     }
 
+    // Takes the pages fetched for this index, and shows the display page
+    // they were fetched for.
+    pub fn take_pages(&mut self, fetched: &FetchedPages, hub: &Hub, rq: &mut RenderQueue) {
+        // The other pages moved when the number of works changed.
+        if fetched.max_works != self.max_works {
+            for page in self.pages.values_mut() {
+                page.status = PageStatus::Dirty;
+            }
+        }
+        if let Some(last_page_len) = fetched.last_page_len {
+            self.max_page = max_page(fetched.max_works, fetched.internal_max, last_page_len, self.max_lines).max(1);
+        }
+        self.internal_max = fetched.internal_max;
+        self.max_works = fetched.max_works;
+        for (index, page) in &fetched.pages {
+            self.pages.insert(*index, page.clone());
+        }
+
+        self.show_page(fetched.display_page.min(self.max_page - 1), hub, rq);
+        self.update_bottom_bar(rq);
+    }
+
     pub fn update(&mut self, metadata: &Vec<String>, rq: &mut RenderQueue) {
         self.children.drain(2..);
         let dpi = CURRENT_DEVICE.dpi;
@@ -274,14 +458,25 @@ impl WorkIndex {
                 book_heights[index] -
                 (if index < self.max_lines - 1 { small_thickness } else { 0 });
 
-            let work = Work::new(
-                rect![self.works_rect.min.x, y_min, self.works_rect.max.x, y_max],
-                info.clone(),
-                index,
-                self.thumbnail_previews,
-                self.work_display.clone()
-            );
-            self.children.push(Box::new(work) as Box<dyn View>);
+            let rect = rect![self.works_rect.min.x, y_min, self.works_rect.max.x, y_max];
+            let subscription = match self.index_type {
+                IndexType::Subscriptions => Subscription::new(info),
+                _ => None,
+            };
+            if let Some(subscription) = subscription {
+                let updated = self.dates.get(&subscription.path).copied();
+                let item = SubscriptionItem::new(rect, subscription, updated);
+                self.children.push(Box::new(item) as Box<dyn View>);
+            } else {
+                let work = Work::new(
+                    rect,
+                    info.clone(),
+                    index,
+                    self.thumbnail_previews,
+                    self.work_display.clone()
+                );
+                self.children.push(Box::new(work) as Box<dyn View>);
+            }
 
             if index < self.max_lines - 1 {
                 let separator = Filler::new(
@@ -314,15 +509,13 @@ impl WorkIndex {
     pub fn go_to_page(
         &mut self,
         index: usize,
-        _hub: &Hub,
-        rq: &mut RenderQueue,
-        context: &mut Context
+        hub: &Hub,
+        rq: &mut RenderQueue
     ) {
         if index >= self.max_page {
             return;
         }
-        self.current_page = index;
-        self.get_works(context, rq);
+        self.show_page(index, hub, rq);
         self.update_bottom_bar(rq);
     }
 
@@ -339,22 +532,21 @@ impl WorkIndex {
     fn go_to_neighbor(
         &mut self,
         dir: CycleDir,
-        _hub: &Hub,
-        rq: &mut RenderQueue,
-        context: &mut Context
+        hub: &Hub,
+        rq: &mut RenderQueue
     ) {
-        match dir {
+        let index = match dir {
             CycleDir::Next if self.current_page < self.max_page.saturating_sub(1) => {
-                self.current_page += 1;
+                self.current_page + 1
             }
             CycleDir::Previous if self.current_page > 0 => {
-                self.current_page -= 1;
+                self.current_page - 1
             }
             _ => {
                 return;
             }
-        }
-        self.get_works(context, rq);
+        };
+        self.show_page(index, hub, rq);
         self.update_bottom_bar(rq);
     }
 }
@@ -371,14 +563,14 @@ impl View for WorkIndex {
         match *evt {
             Event::Submit(ViewId::GoToPageInput, ref text) => {
                 if text == "(" {
-                    self.go_to_page(0, hub, rq, context);
+                    self.go_to_page(0, hub, rq);
                 } else if text == ")" {
-                    self.go_to_page(self.max_page.saturating_sub(1), hub, rq, context);
+                    self.go_to_page(self.max_page.saturating_sub(1), hub, rq);
                 } else if text == "_" {
                     let index = (context.rng.next_u64() % (self.max_page as u64)) as usize;
-                    self.go_to_page(index, hub, rq, context);
+                    self.go_to_page(index, hub, rq);
                 } else if let Ok(index) = text.parse::<usize>() {
-                    self.go_to_page(index.saturating_sub(1), hub, rq, context);
+                    self.go_to_page(index.saturating_sub(1), hub, rq);
                 }
                 true
             }
@@ -396,11 +588,11 @@ impl View for WorkIndex {
                 }
             }
             Event::Page(dir) => {
-                self.go_to_neighbor(dir, hub, rq, context);
+                self.go_to_neighbor(dir, hub, rq);
                 true
             }
             Event::GoTo(location) => {
-                self.go_to_page(location as usize, hub, rq, context);
+                self.go_to_page(location as usize, hub, rq);
                 true
             }
             Event::Device(
@@ -410,7 +602,7 @@ impl View for WorkIndex {
                     ..
                 },
             ) => {
-                self.go_to_neighbor(CycleDir::Previous, hub, rq, context);
+                self.go_to_neighbor(CycleDir::Previous, hub, rq);
                 true
             }
             Event::Device(
@@ -420,7 +612,7 @@ impl View for WorkIndex {
                     ..
                 },
             ) => {
-                self.go_to_neighbor(CycleDir::Next, hub, rq, context);
+                self.go_to_neighbor(CycleDir::Next, hub, rq);
                 true
             }
             _ => false,
@@ -7,11 +7,11 @@ use std::path::Path;
 use std::time::Duration;
 use ao3reader_core::anyhow::{Error, Context as ResultExt};
 use ao3reader_core::chrono::Local;
-use ao3reader_core::helpers::get_url;
 use ao3reader_core::view::overlay::about::About;
 use ao3reader_core::view::works::HistoryView;
 use ao3reader_core::view::works::IndexType;
 use ao3reader_core::view::works::Works;
+use ao3reader_core::view::works::workindex::FetchedPages;
 use sdl2::event::Event as SdlEvent;
 use sdl2::keyboard::{Scancode, Keycode, Mod};
 use sdl2::render::{WindowCanvas, BlendMode};
@@ -21,7 +21,7 @@ use sdl2::rect::Point as SdlPoint;
 use sdl2::rect::Rect as SdlRect;
 use ao3reader_core::framebuffer::{Framebuffer, UpdateMode};
 use ao3reader_core::input::{DeviceEvent, FingerStatus, ButtonCode, ButtonStatus};
-use ao3reader_core::document::sys_info_as_html;
+use ao3reader_core::document::{sys_info_as_html, outbox_as_html};
 use ao3reader_core::view::{View, Event, ViewId, EntryId, AppCmd, EntryKind};
 use ao3reader_core::view::{process_render_queue, wait_for_all, handle_event, RenderQueue, RenderData};
 use ao3reader_core::view::home::Home;
@@ -35,6 +35,7 @@ use ao3reader_core::view::dictionary::Dictionary;
 use ao3reader_core::view::touch_events::TouchEvents;
 use ao3reader_core::view::rotation_values::RotationValues;
 use ao3reader_core::view::common::{locate, locate_by_id, transfer_notifications, overlapping_rectangle};
+use ao3reader_core::view::common::{show_loading, hide_loading};
 use ao3reader_core::view::common::{toggle_input_history_menu, toggle_keyboard_layout_menu};
 use ao3reader_core::helpers::{save_toml};
 use ao3reader_core::settings::{SETTINGS_PATH, IntermKind};
@@ -42,11 +43,11 @@ use ao3reader_core::geom::{Rectangle, Axis};
 use ao3reader_core::gesture::{GestureEvent, gesture_events};
 use ao3reader_core::device::CURRENT_DEVICE;
 use ao3reader_core::context::Context;
+use ao3reader_core::http::pool::FetchPool;
+use ao3reader_core::http::{update_session, clear_session, Ao3Error};
+use ao3reader_core::http::outbox::Ao3Action;
 use ao3reader_core::pt;
 use ao3reader_core::png;
-use reqwest::blocking::Client;
-use reqwest::redirect::Policy;
-use reqwest::StatusCode;
 
 pub const APP_NAME: &str = "AO3 Reader";
 const DEFAULT_ROTATION: i8 = 1;
@@ -204,6 +205,60 @@ impl Framebuffer for FBCanvas {
     }
 }
 
+// Marks a work or updates a subscription right away, or once AO3 can be reached.
+fn send_action(action: Ao3Action, title: String, fetch_pool: &mut FetchPool, hub: &mpsc::Sender<Event>, context: &Context) {
+    if context.online {
+        fetch_pool.send_action(&context.client, action, title);
+    } else {
+        let res = Err(Ao3Error::Network("offline".to_string()));
+        hub.send(Event::ActionSent(Box::new(action), title, res)).ok();
+    }
+}
+
+// Shows what AO3 answered, and has the lists that changed fetched again.
+#[allow(clippy::too_many_arguments)]
+fn action_sent(action: Ao3Action, title: String, res: Result<Vec<String>, Ao3Error>, view: &mut dyn View, history: &mut [Box<dyn View>], hub: &mpsc::Sender<Event>, rq: &mut RenderQueue, context: &mut Context) {
+    let (evt, queued, failed) = match action {
+        Ao3Action::Subscribe { .. } | Ao3Action::Unsubscribe { .. } =>
+            (Event::SubscriptionsChanged, "The subscription will be updated once AO3 can be reached.",
+             "Sorry, we were unable to update the subscription."),
+        _ => (Event::MarkedForLaterChanged, "The work will be marked once AO3 can be reached.",
+              "Sorry, we were unable to mark the work."),
+    };
+    let messages = match res {
+        Ok(messages) => {
+            if view.is::<Works>() {
+                view.handle_event(&evt, hub, &mut VecDeque::new(), rq, context);
+            }
+            for item in history.iter_mut().filter(|item| item.is::<Works>()) {
+                item.handle_event(&evt, hub, &mut VecDeque::new(), &mut RenderQueue::new(), context);
+            }
+            messages
+        },
+        Err(e) if e.is_transient() => {
+            context.outbox.push(action, title);
+            vec![queued.to_string()]
+        },
+        Err(e) => vec![format!("{} {}", failed, e)],
+    };
+    for msg in messages {
+        let notif = Notification::new(msg, hub, rq, context);
+        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+    }
+}
+
+// Lists fetched again after an action changed them on AO3.
+fn reload_index(fetched: &FetchedPages, view: &mut dyn View, history: &mut [Box<dyn View>], hub: &mpsc::Sender<Event>, rq: &mut RenderQueue) {
+    if let Some(works) = view.downcast_mut::<Works>() {
+        works.take_pages(fetched, hub, rq);
+    }
+    for item in history.iter_mut() {
+        if let Some(works) = item.downcast_mut::<Works>() {
+            works.take_pages(fetched, hub, &mut RenderQueue::new());
+        }
+    }
+}
+
 fn main() -> Result<(), Error> {
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
@@ -218,7 +273,9 @@ fn main() -> Result<(), Error> {
     fb.set_blend_mode(BlendMode::Blend);
 
     let mut context = Context::new_from_virtual(Box::new(FBCanvas(fb)));
-    context.client.renew_login();
+    if let Err(e) = context.client.renew_login() {
+        println!("Can't renew login: {}", e);
+    }
 
     if context.settings.import.startup_trigger {
         context.batch_import();
@@ -226,6 +283,8 @@ fn main() -> Result<(), Error> {
 
     context.load_dictionaries();
     context.load_keyboard_layouts();
+    context.load_selectors();
+    context.online = context.settings.wifi;
 
     // Add input sources into a single FIFO queue
     let (tx, rx) = mpsc::channel();
@@ -248,9 +307,14 @@ fn main() -> Result<(), Error> {
     });
 
     let mut history: Vec<Box<dyn View>> = Vec::new();
+    let mut fetch_pool = FetchPool::new(&tx);
+    // Whether to edit the bookmark of the work being opened, when asked from an index.
+    let mut pending_bookmark = false;
+    // The indexes being fetched again, after an action changed them.
     let mut rq = RenderQueue::new();
     let mut view: Box<dyn View> = Box::new(Home::new(context.fb.rect(), &mut rq,
-            context.settings.time_format.clone(), &mut context.fonts, &mut context.battery, context.settings.frontlight, context.client.logged_in, &context.settings.ao3.faves));
+            context.settings.time_format.clone(), &mut context.fonts, &mut context.battery, context.settings.frontlight, context.client.logged_in(),
+            context.settings.ao3.username.as_deref(), &context.settings.ao3.faves));
 
     let mut updating = Vec::new();
 
@@ -270,6 +334,10 @@ fn main() -> Result<(), Error> {
 
     let mut bus = VecDeque::with_capacity(4);
 
+    if context.settings.ao3.secrets_unreadable {
+        tx.send(Event::Notify("Your saved AO3 login couldn't be read. Please log in again.".to_string())).ok();
+    }
+
     // Handle the inputs
     // TODO - why are these different between the Kobo app and the emulator
     'outer: loop {
@@ -405,180 +473,247 @@ fn main() -> Result<(), Error> {
                     }
                 },
                 Event::OpenWork(id) => {
-                    let uri = format!("https://archiveofourown.org/works/{}?view_full_work=true&view_adult=true", id);
-                    let html = context.client.get_html(&uri);
-                    let r = Reader::from_ao3(context.fb.rect(), &html, Some(&uri), &tx, &mut context);
-                    let mut next_view = Box::new(r) as Box<dyn View>;
-                    transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                    history.push(view as Box<dyn View>);
-                    view = next_view;
-    
+                    pending_bookmark = false;
+                    fetch_pool.cancel_all();
+                    fetch_pool.open_work(&context.client, id, context.settings.ao3.lazy_load_words);
+                    show_loading(view.as_mut(), "Loading work…".to_string(), &mut rq, &mut context);
                 },
-                Event::OpenHtml(ref html, ref link_uri) => {
+                Event::Select(EntryId::EditBookmark(work_id)) => {
+                    fetch_pool.cancel_all();
+                    fetch_pool.open_work(&context.client, work_id, context.settings.ao3.lazy_load_words);
+                    pending_bookmark = true;
+                    show_loading(view.as_mut(), "Loading work…".to_string(), &mut rq, &mut context);
+                },
+                Event::Select(EntryId::MarkForLater(work_id, title)) => {
+                    send_action(Ao3Action::MarkForLater { work_id }, title, &mut fetch_pool, &tx, &context);
+                },
+                Event::Select(EntryId::MarkAsRead(work_id, title)) => {
+                    send_action(Ao3Action::MarkAsRead { work_id }, title, &mut fetch_pool, &tx, &context);
+                },
+                Event::Select(EntryId::Subscribe(path, name)) => {
+                    send_action(Ao3Action::Subscribe { path }, name, &mut fetch_pool, &tx, &context);
+                },
+                Event::Select(EntryId::Unsubscribe(path, name)) => {
+                    send_action(Ao3Action::Unsubscribe { path }, name, &mut fetch_pool, &tx, &context);
+                },
+                Event::ActionSent(action, title, res) => {
+                    action_sent(*action, title, res, view.as_mut(), &mut history, &tx, &mut rq, &mut context);
+                },
+                Event::ReloadIndex(request) => {
+                    fetch_pool.reload_index(&context.client, *request);
+                },
+                Event::IndexReloaded(fetched) => {
+                    reload_index(&fetched, view.as_mut(), &mut history, &tx, &mut rq);
+                },
+                Event::FetchLatestUpdates(items) => {
+                    fetch_pool.load_latest_updates(&context.client, items);
+                },
+                Event::LatestUpdateFetched(path, updated) => {
+                    let evt = Event::LatestUpdateFetched(path, updated);
+                    if view.is::<Works>() {
+                        view.handle_event(&evt, &tx, &mut VecDeque::new(), &mut rq, &mut context);
+                    }
+                    for view in history.iter_mut().filter(|view| view.is::<Works>()) {
+                        view.handle_event(&evt, &tx, &mut VecDeque::new(), &mut RenderQueue::new(), &mut context);
+                    }
+                },
+                Event::FetchChapter(path) => {
+                    fetch_pool.load_chapter(&context.client, path);
+                },
+                Event::Select(EntryId::LoadComments(path)) => {
                     view.children_mut().retain(|child| !child.is::<Menu>());
-                    let r = Reader::from_ao3(context.fb.rect(), html, link_uri.as_deref(), &tx, &mut context);
-                    let mut next_view = Box::new(r) as Box<dyn View>;
-                    transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                    history.push(view as Box<dyn View>);
-                    view = next_view;
+                    fetch_pool.load_comments(&context.client, path);
+                    show_loading(view.as_mut(), "Loading comments…".to_string(), &mut rq, &mut context);
                 },
-                Event::LoadIndex( link_uri) => {
-                    println!("loading tag {}", link_uri);
-    
-                    let url = get_url(&link_uri);
-                    let client = Client::builder().redirect(Policy::none()).build().unwrap();
-                    let res = client.get(url.as_str()).send();
-                    match res {
-                        Ok(r) => {
-                            match r.status() {
-                                StatusCode::OK => {
-                                    view.children_mut().retain(|child| !child.is::<Menu>());
-                                    let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), link_uri, &tx,
-                                                                         &mut rq, &mut context, ao3reader_core::view::works::IndexType::TagWorks)?);
-                                    transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                    history.push(view as Box<dyn View>);
-                                    view = next_view;
-                                },
-                                StatusCode::FOUND |
-                                StatusCode::MOVED_PERMANENTLY => {
-                                    // Check if we're being redirected to a different works index
-                                    // because of tag synning
-                                    if let Some(loc) = r.headers().get(reqwest::header::LOCATION) {
-                                        if let Ok(loc) = loc.to_str() {
-                                            let loc_str = loc.to_string();
-                                            if loc_str.ends_with("/works") {
-                                                view.children_mut().retain(|child| !child.is::<Menu>());
-                                                let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), loc_str, &tx,
-                                                                                     &mut rq, &mut context, ao3reader_core::view::works::IndexType::TagWorks)?);
-                                                transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                                history.push(view as Box<dyn View>);
-                                                view = next_view;
-                                            }
-                                        }
-                                    } else {
-                                        // TODO: change this when we can look at tag pages
-                                        let msg = format!("Unwrangled tag! No works available.");
-                                        let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                                    }
-                                },
-                                _ => {
-                                    println!("Got {} for {}", r.status(), link_uri);
-                                    let msg = format!("Error: {}", r.status());
-                                    let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                Event::Select(EntryId::Download(work_id, format)) => {
+                    fetch_pool.download(&context.client, work_id, format, context.settings.ao3.download_path.clone());
+                    show_loading(view.as_mut(), format!("Downloading {}…", format), &mut rq, &mut context);
+                },
+                Event::WorkDownloaded(fetch_id, path) => {
+                    if fetch_pool.finish(fetch_id) {
+                        hide_loading(view.as_mut(), &mut rq);
+                        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                        let notif = Notification::new(format!("Saved {}.", name), &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    }
+                },
+                Event::WorkFetched(fetch_id, ref uri, ref html) => {
+                    if fetch_pool.finish(fetch_id) {
+                        hide_loading(view.as_mut(), &mut rq);
+                        match Reader::from_ao3(context.fb.rect(), html, Some(uri), &tx, &mut context) {
+                            Ok(r) => {
+                                let mut next_view = Box::new(r) as Box<dyn View>;
+                                transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
+                                history.push(view as Box<dyn View>);
+                                view = next_view;
+                                if pending_bookmark {
+                                    pending_bookmark = false;
+                                    tx.send(Event::Show(ViewId::BookmarkEditor)).ok();
                                 }
+                            },
+                            Err(e) => {
+                                println!("Error opening {} - {}", uri, e);
+                                let notif = Notification::new(e.to_string(), &tx, &mut rq, &mut context);
+                                view.children_mut().push(Box::new(notif) as Box<dyn View>);
                             }
-                        },
-                        Err(e) => {
-                            println!("Error fetching {} - {}", link_uri, e);
-                            let msg = format!("Error: {}", e);
-                            let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                            view.children_mut().push(Box::new(notif) as Box<dyn View>);
                         }
-                    };
-    
+                    }
                 },
-                Event::LoadSearch( query) => {
-                    println!("loading search {}", query);
-    
-                    let link_uri = format!("https://archiveofourown.org/works/search?work_search%5Bquery%5D={}", query);
-                    let url = get_url(&link_uri);
-                    let client = Client::builder().redirect(Policy::none()).build().unwrap();
-                    let res = client.get(url.as_str()).send();
-                    match res {
+                Event::CommentsFetched(fetch_id, ref html, ref fields) => {
+                    if fetch_pool.finish(fetch_id) {
+                        hide_loading(view.as_mut(), &mut rq);
+                        let mut r = Reader::from_html(context.fb.rect(), html, None, &tx, &mut context);
+                        r.set_comment_fields(fields.clone());
+                        let mut next_view = Box::new(r) as Box<dyn View>;
+                        transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
+                        history.push(view as Box<dyn View>);
+                        view = next_view;
+                    }
+                },
+                Event::OpenHtml(ref html, ref link_uri) => {
+                    view.children_mut().retain(|child| !child.is::<Menu>());
+                    match Reader::from_ao3(context.fb.rect(), html, link_uri.as_deref(), &tx, &mut context) {
                         Ok(r) => {
-                            match r.status() {
-                                StatusCode::OK => {
-                                    view.children_mut().retain(|child| !child.is::<Menu>());
-                                    let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), link_uri, &tx,
-                                                                         &mut rq, &mut context, IndexType::Search(query))?);
-                                    transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                    history.push(view as Box<dyn View>);
-                                    view = next_view;
-                                },
-                                StatusCode::FOUND |
-                                    StatusCode::MOVED_PERMANENTLY => {
-                                        // Check if we're being redirected to a different works index
-                                        // because of tag synning
-                                        if let Some(loc) = r.headers().get(reqwest::header::LOCATION) {
-                                            if let Ok(loc) = loc.to_str() {
-                                                let loc_str = loc.to_string();
-                                                if loc_str.ends_with("/works") {
-                                                    view.children_mut().retain(|child| !child.is::<Menu>());
-                                                    let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), loc_str, &tx,
-                                                                                         &mut rq, &mut context, ao3reader_core::view::works::IndexType::TagWorks)?);
-                                                    transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                                    history.push(view as Box<dyn View>);
-                                                    view = next_view;
-                                                }
-                                            }
-                                        } else {
-                                            // TODO: change this when we can look at tag pages
-                                            let msg = format!("Unwrangled tag! No works available.");
-                                            let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                            view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                                        }
-                                },
-                                _ => {
-                                    println!("Got {} for {}", r.status(), link_uri);
-                                    let msg = format!("Error: {}", r.status());
-                                    let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                    view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                                }
-                            }
+                            let mut next_view = Box::new(r) as Box<dyn View>;
+                            transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
+                            history.push(view as Box<dyn View>);
+                            view = next_view;
                         },
                         Err(e) => {
-                            println!("Error fetching {} - {}", link_uri, e);
-                            let msg = format!("Error: {}", e);
-                            let notif = Notification::new(msg, &tx, &mut rq, &mut context);
+                            let notif = Notification::new(e.to_string(), &tx, &mut rq, &mut context);
                             view.children_mut().push(Box::new(notif) as Box<dyn View>);
                         }
-                    };
-    
+                    }
+                },
+                Event::LoadIndex(link_uri) => {
+                    println!("loading tag {}", link_uri);
+                    fetch_pool.cancel_all();
+                    fetch_pool.load_index(&context.client, link_uri, IndexType::TagWorks);
+                    show_loading(view.as_mut(), "Loading works…".to_string(), &mut rq, &mut context);
+                },
+                Event::LoadSearch(query) => {
+                    println!("loading search {}", query);
+                    let link_uri = context.settings.ao3.url(&format!("/works/search?work_search%5Bquery%5D={}", query)).to_string();
+                    fetch_pool.cancel_all();
+                    fetch_pool.load_index(&context.client, link_uri, IndexType::Search(query));
+                    show_loading(view.as_mut(), "Searching…".to_string(), &mut rq, &mut context);
                 },
                 Event::LoadHistory(history_view) => {
                     if let Some(ref username) = context.settings.ao3.username {
-    
-                        let mut link_uri = format!("https://archiveofourown.org/users/{}/readings", username);
+
+                        let mut link_uri = context.settings.ao3.url(&format!("/users/{}/readings", username)).to_string();
                         if let HistoryView::MarkedForLater = history_view {
                              link_uri = link_uri + "?show=to-read";
                         }
-    
-                        let res = context.client.get(&link_uri).send();
-                        match res {
-                            Ok(r) => {
-                                match r.status() {
-                                    StatusCode::OK => {
-                                        view.children_mut().retain(|child| !child.is::<Menu>());
-                                        let mut next_view: Box<dyn View> = Box::new(Works::new(context.fb.rect(), link_uri, &tx,
-                                                                             &mut rq, &mut context, IndexType::History(history_view))?);
-                                        transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
-                                        history.push(view as Box<dyn View>);
-                                        view = next_view;
-                                    },
-                                    _ => {
-                                        println!("Got {} for {}", r.status(), link_uri);
-                                        let msg = format!("Error: {}", r.status());
-                                        let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                                    }
-                                }
-                            },
-                            Err(e) => {
-                                println!("Error fetching {} - {}", link_uri, e);
-                                let msg = format!("Error: {}", e);
-                                let notif = Notification::new(msg, &tx, &mut rq, &mut context);
-                                view.children_mut().push(Box::new(notif) as Box<dyn View>);
-                            }
-                        };
+
+                        fetch_pool.cancel_all();
+                        fetch_pool.load_index(&context.client, link_uri, IndexType::History(history_view));
+                        show_loading(view.as_mut(), "Loading history…".to_string(), &mut rq, &mut context);
                     } else {
                         println!("Can't load history without a username!");
-                        let msg = format!("Can't load history without a username!");
+                        let msg = "Can't load history without a username!".to_string();
                         let notif = Notification::new(msg, &tx, &mut rq, &mut context);
                         view.children_mut().push(Box::new(notif) as Box<dyn View>);
                     }
                 },
+                Event::LoadSubscriptions => {
+                    if let Some(ref username) = context.settings.ao3.username {
+                        let link_uri = context.settings.ao3.url(&format!("/users/{}/subscriptions", username)).to_string();
+                        fetch_pool.cancel_all();
+                        fetch_pool.load_index(&context.client, link_uri, IndexType::Subscriptions);
+                        show_loading(view.as_mut(), "Loading subscriptions…".to_string(), &mut rq, &mut context);
+                    } else {
+                        let notif = Notification::new("Can't load subscriptions without a username!".to_string(), &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    }
+                },
+                Event::IndexFetched(fetch_id, data) => {
+                    if fetch_pool.finish(fetch_id) {
+                        hide_loading(view.as_mut(), &mut rq);
+                        let works = Works::new(context.fb.rect(), *data, &tx, &mut rq, &mut context);
+                        view.children_mut().retain(|child| !child.is::<Menu>());
+                        let mut next_view: Box<dyn View> = Box::new(works);
+                        transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
+                        history.push(view as Box<dyn View>);
+                        view = next_view;
+                    }
+                },
+                Event::LoadIndexPages(request) => {
+                    fetch_pool.cancel_all();
+                    fetch_pool.load_index_pages(&context.client, *request);
+                    show_loading(view.as_mut(), "Loading works…".to_string(), &mut rq, &mut context);
+                },
+                Event::IndexPagesFetched(fetch_id, fetched) => {
+                    if fetch_pool.finish(fetch_id) {
+                        hide_loading(view.as_mut(), &mut rq);
+                        if let Some(works) = view.downcast_mut::<Works>() {
+                            works.take_pages(&fetched, &tx, &mut rq);
+                        }
+                    }
+                },
+                Event::FetchProgress(fetch_id, text) => {
+                    if fetch_pool.is_pending(fetch_id) {
+                        show_loading(view.as_mut(), text, &mut rq, &mut context);
+                    }
+                },
+                Event::Login => {
+                    if context.settings.ao3.username.is_some() && context.settings.ao3.secrets.password.is_some() {
+                        fetch_pool.cancel_all();
+                        fetch_pool.login(&context.client);
+                        show_loading(view.as_mut(), "Logging in…".to_string(), &mut rq, &mut context);
+                    } else {
+                        let msg = "Add your AO3 user name and password to Settings.toml to log in.".to_string();
+                        let notif = Notification::new(msg, &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    }
+                },
+                Event::Logout => {
+                    fetch_pool.cancel_all();
+                    fetch_pool.logout(&context.client);
+                    show_loading(view.as_mut(), "Logging out…".to_string(), &mut rq, &mut context);
+                },
+                Event::SessionUpdated(fetch_id) => {
+                    if fetch_pool.finish(fetch_id) {
+                        hide_loading(view.as_mut(), &mut rq);
+                        if context.client.logged_in() {
+                            update_session(&mut context);
+                        } else {
+                            clear_session(&mut context);
+                        }
+                        if view.is::<Home>() {
+                            view = Box::new(Home::new(context.fb.rect(), &mut rq,
+                                    context.settings.time_format.clone(), &mut context.fonts, &mut context.battery, context.settings.frontlight, context.client.logged_in(),
+                                    context.settings.ao3.username.as_deref(), &context.settings.ao3.faves));
+                        }
+                    }
+                },
+                Event::OutboxReplayed(results) => {
+                    if context.client.logged_in() {
+                        update_session(&mut context);
+                    }
+                    for msg in context.outbox.finish_replay(results) {
+                        let notif = Notification::new(msg, &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    }
+                },
+                Event::Select(EntryId::RetryOutbox) => {
+                    context.outbox.retry_failed();
+                    if context.online {
+                        if let Some(actions) = context.outbox.start_replay() {
+                            fetch_pool.replay_outbox(&context.client, actions);
+                        }
+                    }
+                },
+                Event::Select(EntryId::DiscardOutbox) => {
+                    context.outbox.discard_failed();
+                },
+                Event::FetchFailed(fetch_id, e) => {
+                    if fetch_pool.finish(fetch_id) {
+                        hide_loading(view.as_mut(), &mut rq);
+                        let notif = Notification::new(e.to_string(), &tx, &mut rq, &mut context);
+                        view.children_mut().push(Box::new(notif) as Box<dyn View>);
+                    }
+                },
                 Event::Select(EntryId::Launch(app_cmd)) => {
                     view.children_mut().retain(|child| !child.is::<Menu>());
                     let mut next_view: Box<dyn View> = match app_cmd {
@@ -597,7 +732,10 @@ fn main() -> Result<(), Error> {
                     view = next_view;
                 },
                 Event::Back => {
-                    if let Some(v) = history.pop() {
+                    // Back first cancels whatever is still loading.
+                    if fetch_pool.cancel_all() {
+                        hide_loading(view.as_mut(), &mut rq);
+                    } else if let Some(v) = history.pop() {
                         view = v;
                         if view.is::<Home>() {
                             if context.display.rotation % 2 != 1 {
@@ -675,9 +813,13 @@ fn main() -> Result<(), Error> {
                     rq.add(RenderData::new(dialog.id(), *dialog.rect(), UpdateMode::Gui));
                     view.children_mut().push(Box::new(dialog) as Box<dyn View>);
                 },
-                Event::Select(EntryId::SystemInfo) => {
+                Event::Select(EntryId::SystemInfo) | Event::Select(EntryId::ShowOutbox) => {
                     view.children_mut().retain(|child| !child.is::<Menu>());
-                    let html = sys_info_as_html();
+                    let html = if let Event::Select(EntryId::ShowOutbox) = evt {
+                        outbox_as_html(&context.outbox)
+                    } else {
+                        sys_info_as_html()
+                    };
                     let r = Reader::from_html(context.fb.rect(), &html, None, &tx, &mut context);
                     let mut next_view = Box::new(r) as Box<dyn View>;
                     transfer_notifications(view.as_mut(), next_view.as_mut(), &mut rq, &mut context);
@@ -715,6 +857,13 @@ fn main() -> Result<(), Error> {
                     let notif = Notification::new(msg, &tx, &mut rq, &mut context);
                     view.children_mut().push(Box::new(notif) as Box<dyn View>);
                 },
+                Event::Device(DeviceEvent::NetUp) if !context.online => {
+                    context.online = true;
+                    if let Some(actions) = context.outbox.start_replay() {
+                        fetch_pool.replay_outbox(&context.client, actions);
+                    }
+                    tx.send(evt).ok();
+                },
                 Event::Device(DeviceEvent::NetUp) |
                 Event::CheckFetcher(..) |
                 Event::FetcherAddDocument(..) |
@@ -33,8 +33,10 @@ cp -R icons dist
 cp -R resources dist
 cp -R fonts dist
 cp -R css dist
+cp -R selectors dist
 find dist/css -name '*-user.css' -delete
 find dist/keyboard-layouts -name '*-user.json' -delete
+find dist/selectors -name '*-user.toml' -delete
 find dist/hyphenation-patterns -name '*.bounds' -delete
 find dist/scripts -name 'wifi-*-*.sh' -delete
 cp target/arm-unknown-linux-gnueabihf/release/ao3reader dist/
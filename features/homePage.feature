@@ -13,7 +13,7 @@ Scenario: [HALF-BROKEN] The user can in to their Ao3 account
     And the user is logged in
     And a log out button is displayed
 
-Scenario: [BROKEN] The user can log out of their Ao3 account
+Scenario: The user can log out of their Ao3 account
     Given a logged in user
     When the user clicks log out
     Then the login info is cleared
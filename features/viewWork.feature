@@ -7,7 +7,7 @@ Scenario: Opening a work displays work info
     And the title is displayed
     And the author is displayed
 
-Scenario: [BROKEN] Opening a large work displays loading
+Scenario: Opening a large work displays loading
     Given a work blurb is onscreen
     And the work is large
     When the user clicks on the work
@@ -36,6 +36,14 @@ Scenario: Repeated kudos are not allowed
     When the user clicks the kudos button
     Then an "Already added kudos" message is displayed
 
+Scenario: Kudos left offline are sent once the network is back
+    Given a multi-page work is opened
+    And the network is down
+    When the user clicks the kudos button
+    Then a "Kudos will be sent" message is displayed
+    When the network comes back up
+    Then a "Sent 1 pending AO3 action" message is displayed
+
 Scenario: Chapter Index jumps to correct page
     Given a multi-chapter work is opened
     When the user clicks on the index button
@@ -52,3 +60,10 @@ Scenario: About Work opens work popup
     And the Fandoms are displayed
     And the Tags are displayed
     And the Summary is displayed
+
+Scenario: Works can be downloaded from the reader
+    Given a multi-page work is opened
+    When the user clicks the download button
+    And the user picks a format
+    Then a progress indicator is displayed
+    And the work is saved to the download folder